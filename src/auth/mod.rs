@@ -5,25 +5,44 @@
  * @link   https://afaan.dev
  * @link   https://github.com/AfaanBilal/bookstore
  */
+use base64::{engine::general_purpose::STANDARD, Engine};
 use jsonwebtoken::{decode, DecodingKey, Validation};
 use rocket::{
     http::Status,
     request::{self, FromRequest, Outcome, Request},
     serde::{Deserialize, Serialize},
 };
+use sea_orm::*;
+use sha2::{Digest, Sha256};
 
+use crate::controllers::error::GuardFailure;
+use crate::entities::{prelude::*, user};
 use crate::AppConfig;
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(crate = "rocket::serde")]
 pub struct Claims {
     pub sub: i32,
+    /// Tokens issued before roles were added have none and act as a `user`.
+    #[serde(default = "default_role")]
     pub role: String,
     pub exp: u64,
 }
 
+fn default_role() -> String {
+    "user".to_string()
+}
+
+#[derive(Clone)]
 pub struct AuthenticatedUser {
     pub id: i32,
+    pub role: String,
+}
+
+impl AuthenticatedUser {
+    pub fn is_admin(&self) -> bool {
+        self.role == "admin"
+    }
 }
 
 #[rocket::async_trait]
@@ -43,13 +62,114 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
             let claims = match data {
                 Ok(p) => p.claims,
                 Err(_) => {
-                    return Outcome::Error((Status::Unauthorized, "Invalid token".to_string()))
+                    GuardFailure::record(req, "Invalid token");
+                    return Outcome::Error((Status::Unauthorized, "Invalid token".to_string()));
                 }
             };
 
-            Outcome::Success(AuthenticatedUser { id: claims.sub })
+            Outcome::Success(AuthenticatedUser {
+                id: claims.sub,
+                role: claims.role,
+            })
         } else {
+            GuardFailure::record(req, "Token absent");
             Outcome::Error((Status::Unauthorized, "Token absent".to_string()))
         }
     }
 }
+
+/// Generates an API key. Only its hash is stored, see [`hash_api_key`].
+pub fn generate_api_key() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+pub fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// A user signed in the ways catalogue readers can: the `token` header, HTTP
+/// Basic with an email and either the account password or an API key, or an
+/// API key alone in the `X-Api-Key` header or `?api_key=` query.
+///
+/// The catalogue is the same for every user, so only the key is kept.
+pub struct ReaderUser {
+    /// The key from `?api_key=`, which links in the response must carry on,
+    /// if it was the credential the user signed in with.
+    pub api_key: Option<String>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ReaderUser {
+    type Error = String;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        if req.headers().contains("token") {
+            return AuthenticatedUser::from_request(req)
+                .await
+                .map(|_| ReaderUser { api_key: None });
+        }
+
+        let db = req.rocket().state::<DatabaseConnection>().unwrap();
+        let basic = req.headers().get_one("Authorization");
+        let header_key = req.headers().get_one("X-Api-Key");
+        let query_key = req.query_value::<&str>("api_key").and_then(Result::ok);
+
+        // Headers win, and a key in the query is then neither checked nor carried on.
+        let api_key = query_key.filter(|_| basic.is_none() && header_key.is_none());
+
+        let found = if let Some(basic) = basic {
+            basic_user(db, basic).await
+        } else if let Some(key) = header_key.or(query_key) {
+            User::find()
+                .filter(user::Column::ApiKey.eq(hash_api_key(key)))
+                .one(db)
+                .await
+                .map_err(|e| e.to_string())
+        } else {
+            GuardFailure::record(req, "Credentials absent");
+            return Outcome::Error((Status::Unauthorized, "Credentials absent".to_string()));
+        };
+
+        match found {
+            Ok(Some(_)) => Outcome::Success(ReaderUser {
+                api_key: api_key.map(str::to_string),
+            }),
+            Ok(None) => {
+                GuardFailure::record(req, "Invalid credentials");
+                Outcome::Error((Status::Unauthorized, "Invalid credentials".to_string()))
+            }
+            Err(e) => {
+                error!("Could not check the credentials of a reader: {}", e);
+                Outcome::Error((Status::InternalServerError, e))
+            }
+        }
+    }
+}
+
+/// The user named by `Authorization: Basic`, if the password is theirs or
+/// their API key. The key is checked first, as it is far cheaper than bcrypt.
+async fn basic_user(db: &DatabaseConnection, header: &str) -> Result<Option<user::Model>, String> {
+    let credentials = header
+        .strip_prefix("Basic ")
+        .and_then(|c| STANDARD.decode(c.trim()).ok())
+        .and_then(|c| String::from_utf8(c).ok());
+
+    let Some((email, password)) = credentials.as_deref().and_then(|c| c.split_once(':')) else {
+        return Ok(None);
+    };
+
+    let u = User::find()
+        .filter(user::Column::Email.eq(email))
+        .one(db)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(u.filter(|u| {
+        u.api_key.as_deref() == Some(hash_api_key(password).as_str())
+            || bcrypt::verify(password, &u.password).unwrap_or(false)
+    }))
+}
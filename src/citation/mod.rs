@@ -0,0 +1,365 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use rocket::http::{Accept, MediaType};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::publication::PublicationDate;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CitationFormat {
+    Bibtex,
+    Ris,
+    CslJson,
+    Apa,
+}
+
+impl CitationFormat {
+    pub const ALL: [CitationFormat; 4] = [
+        CitationFormat::Bibtex,
+        CitationFormat::Ris,
+        CitationFormat::CslJson,
+        CitationFormat::Apa,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CitationFormat::Bibtex => "bibtex",
+            CitationFormat::Ris => "ris",
+            CitationFormat::CslJson => "csl-json",
+            CitationFormat::Apa => "apa",
+        }
+    }
+
+    pub fn media_type(&self) -> MediaType {
+        match self {
+            CitationFormat::Bibtex => MediaType::new("application", "x-bibtex"),
+            CitationFormat::Ris => MediaType::new("application", "x-research-info-systems"),
+            CitationFormat::CslJson => MediaType::new("application", "vnd.citationstyles.csl+json"),
+            CitationFormat::Apa => MediaType::Plain,
+        }
+    }
+
+    /// Picks the format the client prefers from its `Accept` header, BibTeX if
+    /// it accepts anything. `None` if it accepts none of them.
+    pub fn negotiate(accept: Option<&Accept>) -> Option<Self> {
+        let accept = match accept {
+            Some(accept) => accept,
+            None => return Some(CitationFormat::Bibtex),
+        };
+
+        let mut accepted = accept
+            .iter()
+            .filter(|m| m.weight_or(1.0) > 0.0)
+            .collect::<Vec<_>>();
+
+        // Stable, so equally weighted types keep the client's order.
+        accepted.sort_by(|a, b| b.weight_or(1.0).total_cmp(&a.weight_or(1.0)));
+
+        accepted.into_iter().find_map(|m| {
+            let m = m.media_type();
+
+            if m.top() == "*" {
+                return Some(CitationFormat::Bibtex);
+            }
+
+            Self::ALL.into_iter().find(|f| {
+                let own = f.media_type();
+                m.top() == own.top() && (m.sub() == "*" || m.sub() == own.sub())
+            })
+        })
+    }
+
+    /// `books` in this format: one entry each, or a CSL-JSON array of them.
+    pub fn render(&self, books: &[Citation]) -> String {
+        match self {
+            CitationFormat::Bibtex => bibtex(books),
+            CitationFormat::Ris => books.iter().map(ris).collect(),
+            CitationFormat::CslJson => {
+                Value::Array(books.iter().map(csl_json).collect()).to_string()
+            }
+            CitationFormat::Apa => books.iter().map(|b| apa(b) + "\n").collect(),
+        }
+    }
+}
+
+/// What a book is cited by.
+pub struct Citation {
+    pub id: i32,
+    pub title: String,
+    /// Blank for authors known by a single name.
+    pub author_given: String,
+    pub author_family: String,
+    pub published: Option<PublicationDate>,
+    pub publisher: Option<String>,
+    pub edition: Option<String>,
+    pub isbn: Option<String>,
+    pub pages: Option<i32>,
+}
+
+impl Citation {
+    /// The author as "Family, Given", or the single name.
+    fn author_inverted(&self) -> String {
+        match self.author_given.as_str() {
+            "" => self.author_family.clone(),
+            given => format!("{}, {}", self.author_family, given),
+        }
+    }
+
+    /// A key like `leguin1968wizard`, from the author, year and the first word
+    /// of the title other than an article.
+    fn key(&self) -> String {
+        let word = self
+            .title
+            .split_whitespace()
+            .map(ascii_lowercase)
+            .find(|w| !w.is_empty() && !["a", "an", "the"].contains(&w.as_str()))
+            .unwrap_or_default();
+
+        let key = format!(
+            "{}{}{}",
+            ascii_lowercase(&self.author_family),
+            self.published.map_or(String::new(), |p| p.year.to_string()),
+            word
+        );
+
+        if key.is_empty() {
+            format!("book{}", self.id)
+        } else {
+            key
+        }
+    }
+}
+
+fn ascii_lowercase(s: &str) -> String {
+    s.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// BibTeX `@book` entries. Repeated keys get the book's ID, as in
+/// `leguin1968wizard-12`.
+fn bibtex(books: &[Citation]) -> String {
+    let mut seen = HashSet::new();
+    let mut out = String::new();
+
+    for book in books {
+        let mut key = book.key();
+
+        if !seen.insert(key.clone()) {
+            key = format!("{}-{}", key, book.id);
+        }
+
+        if !out.is_empty() {
+            out.push('\n');
+        }
+
+        let _ = writeln!(out, "@book{{{},", key);
+        let _ = writeln!(
+            out,
+            "  author = {{{}}},",
+            bibtex_escape(&book.author_inverted())
+        );
+        let _ = writeln!(out, "  title = {{{}}},", bibtex_escape(&book.title));
+
+        if let Some(published) = book.published {
+            let _ = writeln!(out, "  year = {{{}}},", published.year);
+
+            if let Some(month) = published.month {
+                let _ = writeln!(out, "  month = {},", MONTHS[month as usize - 1]);
+            }
+        }
+
+        for (field, value) in [
+            ("publisher", &book.publisher),
+            ("edition", &book.edition),
+            ("isbn", &book.isbn),
+        ] {
+            if let Some(value) = value {
+                let _ = writeln!(out, "  {} = {{{}}},", field, bibtex_escape(value));
+            }
+        }
+
+        if let Some(pages) = book.pages {
+            let _ = writeln!(out, "  pagetotal = {{{}}},", pages);
+        }
+
+        out.push_str("}\n");
+    }
+
+    out
+}
+
+fn bibtex_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '\\' => out.push_str("\\textbackslash{}"),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// A RIS record. Lines end in CRLF, as the format asks.
+fn ris(book: &Citation) -> String {
+    let mut lines = vec![
+        ("TY", "BOOK".to_string()),
+        ("AU", book.author_inverted()),
+        ("TI", book.title.clone()),
+    ];
+
+    if let Some(p) = book.published {
+        lines.push(("PY", p.year.to_string()));
+
+        if let Some(month) = p.month {
+            let day = p.day.map_or(String::new(), |d| format!("{:02}", d));
+            lines.push(("DA", format!("{:04}/{:02}/{}/", p.year, month, day)));
+        }
+    }
+
+    for (tag, value) in [
+        ("PB", &book.publisher),
+        ("ET", &book.edition),
+        ("SN", &book.isbn),
+    ] {
+        if let Some(value) = value {
+            lines.push((tag, value.clone()));
+        }
+    }
+
+    lines.push(("ER", String::new()));
+
+    lines
+        .into_iter()
+        .map(|(tag, value)| format!("{}  - {}\r\n", tag, value))
+        .collect()
+}
+
+fn csl_json(book: &Citation) -> Value {
+    let author = match book.author_given.as_str() {
+        "" => json!({ "family": book.author_family }),
+        given => json!({ "family": book.author_family, "given": given }),
+    };
+
+    let mut item = json!({
+        "id": format!("book-{}", book.id),
+        "type": "book",
+        "title": book.title,
+        "author": [author],
+    });
+
+    if let Some(p) = book.published {
+        let parts = [Some(p.year as u32), p.month, p.day]
+            .into_iter()
+            .map_while(|part| part)
+            .collect::<Vec<_>>();
+
+        item["issued"] = json!({ "date-parts": [parts] });
+    }
+
+    for (field, value) in [
+        ("publisher", &book.publisher),
+        ("edition", &book.edition),
+        ("ISBN", &book.isbn),
+    ] {
+        if let Some(value) = value {
+            item[field] = json!(value);
+        }
+    }
+
+    if let Some(pages) = book.pages {
+        item["number-of-pages"] = json!(pages);
+    }
+
+    item
+}
+
+/// An APA (7th edition) reference, as plain text without the italics.
+fn apa(book: &Citation) -> String {
+    let author = match book.author_given.as_str() {
+        "" => format!("{}.", book.author_family.trim_end_matches('.')),
+        given => format!("{}, {}", book.author_family, initials(given)),
+    };
+
+    let year = book
+        .published
+        .map_or("n.d.".to_string(), |p| p.year.to_string());
+
+    let mut out = format!("{} ({}). {}", author, year, book.title);
+
+    if let Some(edition) = book.edition.as_deref().and_then(apa_edition) {
+        let _ = write!(out, " ({})", edition);
+    }
+
+    if !out.ends_with(['.', '?', '!']) {
+        out.push('.');
+    }
+
+    if let Some(publisher) = &book.publisher {
+        let _ = write!(out, " {}.", publisher.trim_end_matches('.'));
+    }
+
+    out
+}
+
+/// "Ursula K." as "U. K.", "Jean-Paul" as "J.-P.".
+fn initials(given: &str) -> String {
+    given
+        .split_whitespace()
+        .map(|name| {
+            name.split('-')
+                .filter_map(|part| part.chars().next())
+                .map(|c| format!("{}.", c))
+                .collect::<Vec<_>>()
+                .join("-")
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A numbered edition as "2nd ed.", leaving out the first. Others as given.
+fn apa_edition(edition: &str) -> Option<String> {
+    let edition = edition.trim();
+    let digits = edition.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+
+    match digits.parse::<u32>() {
+        Ok(1) => None,
+        Ok(n) => {
+            let suffix = match (n % 10, n % 100) {
+                (_, 11..=13) => "th",
+                (1, _) => "st",
+                (2, _) => "nd",
+                (3, _) => "rd",
+                _ => "th",
+            };
+
+            Some(format!("{}{} ed.", n, suffix))
+        }
+        Err(_) if edition.is_empty() => None,
+        Err(_) => Some(edition.to_string()),
+    }
+}
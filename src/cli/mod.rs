@@ -0,0 +1,255 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use sea_orm::*;
+use std::io::Write;
+
+use crate::auth::AuthenticatedUser;
+use crate::entities::{prelude::*, sea_orm_active_enums::ImportFormat, user};
+use crate::imports::{self, Progress, Summary};
+use crate::marc;
+use crate::openlibrary;
+use crate::AppConfig;
+
+const USAGE: &str = "Usage:
+    bookstore                      Serve the API.
+    bookstore import <file> --user <email> [--format csv|jsonl|onix|marc] [--dry-run]
+                                   Import books from a CSV, JSON Lines, ONIX or MARC file.
+    bookstore convert <file> [--to marc21|marcxml] [--output <file>]
+                                   Convert MARC records between MARC21 and MARCXML,
+                                   by default to the one the file is not in.
+    bookstore openlibrary <file>...
+                                   Load Open Library works, authors and editions dumps,
+                                   optionally gzipped, for enriching books.";
+
+/// Runs the commands given by `args` that need neither the config nor the
+/// database, returning the exit code, or `None` for serving and the commands
+/// that need them.
+pub fn run_offline(args: &[String]) -> Option<i32> {
+    let result = match args.first().map(String::as_str) {
+        None | Some("import" | "openlibrary") => return None,
+        Some("convert") => convert(&args[1..]),
+        Some("help" | "--help" | "-h") => {
+            println!("{}", USAGE);
+            return Some(0);
+        }
+        _ => Err(format!("Unknown command.\n\n{}", USAGE)),
+    };
+
+    Some(exit_code(result))
+}
+
+/// Runs the command given by `args`, returning the exit code.
+pub async fn run(db: &DatabaseConnection, config: &AppConfig, args: &[String]) -> i32 {
+    let result = match args.first().map(String::as_str) {
+        Some("import") => import(db, config, &args[1..]).await,
+        Some("openlibrary") => load_open_library(db, &args[1..]).await,
+        _ => Err(format!("Unknown command.\n\n{}", USAGE)),
+    };
+
+    exit_code(result)
+}
+
+fn exit_code(result: Result<(), String>) -> i32 {
+    match result {
+        Ok(()) => 0,
+        Err(message) => {
+            eprintln!("{}", message);
+            1
+        }
+    }
+}
+
+/// Prints how far the import got.
+struct Printer {
+    total: usize,
+}
+
+#[rocket::async_trait]
+impl Progress for Printer {
+    async fn report(&self, summary: &Summary) {
+        eprintln!("Processed {} of {} rows.", summary.processed, self.total);
+    }
+}
+
+async fn import(
+    db: &DatabaseConnection,
+    config: &AppConfig,
+    args: &[String],
+) -> Result<(), String> {
+    let mut file = None;
+    let mut format = None;
+    let mut email = None;
+    let mut dry_run = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                let name = args.next().ok_or("--format needs a value.")?;
+                format = Some(
+                    ImportFormat::from_name(name)
+                        .ok_or("The format must be csv, jsonl, onix or marc.")?,
+                );
+            }
+            "--user" => email = Some(args.next().ok_or("--user needs a value.")?),
+            "--dry-run" => dry_run = true,
+            _ if file.is_none() && !arg.starts_with("--") => file = Some(arg),
+            _ => return Err(format!("Unexpected argument {}.\n\n{}", arg, USAGE)),
+        }
+    }
+
+    let file = file.ok_or(format!("No file given.\n\n{}", USAGE))?;
+    let email = email.ok_or(format!("No user given.\n\n{}", USAGE))?;
+
+    let format = match format {
+        Some(format) => format,
+        None if file.ends_with(".csv") => ImportFormat::Csv,
+        None if file.ends_with(".jsonl") || file.ends_with(".ndjson") => ImportFormat::Jsonl,
+        None if file.ends_with(".xml") || file.ends_with(".onix") => ImportFormat::Onix,
+        None if file.ends_with(".mrc") || file.ends_with(".marc") => ImportFormat::Marc,
+        None => return Err("Set --format, as it can't be told from the file name.".to_string()),
+    };
+
+    let user = User::find()
+        .filter(user::Column::Email.eq(email.as_str()))
+        .one(db)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or(format!("No user found with the email {}.", email))?;
+
+    let body = std::fs::read(file).map_err(|e| format!("Could not read {}: {}", file, e))?;
+    let rows = imports::parse(&format, &body)?;
+
+    let user = AuthenticatedUser {
+        id: user.id,
+        role: user.role,
+    };
+
+    let progress = Printer { total: rows.len() };
+
+    let summary = imports::run(db, config, &user, rows, dry_run, &progress)
+        .await
+        .map_err(|e| format!("The import failed: {}", e))?;
+
+    println!(
+        "{}{} rows, {} failed. {} books created, {} books updated, {} authors created, {} authors matched.",
+        if dry_run { "Dry run: " } else { "" },
+        summary.processed,
+        summary.failed,
+        summary.books_created,
+        summary.books_updated,
+        summary.authors_created,
+        summary.authors_matched,
+    );
+
+    if !summary.errors.is_empty() {
+        let report = imports::error_report(&summary.errors).map_err(|e| e.to_string())?;
+        std::io::stderr()
+            .write_all(&report)
+            .map_err(|e| e.to_string())?;
+
+        return Err(format!("{} rows could not be imported.", summary.failed));
+    }
+
+    Ok(())
+}
+
+fn convert(args: &[String]) -> Result<(), String> {
+    let mut file = None;
+    let mut to = None;
+    let mut output = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--to" => match args.next().map(String::as_str) {
+                Some("marc21") => to = Some(false),
+                Some("marcxml") => to = Some(true),
+                _ => return Err("--to must be marc21 or marcxml.".to_string()),
+            },
+            "--output" => output = Some(args.next().ok_or("--output needs a value.")?),
+            _ if file.is_none() && !arg.starts_with("--") => file = Some(arg),
+            _ => return Err(format!("Unexpected argument {}.\n\n{}", arg, USAGE)),
+        }
+    }
+
+    let file = file.ok_or(format!("No file given.\n\n{}", USAGE))?;
+    let body = std::fs::read(file).map_err(|e| format!("Could not read {}: {}", file, e))?;
+    let to_xml = to.unwrap_or(!marc::is_xml(&body));
+
+    let mut out = vec![];
+    let mut failed = 0;
+
+    if to_xml {
+        out.extend(marc::header().into_bytes());
+    }
+
+    for (position, record) in marc::parse(&body)? {
+        let converted = record.and_then(|r| match to_xml {
+            true => Ok(r.to_xml().into_bytes()),
+            false => r.to_binary(),
+        });
+
+        match converted {
+            Ok(bytes) => out.extend(bytes),
+            Err(message) => {
+                failed += 1;
+                eprintln!("Record at {}: {}", position, message);
+            }
+        }
+    }
+
+    if to_xml {
+        out.extend(marc::footer().into_bytes());
+    }
+
+    match output {
+        Some(path) => std::fs::write(path, &out),
+        None => std::io::stdout().write_all(&out),
+    }
+    .map_err(|e| format!("Could not write the records: {}", e))?;
+
+    if failed > 0 {
+        return Err(format!("{} records could not be converted.", failed));
+    }
+
+    Ok(())
+}
+
+async fn load_open_library(db: &DatabaseConnection, files: &[String]) -> Result<(), String> {
+    if files.is_empty() {
+        return Err(format!("No file given.\n\n{}", USAGE));
+    }
+
+    let mut failed = 0;
+
+    for file in files {
+        let input = openlibrary::open(file)?;
+
+        let summary = openlibrary::load(
+            db,
+            input,
+            |line, message| eprintln!("{} line {}: {}", file, line, message),
+            |summary| eprintln!("{}: read {} lines.", file, summary.lines),
+        )
+        .await?;
+
+        println!(
+            "{}: {} lines, {} failed. {} editions, {} works, {} authors loaded.",
+            file, summary.lines, summary.failed, summary.editions, summary.works, summary.authors,
+        );
+
+        failed += summary.failed;
+    }
+
+    if failed > 0 {
+        return Err(format!("{} lines could not be loaded.", failed));
+    }
+
+    Ok(())
+}
@@ -5,10 +5,11 @@
  * @link   https://afaan.dev
  * @link   https://github.com/AfaanBilal/bookstore
  */
-use super::{Response, SuccessResponse};
-use crate::auth::{AuthenticatedUser, Claims};
+use super::{error::Problem, Response, SuccessResponse};
+use crate::auth::{generate_api_key, hash_api_key, AuthenticatedUser, Claims};
 use crate::controllers::ErrorResponse;
 use crate::entities::{prelude::*, user};
+use crate::idempotency::{Idempotency, Idempotent};
 use crate::AppConfig;
 use bcrypt::{hash, verify, DEFAULT_COST};
 use jsonwebtoken::{encode, EncodingKey, Header};
@@ -17,22 +18,34 @@ use rocket::{
     serde::{json::Json, Deserialize, Serialize},
     State,
 };
-use sea_orm::*;
+use sea_orm::{sea_query::Expr, *};
 use std::time::SystemTime;
+use utoipa::ToSchema;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(crate = "rocket::serde")]
 pub struct ReqSignIn {
     email: String,
     password: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 #[serde(crate = "rocket::serde")]
 pub struct ResSignIn {
     token: String,
 }
 
+/// Exchanges credentials for a token.
+#[utoipa::path(
+    context_path = "/v1/auth",
+    tag = "auth",
+    request_body = ReqSignIn,
+    responses(
+        (status = 200, body = ResSignIn),
+        (status = 401, response = Problem),
+        (status = 422, response = Problem)
+    )
+)]
 #[post("/sign-in", data = "<req_sign_in>")]
 pub async fn sign_in(
     db: &State<DatabaseConnection>,
@@ -49,23 +62,23 @@ pub async fn sign_in(
     {
         Some(u) => u,
         None => {
-            return Err(ErrorResponse((
+            return Err(ErrorResponse::new(
                 Status::Unauthorized,
                 "Invalid credentials".to_string(),
-            )))
+            ))
         }
     };
 
     if !verify(&req_sign_in.password, &u.password).unwrap() {
-        return Err(ErrorResponse((
+        return Err(ErrorResponse::new(
             Status::Unauthorized,
             "Invalid credentials".to_string(),
-        )));
+        ));
     }
 
     let claims = Claims {
         sub: u.id,
-        role: "user".to_string(),
+        role: u.role,
         exp: SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap()
@@ -83,7 +96,7 @@ pub async fn sign_in(
     Ok(SuccessResponse((Status::Ok, Json(ResSignIn { token }))))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(crate = "rocket::serde")]
 pub struct ReqSignUp {
     email: String,
@@ -92,10 +105,24 @@ pub struct ReqSignUp {
     lastname: Option<String>,
 }
 
+/// Creates an account.
+#[utoipa::path(
+    context_path = "/v1/auth",
+    tag = "auth",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response replayed.")
+    ),
+    request_body = ReqSignUp,
+    responses(
+        (status = 201, body = String),
+        (status = 409, response = Problem),
+        (status = 422, response = Problem)
+    )
+)]
 #[post("/sign-up", data = "<req_sign_up>")]
 pub async fn sign_up(
     db: &State<DatabaseConnection>,
-    req_sign_up: Json<ReqSignUp>,
+    req_sign_up: Idempotent<ReqSignUp>,
 ) -> Response<String> {
     let db = db as &DatabaseConnection;
 
@@ -105,10 +132,10 @@ pub async fn sign_up(
         .await?
         .is_some()
     {
-        return Err(ErrorResponse((
-            Status::UnprocessableEntity,
-            "An account exists with that email address.".to_string(),
-        )));
+        return Err(ErrorResponse::field(
+            "email",
+            "An account exists with that email address.",
+        ));
     }
 
     User::insert(user::ActiveModel {
@@ -127,7 +154,7 @@ pub async fn sign_up(
     )))
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 #[serde(crate = "rocket::serde")]
 pub struct ResMe {
     id: i32,
@@ -136,6 +163,35 @@ pub struct ResMe {
     lastname: Option<String>,
 }
 
+/// Another user, as embedded in books and authors.
+#[derive(Serialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct ResUser {
+    pub id: i32,
+    pub firstname: Option<String>,
+    pub lastname: Option<String>,
+}
+
+impl From<&user::Model> for ResUser {
+    fn from(u: &user::Model) -> Self {
+        Self {
+            id: u.id,
+            firstname: u.firstname.to_owned(),
+            lastname: u.lastname.to_owned(),
+        }
+    }
+}
+
+/// The signed-in user.
+#[utoipa::path(
+    context_path = "/v1/auth",
+    tag = "auth",
+    responses(
+        (status = 200, body = ResMe),
+        (status = 401, response = Problem)
+    ),
+    security(("token" = []))
+)]
 #[get("/me")]
 pub async fn me(db: &State<DatabaseConnection>, user: AuthenticatedUser) -> Response<Json<ResMe>> {
     let db = db as &DatabaseConnection;
@@ -152,3 +208,74 @@ pub async fn me(db: &State<DatabaseConnection>, user: AuthenticatedUser) -> Resp
         }),
     )))
 }
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct ResApiKey {
+    api_key: String,
+}
+
+/// Issues the signed-in user an API key for catalogue readers, replacing any
+/// earlier one. The key is only shown this once.
+#[utoipa::path(
+    context_path = "/v1/api-key",
+    tag = "auth",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response replayed.")
+    ),
+    responses(
+        (status = 201, body = ResApiKey),
+        (status = 401, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[post("/")]
+pub async fn create_api_key(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    _idempotency: Idempotency,
+) -> Response<Json<ResApiKey>> {
+    let db = db as &DatabaseConnection;
+
+    let api_key = generate_api_key();
+
+    User::update_many()
+        .col_expr(user::Column::ApiKey, Expr::value(hash_api_key(&api_key)))
+        .filter(user::Column::Id.eq(user.id))
+        .exec(db)
+        .await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResApiKey { api_key }),
+    )))
+}
+
+/// Revokes the signed-in user's API key.
+#[utoipa::path(
+    context_path = "/v1/api-key",
+    tag = "auth",
+    responses(
+        (status = 200, body = String),
+        (status = 401, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[delete("/")]
+pub async fn revoke_api_key(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+) -> Response<String> {
+    let db = db as &DatabaseConnection;
+
+    User::update_many()
+        .col_expr(user::Column::ApiKey, Expr::value(Option::<String>::None))
+        .filter(user::Column::Id.eq(user.id))
+        .exec(db)
+        .await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        "API key revoked.".to_string(),
+    )))
+}
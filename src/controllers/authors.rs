@@ -5,28 +5,68 @@
  * @link   https://afaan.dev
  * @link   https://github.com/AfaanBilal/bookstore
  */
+use async_graphql::InputObject;
 use rocket::{
-    http::Status,
+    http::{uri::Origin, Status},
     serde::{json::Json, Deserialize, Serialize},
     State,
 };
-use sea_orm::{prelude::DateTimeUtc, *};
-use std::time::SystemTime;
+use sea_orm::{
+    prelude::DateTimeUtc,
+    sea_query::{Expr, Query},
+    *,
+};
+use serde_json::{json, Value};
+use std::{
+    collections::{HashMap, HashSet},
+    time::SystemTime,
+};
+use utoipa::ToSchema;
 
 use super::{
+    auth::ResUser,
     books::{ResBook, ResBookList},
+    bulk::{Applied, BulkMode, Operation, ResBulk},
+    conditional::{guarded, precondition_failed, Preconditions, Tagged, Versioned},
+    embed::{self, Embed},
+    error::Problem,
+    include_deleted as check_include_deleted,
+    patch::ReqPatch,
     ErrorResponse, Response, SuccessResponse,
 };
-use crate::entities::{author, prelude::*};
-use crate::{auth::AuthenticatedUser, entities::book};
+use crate::entities::{
+    author, author_alias,
+    prelude::*,
+    sea_orm_active_enums::{AliasKind, RevisionAction},
+};
+use crate::idempotency::{Idempotency, Idempotent};
+use crate::names;
+use crate::publication::{ParsePublicationDateError, PublicationDate};
+use crate::revisions::AuthorWithAliases;
+use crate::validation::{Rule, Validate, Validator, MAX_STRING_LENGTH};
+use crate::{auth::AuthenticatedUser, entities::book, revisions, AppConfig};
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(crate = "rocket::serde")]
 pub struct ResAuthor {
-    id: i32,
-    firstname: String,
-    lastname: String,
-    bio: String,
+    pub id: i32,
+    /// Blank for authors known by a single name.
+    pub firstname: String,
+    pub lastname: String,
+    /// How the author files in lists sorted by name, e.g. `Le Guin, Ursula K.`.
+    pub sort_name: String,
+    pub bio: String,
+    pub born: Option<String>,
+    pub died: Option<String>,
+    pub nationality: Option<String>,
+    pub website: Option<String>,
+    pub photo: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub deleted_at: Option<DateTimeUtc>,
+    /// The author this one was merged into, for merged authors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merged_into_id: Option<i32>,
 }
 
 impl From<&author::Model> for ResAuthor {
@@ -35,176 +75,1227 @@ impl From<&author::Model> for ResAuthor {
             id: a.id,
             firstname: a.firstname.to_owned(),
             lastname: a.lastname.to_owned(),
+            sort_name: a.sort_name.to_owned(),
             bio: a.bio.to_owned(),
+            born: PublicationDate::from_columns(a.born_on, a.born_precision.as_ref())
+                .map(|d| d.to_string()),
+            died: PublicationDate::from_columns(a.died_on, a.died_precision.as_ref())
+                .map(|d| d.to_string()),
+            nationality: a.nationality.to_owned(),
+            website: a.website.to_owned(),
+            photo: a.photo.to_owned(),
+            deleted_at: a.deleted_at,
+            merged_into_id: a.merged_into_id,
         }
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(crate = "rocket::serde")]
 pub struct ResAuthorList {
     total: usize,
     authors: Vec<ResAuthor>,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(crate = "rocket::serde")]
+pub struct ResAlias {
+    pub name: String,
+    /// `alias` or `pen_name`.
+    pub kind: String,
+}
+
+impl From<&author_alias::Model> for ResAlias {
+    fn from(a: &author_alias::Model) -> Self {
+        Self {
+            name: a.name.to_owned(),
+            kind: a.kind.to_value(),
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize, ToSchema, InputObject)]
+#[serde(crate = "rocket::serde")]
+#[graphql(name = "AuthorInput")]
 pub struct ReqAuthor {
-    firstname: String,
-    lastname: String,
-    bio: String,
+    /// Left blank for authors known by a single name, e.g. Homer.
+    #[serde(default)]
+    pub(crate) firstname: String,
+    /// The surname, or the single name.
+    pub(crate) lastname: String,
+    pub(crate) bio: String,
+    /// Defaults to `lastname, firstname`, or the single name.
+    #[serde(default)]
+    pub(crate) sort_name: Option<String>,
+    /// `YYYY`, `YYYY-MM` or `YYYY-MM-DD`.
+    #[serde(default)]
+    pub(crate) born: Option<String>,
+    /// `YYYY`, `YYYY-MM` or `YYYY-MM-DD`.
+    #[serde(default)]
+    pub(crate) died: Option<String>,
+    #[serde(default)]
+    pub(crate) nationality: Option<String>,
+    #[serde(default)]
+    pub(crate) website: Option<String>,
+    /// The URL of a photo of the author.
+    #[serde(default)]
+    pub(crate) photo: Option<String>,
+    /// Replaces the author's aliases and pen names when given.
+    #[serde(default)]
+    pub(crate) aliases: Option<Vec<ReqAlias>>,
+}
+
+#[derive(Clone, Serialize, Deserialize, ToSchema, InputObject)]
+#[serde(crate = "rocket::serde")]
+#[graphql(name = "AliasInput")]
+pub struct ReqAlias {
+    pub(crate) name: String,
+    /// `alias`, the default, or `pen_name`.
+    #[serde(default)]
+    pub(crate) kind: Option<String>,
 }
 
-#[get("/")]
+impl ReqAlias {
+    fn kind(&self) -> Option<AliasKind> {
+        match self.kind.as_deref().map(str::trim) {
+            None | Some("") => Some(AliasKind::Alias),
+            Some(k) => AliasKind::try_from_value(&k.to_string()).ok(),
+        }
+    }
+}
+
+impl From<&author::Model> for ReqAuthor {
+    fn from(a: &author::Model) -> Self {
+        let res = ResAuthor::from(a);
+
+        Self {
+            sort_name: (res.sort_name != sort_name(&res.firstname, &res.lastname))
+                .then_some(res.sort_name),
+            firstname: res.firstname,
+            lastname: res.lastname,
+            bio: res.bio,
+            born: res.born,
+            died: res.died,
+            nationality: res.nationality,
+            website: res.website,
+            photo: res.photo,
+            aliases: None,
+        }
+    }
+}
+
+/// The sort name of an author named so, cut to fit the column.
+fn sort_name(firstname: &str, lastname: &str) -> String {
+    names::sort_name(firstname, lastname)
+        .chars()
+        .take(MAX_STRING_LENGTH)
+        .collect()
+}
+
+impl ReqAuthor {
+    fn born(&self) -> Result<Option<PublicationDate>, ParsePublicationDateError> {
+        Self::date(&self.born)
+    }
+
+    fn died(&self) -> Result<Option<PublicationDate>, ParsePublicationDateError> {
+        Self::date(&self.died)
+    }
+
+    fn date(value: &Option<String>) -> Result<Option<PublicationDate>, ParsePublicationDateError> {
+        match value.as_deref().map(str::trim) {
+            None | Some("") => Ok(None),
+            Some(d) => d.parse().map(Some),
+        }
+    }
+
+    /// The sort name given, or else the one the names make.
+    fn sort_name(&self) -> String {
+        Self::text(&self.sort_name).unwrap_or_else(|| sort_name(&self.firstname, &self.lastname))
+    }
+
+    /// Blank optional text is stored as `NULL`.
+    fn text(value: &Option<String>) -> Option<String> {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_owned)
+    }
+}
+
+impl Validate for ReqAuthor {
+    fn rules(&self, v: &mut Validator) {
+        let name = [Rule::Required, Rule::MaxLength(MAX_STRING_LENGTH)];
+        let text = [Rule::MaxLength(MAX_STRING_LENGTH)];
+        let url = [Rule::MaxLength(MAX_STRING_LENGTH), Rule::Url];
+
+        v.field("firstname", &self.firstname, &text)
+            .field("lastname", &self.lastname, &name)
+            .field("bio", &self.bio, &text)
+            .field("sort_name", self.sort_name.as_deref().unwrap_or(""), &text)
+            .field(
+                "nationality",
+                self.nationality.as_deref().unwrap_or(""),
+                &text,
+            )
+            .field("website", self.website.as_deref().unwrap_or(""), &url)
+            .field("photo", self.photo.as_deref().unwrap_or(""), &url);
+
+        if let Err(e) = self.born() {
+            v.error("born", format!("Invalid date: {}.", e));
+        }
+
+        if let Err(e) = self.died() {
+            v.error("died", format!("Invalid date: {}.", e));
+        }
+
+        if let (Ok(Some(born)), Ok(Some(died))) = (self.born(), self.died()) {
+            if died.last_day() < born.first_day() {
+                v.error("died", "Must not be before `born`.");
+            }
+        }
+
+        let mut seen = HashSet::new();
+
+        for (i, alias) in self.aliases.iter().flatten().enumerate() {
+            let field = format!("aliases[{}]", i);
+
+            v.field(&format!("{}.name", field), &alias.name, &name);
+
+            if !alias.name.trim().is_empty() && !seen.insert(alias.name.trim().to_lowercase()) {
+                v.error(&format!("{}.name", field), "Listed more than once.");
+            }
+
+            if alias.kind().is_none() {
+                v.error(
+                    &format!("{}.kind", field),
+                    "Expected `alias` or `pen_name`.",
+                );
+            }
+        }
+    }
+}
+
+/// Finds an author by ID. Soft-deleted authors are only found if `include_deleted` is set.
+pub(super) async fn find_author<C: ConnectionTrait>(
+    db: &C,
+    id: i32,
+    include_deleted: bool,
+) -> Result<author::Model, ErrorResponse> {
+    let mut query = Author::find_by_id(id);
+
+    if !include_deleted {
+        query = query.filter(author::Column::DeletedAt.is_null());
+    }
+
+    match query.one(db).await? {
+        Some(a) => Ok(a),
+        None => Err(ErrorResponse::new(
+            Status::NotFound,
+            "No author found with the specified ID.".to_string(),
+        )),
+    }
+}
+
+/// Answers the 404 of [`find_author`] for an author merged into another with a
+/// 308 to the same path of the author they were merged into.
+async fn follow_merge<C: ConnectionTrait>(
+    db: &C,
+    id: i32,
+    uri: &Origin<'_>,
+    not_found: ErrorResponse,
+) -> ErrorResponse {
+    let survivor = match Author::find_by_id(id).one(db).await {
+        Ok(author) => author.and_then(|a| a.merged_into_id),
+        Err(e) => return e.into(),
+    };
+
+    let Some(survivor) = survivor else {
+        return not_found;
+    };
+
+    let path = uri.path().as_str();
+    let segment = format!("/authors/{}", id);
+
+    let location = match path.find(&segment) {
+        Some(at) => format!(
+            "{}/authors/{}{}",
+            &path[..at],
+            survivor,
+            &path[at + segment.len()..]
+        ),
+        None => path.to_string(),
+    };
+
+    let location = match uri.query() {
+        Some(query) => format!("{}?{}", location, query),
+        None => location,
+    };
+
+    ErrorResponse::moved(
+        location,
+        format!("The author was merged into the author {}.", survivor),
+    )
+}
+
+/// One operation of `POST /authors/bulk`.
+#[derive(Deserialize, ToSchema)]
+#[serde(crate = "rocket::serde", tag = "op", rename_all = "snake_case")]
+pub enum AuthorOperation {
+    Create {
+        data: ReqAuthor,
+    },
+    Update {
+        id: i32,
+        data: ReqAuthor,
+        /// Works as the `If-Match` header.
+        if_match: Option<String>,
+    },
+    /// `strategy` and `reassign_to` work as on `DELETE /authors/{id}`.
+    Delete {
+        id: i32,
+        strategy: Option<String>,
+        reassign_to: Option<i32>,
+        /// Works as the `If-Match` header.
+        if_match: Option<String>,
+    },
+}
+
+#[rocket::async_trait]
+impl Operation for AuthorOperation {
+    type Output = ResAuthor;
+
+    async fn apply(
+        self,
+        txn: &DatabaseTransaction,
+        config: &AppConfig,
+        user: &AuthenticatedUser,
+    ) -> Result<Applied<ResAuthor>, ErrorResponse> {
+        match self {
+            AuthorOperation::Create { data } => {
+                let author = insert(txn, user, &data).await?;
+
+                Ok(Applied {
+                    status: Status::Created,
+                    id: author.id,
+                    data: Some(ResAuthor::from(&author)),
+                })
+            }
+            AuthorOperation::Update { id, data, if_match } => {
+                let before = find_author(txn, id, false).await?;
+                let preconditions = Preconditions::if_match(if_match);
+                let author = save(txn, config, user, &preconditions, before, &data).await?;
+
+                Ok(Applied {
+                    status: Status::Ok,
+                    id,
+                    data: Some(ResAuthor::from(&author)),
+                })
+            }
+            AuthorOperation::Delete {
+                id,
+                strategy,
+                reassign_to,
+                if_match,
+            } => {
+                let preconditions = Preconditions::if_match(if_match);
+                remove(
+                    txn,
+                    config,
+                    user,
+                    &preconditions,
+                    id,
+                    strategy.as_deref(),
+                    reassign_to,
+                )
+                .await?;
+
+                Ok(Applied {
+                    status: Status::Ok,
+                    id,
+                    data: None,
+                })
+            }
+        }
+    }
+}
+
+/// Relations `?include=` may name on authors.
+const RELATIONS: [&str; 3] = ["aliases", "books", "user"];
+
+/// The related records `?include=` asked for, loaded for a page of authors.
+#[derive(Default)]
+struct Included {
+    aliases: HashMap<i32, Vec<author_alias::Model>>,
+    books: HashMap<i32, Vec<book::Model>>,
+    users: HashMap<i32, ResUser>,
+}
+
+impl Included {
+    async fn load(
+        db: &DatabaseConnection,
+        embed: &Embed<'_>,
+        authors: &[author::Model],
+    ) -> Result<Self, DbErr> {
+        let mut included = Self::default();
+
+        if embed.includes("aliases") {
+            included.aliases = embed::aliases_by_author(db, authors.iter().map(|a| a.id)).await?;
+        }
+
+        if embed.includes("books") {
+            included.books = embed::books_by_author(db, authors.iter().map(|a| a.id)).await?;
+        }
+
+        if embed.includes("user") {
+            included.users = embed::users(db, authors.iter().map(|a| a.user_id)).await?;
+        }
+
+        Ok(included)
+    }
+}
+
+/// Renders an author with whichever of their aliases, live books and user were included.
+fn embed_author(
+    embed: &Embed,
+    author: &author::Model,
+    included: &Included,
+) -> Result<Value, ErrorResponse> {
+    let mut res = embed.select("author", &ResAuthor::from(author))?;
+
+    if embed.includes("aliases") {
+        let aliases = included
+            .aliases
+            .get(&author.id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(|a| json!(ResAlias::from(a)))
+            .collect::<Vec<_>>();
+        res.insert("aliases".to_string(), aliases.into());
+    }
+
+    if embed.includes("books") {
+        let books = included
+            .books
+            .get(&author.id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(|b| embed.select("book", &ResBook::from(b)).map(Value::Object))
+            .collect::<Result<Vec<_>, _>>()?;
+        res.insert("books".to_string(), books.into());
+    }
+
+    if embed.includes("user") {
+        let user = included
+            .users
+            .get(&author.user_id)
+            .map(|u| embed.select("user", u))
+            .transpose()?;
+        res.insert("user".to_string(), user.into());
+    }
+
+    Ok(Value::Object(res))
+}
+
+/// Lists authors.
+///
+/// `q` finds authors by any of their names, aliases and pen names included,
+/// and `sort=name` lists them by sort name rather than most recently updated
+/// first. `include` embeds each author's `aliases`, live `books` and `user`,
+/// and `fields[author]`, `fields[book]` and `fields[user]` limit the fields
+/// returned.
+#[utoipa::path(
+    context_path = "/v1/authors",
+    tag = "authors",
+    params(
+        ("q" = Option<String>, Query, description = "Words that must each appear in the author's first name, last name, sort name or one of their aliases."),
+        ("sort" = Option<String>, Query, description = "`updated`, the default, for the most recently updated first, or `name` for by sort name."),
+        ("include" = Option<String>, Query, description = "Comma-separated relations to embed: `aliases`, `books`, `user`."),
+        ("fields" = Option<HashMap<String, String>>, Query, style = DeepObject, description = "Comma-separated fields to return per type, e.g. `fields[author]=lastname`.")
+    ),
+    responses(
+        (status = 200, body = ResAuthorList),
+        (status = 400, response = Problem),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[get("/?<q>&<sort>&<include_deleted>&<include>&<fields>")]
 pub async fn index(
     db: &State<DatabaseConnection>,
-    _user: AuthenticatedUser,
-) -> Response<Json<ResAuthorList>> {
+    user: AuthenticatedUser,
+    q: Option<&str>,
+    sort: Option<&str>,
+    include_deleted: Option<bool>,
+    include: Option<&str>,
+    fields: HashMap<&str, &str>,
+) -> Response<Json<Value>> {
     let db = db as &DatabaseConnection;
 
-    let authors = Author::find()
-        .order_by_desc(author::Column::UpdatedAt)
-        .all(db)
-        .await?
+    let embed = Embed::parse(include, &fields, &RELATIONS)?;
+
+    let query = Author::find().filter(list_filter(&user, q, include_deleted)?);
+
+    let query = match sort {
+        None | Some("updated") => query.order_by_desc(author::Column::UpdatedAt),
+        Some("name") => query
+            .order_by_asc(author::Column::SortName)
+            .order_by_asc(author::Column::Id),
+        Some(s) => {
+            return Err(ErrorResponse::new(
+                Status::BadRequest,
+                format!("Unknown sort \"{}\". Expected \"updated\" or \"name\".", s),
+            ))
+        }
+    };
+
+    let authors = query.all(db).await?;
+    let included = Included::load(db, &embed, &authors).await?;
+
+    let authors = authors
         .iter()
-        .map(ResAuthor::from)
-        .collect::<Vec<_>>();
+        .map(|a| embed_author(&embed, a, &included))
+        .collect::<Result<Vec<_>, _>>()?;
 
     Ok(SuccessResponse((
         Status::Ok,
-        Json(ResAuthorList {
-            total: authors.len(),
-            authors,
-        }),
+        Json(json!({ "total": authors.len(), "authors": authors })),
     )))
 }
 
+/// The authors with each word of `q` in their first, last or sort name, or in
+/// one of their aliases. Every author matches a blank `q`.
+fn search(q: &str) -> Condition {
+    q.split_whitespace().fold(Condition::all(), |filter, word| {
+        let aliases = Query::select()
+            .column(author_alias::Column::AuthorId)
+            .from(AuthorAlias)
+            .and_where(author_alias::Column::Name.contains(word))
+            .to_owned();
+
+        filter.add(
+            Condition::any()
+                .add(author::Column::Firstname.contains(word))
+                .add(author::Column::Lastname.contains(word))
+                .add(author::Column::SortName.contains(word))
+                .add(author::Column::Id.in_subquery(aliases)),
+        )
+    })
+}
+
+/// The authors matching the list filters, shared by the list and the export.
+pub(crate) fn list_filter(
+    user: &AuthenticatedUser,
+    q: Option<&str>,
+    include_deleted: Option<bool>,
+) -> Result<Condition, ErrorResponse> {
+    let mut filter = search(q.unwrap_or(""));
+
+    if !check_include_deleted(user, include_deleted)? {
+        filter = filter.add(author::Column::DeletedAt.is_null());
+    }
+
+    Ok(filter)
+}
+
+/// Creates an author.
+#[utoipa::path(
+    context_path = "/v1/authors",
+    tag = "authors",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response replayed.")
+    ),
+    request_body = ReqAuthor,
+    responses(
+        (status = 201, body = ResAuthor),
+        (status = 401, response = Problem),
+        (status = 409, response = Problem),
+        (status = 422, response = Problem)
+    ),
+    security(("token" = []))
+)]
 #[post("/", data = "<req_author>")]
 pub async fn create(
     db: &State<DatabaseConnection>,
     user: AuthenticatedUser,
-    req_author: Json<ReqAuthor>,
+    req_author: Idempotent<ReqAuthor>,
 ) -> Response<Json<ResAuthor>> {
-    let db = db as &DatabaseConnection;
+    let author = insert(db as &DatabaseConnection, &user, &req_author).await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResAuthor::from(&author)),
+    )))
+}
+
+/// Validates and inserts an author, recording their first revision.
+pub(crate) async fn insert<C: ConnectionTrait + TransactionTrait>(
+    db: &C,
+    user: &AuthenticatedUser,
+    req_author: &ReqAuthor,
+) -> Result<author::Model, ErrorResponse> {
+    req_author.validate(db).await?;
+    let born = req_author
+        .born()
+        .ok()
+        .flatten()
+        .map(PublicationDate::to_columns);
+    let died = req_author
+        .died()
+        .ok()
+        .flatten()
+        .map(PublicationDate::to_columns);
 
     let author = author::ActiveModel {
         user_id: Set(user.id),
         firstname: Set(req_author.firstname.to_owned()),
         lastname: Set(req_author.lastname.to_owned()),
+        sort_name: Set(req_author.sort_name()),
         bio: Set(req_author.bio.to_owned()),
+        born_on: Set(born.as_ref().map(|b| b.0)),
+        born_precision: Set(born.map(|b| b.1)),
+        died_on: Set(died.as_ref().map(|d| d.0)),
+        died_precision: Set(died.map(|d| d.1)),
+        nationality: Set(ReqAuthor::text(&req_author.nationality)),
+        website: Set(ReqAuthor::text(&req_author.website)),
+        photo: Set(ReqAuthor::text(&req_author.photo)),
         ..Default::default()
     };
 
-    let author = author.insert(db).await?;
+    let txn = db.begin().await?;
 
-    Ok(SuccessResponse((
-        Status::Created,
-        Json(ResAuthor::from(&author)),
-    )))
+    let author = author.insert(&txn).await?;
+
+    if let Some(aliases) = &req_author.aliases {
+        replace_aliases(&txn, author.id, aliases).await?;
+    }
+
+    let after = AuthorWithAliases::load(&txn, &author).await?;
+    revisions::record(&txn, user.id, RevisionAction::Create, None, Some(&after)).await?;
+
+    txn.commit().await?;
+
+    Ok(author)
 }
 
-#[get("/<id>")]
+/// Replaces the aliases of the author `id` with `aliases`.
+pub(super) async fn replace_aliases<C: ConnectionTrait>(
+    db: &C,
+    id: i32,
+    aliases: &[ReqAlias],
+) -> Result<(), DbErr> {
+    AuthorAlias::delete_many()
+        .filter(author_alias::Column::AuthorId.eq(id))
+        .exec(db)
+        .await?;
+
+    if aliases.is_empty() {
+        return Ok(());
+    }
+
+    AuthorAlias::insert_many(aliases.iter().map(|a| author_alias::ActiveModel {
+        author_id: Set(id),
+        name: Set(a.name.trim().to_owned()),
+        kind: Set(a.kind().unwrap_or(AliasKind::Alias)),
+        ..Default::default()
+    }))
+    .exec(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Creates, updates and deletes authors in one request, either all or nothing
+/// (`mode=atomic`, the default) or each on its own (`mode=best_effort`).
+#[utoipa::path(
+    context_path = "/v1/authors",
+    tag = "authors",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response replayed.")
+    ),
+    request_body = Vec<AuthorOperation>,
+    responses(
+        (status = 200, body = ResBulk<ResAuthor>),
+        (status = 207, body = ResBulk<ResAuthor>, description = "Some operations failed in best effort mode."),
+        (status = 401, response = Problem),
+        (status = 413, response = Problem),
+        (status = 409, response = Problem),
+        (status = 422, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[post("/bulk?<mode>", data = "<ops>")]
+pub async fn bulk(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    mode: Option<BulkMode>,
+    ops: Idempotent<Vec<AuthorOperation>>,
+) -> Response<Json<ResBulk<ResAuthor>>> {
+    super::bulk::run(
+        db as &DatabaseConnection,
+        config,
+        &user,
+        mode.unwrap_or_default(),
+        ops.into_inner(),
+    )
+    .await
+}
+
+/// Gets an author.
+///
+/// Takes `include` and `fields` as on the list.
+#[utoipa::path(
+    context_path = "/v1/authors",
+    tag = "authors",
+    params(
+        ("include" = Option<String>, Query, description = "Comma-separated relations to embed: `aliases`, `books`, `user`."),
+        ("fields" = Option<HashMap<String, String>>, Query, style = DeepObject, description = "Comma-separated fields to return per type, e.g. `fields[author]=lastname`.")
+    ),
+    responses(
+        (status = 200, body = ResAuthor, headers(("ETag" = String, description = "The version of the record."))),
+        (status = 304, description = "The author is unchanged. Never sent with `include`."),
+        (status = 308, description = "The author was merged into another.", headers(("Location" = String, description = "The same path for the author they were merged into."))),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem),
+        (status = 404, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[get("/<id>?<include_deleted>&<include>&<fields>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn show(
     db: &State<DatabaseConnection>,
-    _user: AuthenticatedUser,
+    user: AuthenticatedUser,
+    preconditions: Preconditions,
+    uri: &Origin<'_>,
     id: i32,
-) -> Response<Json<ResAuthor>> {
+    include_deleted: Option<bool>,
+    include: Option<&str>,
+    fields: HashMap<&str, &str>,
+) -> Response<Tagged<Json<Value>>> {
     let db = db as &DatabaseConnection;
 
-    let author = Author::find_by_id(id).one(db).await?;
-
-    let author = match author {
-        Some(a) => a,
-        None => {
-            return Err(ErrorResponse((
-                Status::NotFound,
-                "No author found with the specified ID.".to_string(),
-            )))
-        }
+    let embed = Embed::parse(include, &fields, &RELATIONS)?;
+    let include_deleted = check_include_deleted(&user, include_deleted)?;
+    let author = match find_author(db, id, include_deleted).await {
+        Ok(author) => author,
+        Err(e) => return Err(follow_merge(db, id, uri, e).await),
     };
 
+    // Included records change without the author's version, so only a author
+    // without them can be known to be unchanged.
+    if !embed.includes_any() && preconditions.is_not_modified(&author.etag()) {
+        return Ok(SuccessResponse((
+            Status::NotModified,
+            Tagged::not_modified(&author),
+        )));
+    }
+
+    let included = Included::load(db, &embed, std::slice::from_ref(&author)).await?;
+
     Ok(SuccessResponse((
         Status::Ok,
-        Json(ResAuthor::from(&author)),
+        Tagged::new(Json(embed_author(&embed, &author, &included)?), &author),
     )))
 }
 
+/// Replaces an author.
+#[utoipa::path(
+    context_path = "/v1/authors",
+    tag = "authors",
+    request_body = ReqAuthor,
+    responses(
+        (status = 200, body = ResAuthor, headers(("ETag" = String, description = "The version of the record."))),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 412, response = Problem),
+        (status = 422, response = Problem),
+        (status = 428, response = Problem)
+    ),
+    security(("token" = []))
+)]
 #[put("/<id>", data = "<req_author>")]
 pub async fn update(
     db: &State<DatabaseConnection>,
-    _user: AuthenticatedUser,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    preconditions: Preconditions,
     id: i32,
     req_author: Json<ReqAuthor>,
-) -> Response<Json<ResAuthor>> {
+) -> Response<Tagged<Json<ResAuthor>>> {
     let db = db as &DatabaseConnection;
 
-    let mut author: author::ActiveModel = match Author::find_by_id(id).one(db).await? {
-        Some(a) => a.into(),
-        None => {
-            return Err(ErrorResponse((
-                Status::NotFound,
-                "No author found with the specified ID.".to_string(),
-            )))
-        }
-    };
+    let before = find_author(db, id, false).await?;
+    let author = save(db, config, &user, &preconditions, before, &req_author).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Tagged::new(Json(ResAuthor::from(&author)), &author),
+    )))
+}
+
+/// Updates only the fields present in the patch. See [`ReqPatch`].
+#[utoipa::path(
+    context_path = "/v1/authors",
+    tag = "authors",
+    request_body(content((Object = "application/merge-patch+json"), (Vec<Object> = "application/json-patch+json"))),
+    responses(
+        (status = 200, body = ResAuthor, headers(("ETag" = String, description = "The version of the record."))),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 409, response = Problem),
+        (status = 412, response = Problem),
+        (status = 422, response = Problem),
+        (status = 428, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[patch("/<id>", data = "<req_patch>")]
+pub async fn patch(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    preconditions: Preconditions,
+    id: i32,
+    req_patch: ReqPatch,
+) -> Response<Tagged<Json<ResAuthor>>> {
+    let db = db as &DatabaseConnection;
+
+    let before = find_author(db, id, false).await?;
+    let req_author = req_patch.apply(&ReqAuthor::from(&before))?;
+    let author = save(db, config, &user, &preconditions, before, &req_author).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Tagged::new(Json(ResAuthor::from(&author)), &author),
+    )))
+}
+
+/// Writes `req_author` over `before`, provided nobody else changed it in the meantime.
+pub(super) async fn save<C: ConnectionTrait + TransactionTrait>(
+    db: &C,
+    config: &AppConfig,
+    user: &AuthenticatedUser,
+    preconditions: &Preconditions,
+    before: author::Model,
+    req_author: &ReqAuthor,
+) -> Result<author::Model, ErrorResponse> {
+    preconditions.check_if_match(&before.etag(), config.require_if_match)?;
+
+    req_author.validate(db).await?;
+    let born = req_author
+        .born()
+        .ok()
+        .flatten()
+        .map(PublicationDate::to_columns);
+    let died = req_author
+        .died()
+        .ok()
+        .flatten()
+        .map(PublicationDate::to_columns);
+
+    let mut author: author::ActiveModel = before.clone().into();
 
     author.firstname = Set(req_author.firstname.to_owned());
     author.lastname = Set(req_author.lastname.to_owned());
+    author.sort_name = Set(req_author.sort_name());
     author.bio = Set(req_author.bio.to_owned());
+    author.born_on = Set(born.as_ref().map(|b| b.0));
+    author.born_precision = Set(born.map(|b| b.1));
+    author.died_on = Set(died.as_ref().map(|d| d.0));
+    author.died_precision = Set(died.map(|d| d.1));
+    author.nationality = Set(ReqAuthor::text(&req_author.nationality));
+    author.website = Set(ReqAuthor::text(&req_author.website));
+    author.photo = Set(ReqAuthor::text(&req_author.photo));
 
     author.updated_at = Set(DateTimeUtc::from(SystemTime::now()));
+    author.version = Set(before.version + 1);
 
-    let author = author.update(db).await?;
+    let txn = db.begin().await?;
 
-    Ok(SuccessResponse((
-        Status::Ok,
-        Json(ResAuthor::from(&author)),
-    )))
+    let tracked = AuthorWithAliases::load(&txn, &before).await?;
+
+    // Only applies if nobody else updated the record since it was read.
+    let author = Author::update(author)
+        .filter(author::Column::Version.eq(before.version))
+        .exec(&txn)
+        .await
+        .map_err(guarded)?;
+
+    if let Some(aliases) = &req_author.aliases {
+        replace_aliases(&txn, author.id, aliases).await?;
+    }
+
+    revisions::record(
+        &txn,
+        user.id,
+        RevisionAction::Update,
+        Some(&tracked),
+        Some(&AuthorWithAliases::load(&txn, &author).await?),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(author)
 }
 
-#[delete("/<id>")]
+/// Deletes an author. If the author still has books, the request fails with
+/// 409 Conflict listing them, unless `strategy=cascade` (delete the books too)
+/// or `reassign_to=<author_id>` (move the books to another author) is given.
+#[utoipa::path(
+    context_path = "/v1/authors",
+    tag = "authors",
+    responses(
+        (status = 200, body = String),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 409, response = Problem),
+        (status = 412, response = Problem),
+        (status = 422, response = Problem),
+        (status = 428, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[delete("/<id>?<strategy>&<reassign_to>")]
 pub async fn delete(
     db: &State<DatabaseConnection>,
-    _user: AuthenticatedUser,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    preconditions: Preconditions,
     id: i32,
+    strategy: Option<&str>,
+    reassign_to: Option<i32>,
 ) -> Response<String> {
-    let db = db as &DatabaseConnection;
+    remove(
+        db as &DatabaseConnection,
+        config,
+        &user,
+        &preconditions,
+        id,
+        strategy,
+        reassign_to,
+    )
+    .await?;
+
+    Ok(SuccessResponse((Status::Ok, "Author deleted.".to_string())))
+}
 
-    let author = match Author::find_by_id(id).one(db).await? {
-        Some(a) => a,
-        None => {
-            return Err(ErrorResponse((
-                Status::NotFound,
-                "No author found with the specified ID.".to_string(),
-            )))
+/// Marks a live author as deleted, handling their books as `strategy` and
+/// `reassign_to` ask.
+pub(super) async fn remove<C: ConnectionTrait + TransactionTrait>(
+    db: &C,
+    config: &AppConfig,
+    user: &AuthenticatedUser,
+    preconditions: &Preconditions,
+    id: i32,
+    strategy: Option<&str>,
+    reassign_to: Option<i32>,
+) -> Result<(), ErrorResponse> {
+    let cascade = match (strategy, reassign_to) {
+        (None | Some("restrict"), _) => false,
+        (Some("cascade"), None) => true,
+        (Some("cascade"), Some(_)) => {
+            return Err(ErrorResponse::new(
+                Status::BadRequest,
+                "strategy=cascade cannot be combined with reassign_to.".to_string(),
+            ))
+        }
+        (Some(s), _) => {
+            return Err(ErrorResponse::new(
+                Status::BadRequest,
+                format!(
+                    "Unknown strategy \"{}\". Expected \"restrict\" or \"cascade\".",
+                    s
+                ),
+            ))
         }
     };
 
-    author.delete(db).await?;
+    let author = find_author(db, id, false).await?;
+    preconditions.check_if_match(&author.etag(), config.require_if_match)?;
 
-    Ok(SuccessResponse((Status::Ok, "Author deleted.".to_string())))
+    if reassign_to == Some(id) {
+        return Err(ErrorResponse::field(
+            "reassign_to",
+            "Cannot reassign books to the author being deleted.",
+        ));
+    }
+
+    if let Some(target) = reassign_to {
+        find_author(db, target, false).await.map_err(|_| {
+            ErrorResponse::field("reassign_to", "No author found with the specified ID.")
+        })?;
+    }
+
+    let txn = db.begin().await?;
+
+    let books = author
+        .find_related(Book)
+        .filter(book::Column::DeletedAt.is_null())
+        .all(&txn)
+        .await?;
+
+    if !books.is_empty() {
+        let now = DateTimeUtc::from(SystemTime::now());
+
+        if let Some(target) = reassign_to {
+            move_books(&txn, user, &books, target)
+                .await
+                .map_err(guarded)?;
+        } else if cascade {
+            let res = Book::update_many()
+                .col_expr(book::Column::DeletedAt, Expr::value(Some(now)))
+                .col_expr(
+                    book::Column::Version,
+                    Expr::col(book::Column::Version).add(1),
+                )
+                .filter(unchanged(&books))
+                .exec(&txn)
+                .await?;
+
+            if res.rows_affected != books.len() as u64 {
+                return Err(precondition_failed());
+            }
+
+            for before in &books {
+                let after = book::Model {
+                    deleted_at: Some(now),
+                    version: before.version + 1,
+                    ..before.clone()
+                };
+
+                revisions::record(
+                    &txn,
+                    user.id,
+                    RevisionAction::Delete,
+                    Some(before),
+                    Some(&after),
+                )
+                .await?;
+            }
+        } else {
+            return Err(ErrorResponse::new(
+                Status::Conflict,
+                format!(
+                    "The author still has {} book(s): {}. Delete them with ?strategy=cascade or move them with ?reassign_to=<author_id>.",
+                    books.len(),
+                    books
+                        .iter()
+                        .map(|b| format!("#{} \"{}\"", b.id, b.title))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            ));
+        }
+    }
+
+    let before = author.clone();
+    let mut author: author::ActiveModel = author.into();
+    author.deleted_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+    author.version = Set(before.version + 1);
+
+    let author = Author::update(author)
+        .filter(author::Column::Version.eq(before.version))
+        .exec(&txn)
+        .await
+        .map_err(guarded)?;
+    revisions::record(
+        &txn,
+        user.id,
+        RevisionAction::Delete,
+        Some(&before),
+        Some(&author),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(())
+}
+
+/// Matches each of `books` only while it is unchanged since it was read.
+fn unchanged(books: &[book::Model]) -> Condition {
+    books.iter().fold(Condition::any(), |cond, b| {
+        cond.add(
+            book::Column::Id
+                .eq(b.id)
+                .and(book::Column::Version.eq(b.version)),
+        )
+    })
+}
+
+/// Moves `books` to the author `target`, recording a revision for each.
+/// Fails with `RecordNotUpdated` if any of them changed since they were read.
+pub(super) async fn move_books<C: ConnectionTrait>(
+    db: &C,
+    user: &AuthenticatedUser,
+    books: &[book::Model],
+    target: i32,
+) -> Result<(), DbErr> {
+    if books.is_empty() {
+        return Ok(());
+    }
+
+    let res = Book::update_many()
+        .col_expr(book::Column::AuthorId, Expr::value(target))
+        .col_expr(
+            book::Column::UpdatedAt,
+            Expr::value(DateTimeUtc::from(SystemTime::now())),
+        )
+        .col_expr(
+            book::Column::Version,
+            Expr::col(book::Column::Version).add(1),
+        )
+        .filter(unchanged(books))
+        .exec(db)
+        .await?;
+
+    if res.rows_affected != books.len() as u64 {
+        return Err(DbErr::RecordNotUpdated);
+    }
+
+    for before in books {
+        let after = book::Model {
+            author_id: target,
+            version: before.version + 1,
+            ..before.clone()
+        };
+
+        revisions::record(
+            db,
+            user.id,
+            RevisionAction::Update,
+            Some(before),
+            Some(&after),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Restores a deleted author.
+#[utoipa::path(
+    context_path = "/v1/authors",
+    tag = "authors",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response replayed.")
+    ),
+    responses(
+        (status = 200, body = ResAuthor, headers(("ETag" = String, description = "The version of the record."))),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 409, response = Problem),
+        (status = 412, response = Problem),
+        (status = 428, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[post("/<id>/restore")]
+pub async fn restore(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    preconditions: Preconditions,
+    _idempotency: Idempotency,
+    id: i32,
+) -> Response<Tagged<Json<ResAuthor>>> {
+    let db = db as &DatabaseConnection;
+
+    let before = find_author(db, id, true).await?;
+    preconditions.check_if_match(&before.etag(), config.require_if_match)?;
+
+    if let Some(survivor) = before.merged_into_id {
+        return Err(ErrorResponse::new(
+            Status::Conflict,
+            format!(
+                "The author was merged into the author {} and can't be restored.",
+                survivor
+            ),
+        ));
+    }
+
+    let mut author: author::ActiveModel = before.clone().into();
+    author.deleted_at = Set(None);
+    author.version = Set(before.version + 1);
+
+    let txn = db.begin().await?;
+
+    let author = Author::update(author)
+        .filter(author::Column::Version.eq(before.version))
+        .exec(&txn)
+        .await
+        .map_err(guarded)?;
+    revisions::record(
+        &txn,
+        user.id,
+        RevisionAction::Restore,
+        Some(&before),
+        Some(&author),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Tagged::new(Json(ResAuthor::from(&author)), &author),
+    )))
+}
+
+/// An author and their books, shared by the list and the catalogue feeds.
+pub(crate) async fn books_of<C: ConnectionTrait>(
+    db: &C,
+    id: i32,
+    include_deleted: bool,
+) -> Result<(author::Model, Vec<book::Model>), ErrorResponse> {
+    let author = find_author(db, id, include_deleted).await?;
+
+    let mut query = author.find_related(Book);
+
+    if !include_deleted {
+        query = query.filter(book::Column::DeletedAt.is_null());
+    }
+
+    let books = query.all(db).await?;
+
+    Ok((author, books))
 }
 
-#[get("/<id>/books")]
+/// Lists an author's books.
+#[utoipa::path(
+    context_path = "/v1/authors",
+    tag = "authors",
+    responses(
+        (status = 200, body = ResBookList),
+        (status = 308, description = "The author was merged into another.", headers(("Location" = String, description = "The same path for the author they were merged into."))),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem),
+        (status = 404, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[get("/<id>/books?<include_deleted>")]
 pub async fn get_books(
     db: &State<DatabaseConnection>,
-    _user: AuthenticatedUser,
+    user: AuthenticatedUser,
+    uri: &Origin<'_>,
     id: i32,
+    include_deleted: Option<bool>,
 ) -> Response<Json<ResBookList>> {
     let db = db as &DatabaseConnection;
 
-    let author = match Author::find_by_id(id).one(db).await? {
-        Some(a) => a,
-        None => {
-            return Err(ErrorResponse((
-                Status::NotFound,
-                "No author found with the specified ID.".to_string(),
-            )))
-        }
+    let include_deleted = check_include_deleted(&user, include_deleted)?;
+    let (_, books) = match books_of(db, id, include_deleted).await {
+        Ok(found) => found,
+        Err(e) => return Err(follow_merge(db, id, uri, e).await),
     };
 
-    let books: Vec<book::Model> = author.find_related(Book).all(db).await?;
-
     Ok(SuccessResponse((
         Status::Ok,
         Json(ResBookList {
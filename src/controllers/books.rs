@@ -5,26 +5,55 @@
  * @link   https://afaan.dev
  * @link   https://github.com/AfaanBilal/bookstore
  */
+use async_graphql::InputObject;
 use rocket::{
     http::Status,
     serde::{json::Json, Deserialize, Serialize},
     State,
 };
 use sea_orm::{prelude::DateTimeUtc, *};
-use std::time::SystemTime;
+use serde_json::{json, Value};
+use std::{collections::HashMap, time::SystemTime};
+use utoipa::ToSchema;
 
-use super::{ErrorResponse, Response, SuccessResponse};
+use super::{
+    auth::ResUser,
+    authors::ResAuthor,
+    bulk::{Applied, BulkMode, Operation, ResBulk},
+    conditional::{guarded, Preconditions, Tagged, Versioned},
+    embed::{self, Embed},
+    enrichment,
+    error::Problem,
+    include_deleted as check_include_deleted,
+    patch::ReqPatch,
+    ErrorResponse, Response, SuccessResponse,
+};
 use crate::auth::AuthenticatedUser;
-use crate::entities::{book, prelude::*};
+use crate::entities::{author, book, prelude::*, sea_orm_active_enums::RevisionAction};
+use crate::idempotency::{Idempotency, Idempotent};
+use crate::isbn::{Isbn, ParseIsbnError};
+use crate::publication::{ParsePublicationDateError, PublicationDate};
+use crate::revisions;
+use crate::validation::{Rule, Validate, Validator, MAX_STRING_LENGTH};
+use crate::AppConfig;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(crate = "rocket::serde")]
 pub struct ResBook {
     pub id: i32,
     pub author_id: i32,
     pub title: String,
-    pub year: String,
+    pub published: Option<String>,
     pub cover: String,
+    /// As ISBN-13 digits.
+    pub isbn: Option<String>,
+    pub publisher: Option<String>,
+    pub edition: Option<String>,
+    pub pages: Option<i32>,
+    pub genre: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub deleted_at: Option<DateTimeUtc>,
 }
 
 impl From<&book::Model> for ResBook {
@@ -33,156 +62,838 @@ impl From<&book::Model> for ResBook {
             id: b.id,
             author_id: b.author_id,
             title: b.title.to_owned(),
-            year: b.year.to_owned(),
+            published: PublicationDate::from_columns(
+                b.published_on,
+                b.published_precision.as_ref(),
+            )
+            .map(|d| d.to_string()),
             cover: b.cover.to_owned(),
+            isbn: b.isbn.to_owned(),
+            publisher: b.publisher.to_owned(),
+            edition: b.edition.to_owned(),
+            pages: b.pages,
+            genre: b.genre.to_owned(),
+            deleted_at: b.deleted_at,
         }
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(crate = "rocket::serde")]
 pub struct ResBookList {
     pub total: usize,
     pub books: Vec<ResBook>,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema, InputObject)]
 #[serde(crate = "rocket::serde")]
+#[graphql(name = "BookInput")]
 pub struct ReqBook {
-    author_id: i32,
-    title: String,
-    year: String,
-    cover: String,
+    pub(crate) author_id: i32,
+    /// May be left out when `autofill` finds the book by ISBN.
+    #[serde(default)]
+    pub(crate) title: String,
+    pub(crate) published: Option<String>,
+    pub(crate) cover: String,
+    /// ISBN-10 or ISBN-13, hyphens allowed. Stored as ISBN-13.
+    #[serde(default)]
+    pub(crate) isbn: Option<String>,
+    #[serde(default)]
+    pub(crate) publisher: Option<String>,
+    /// E.g. `2nd edition`.
+    #[serde(default)]
+    pub(crate) edition: Option<String>,
+    #[serde(default)]
+    pub(crate) pages: Option<i32>,
+    /// E.g. `Science fiction`. Books are browsed by genre in the OPDS catalogue.
+    #[serde(default)]
+    pub(crate) genre: Option<String>,
+}
+
+impl From<&book::Model> for ReqBook {
+    fn from(b: &book::Model) -> Self {
+        let res = ResBook::from(b);
+
+        Self {
+            author_id: res.author_id,
+            title: res.title,
+            published: res.published,
+            cover: res.cover,
+            isbn: res.isbn,
+            publisher: res.publisher,
+            edition: res.edition,
+            pages: res.pages,
+            genre: res.genre,
+        }
+    }
+}
+
+impl ReqBook {
+    fn published(&self) -> Result<Option<PublicationDate>, ParsePublicationDateError> {
+        match self.published.as_deref().map(str::trim) {
+            None | Some("") => Ok(None),
+            Some(p) => p.parse().map(Some),
+        }
+    }
+
+    fn isbn(&self) -> Result<Option<Isbn>, ParseIsbnError> {
+        match self.isbn.as_deref().map(str::trim) {
+            None | Some("") => Ok(None),
+            Some(i) => i.parse().map(Some),
+        }
+    }
+
+    /// Blank optional text is stored as `NULL`.
+    fn text(value: &Option<String>) -> Option<String> {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_owned)
+    }
+}
+
+#[rocket::async_trait]
+impl Validate for ReqBook {
+    fn rules(&self, v: &mut Validator) {
+        v.field(
+            "title",
+            &self.title,
+            &[Rule::Required, Rule::MaxLength(MAX_STRING_LENGTH)],
+        )
+        .field(
+            "cover",
+            &self.cover,
+            &[Rule::MaxLength(MAX_STRING_LENGTH), Rule::Url],
+        );
+
+        v.field(
+            "publisher",
+            self.publisher.as_deref().unwrap_or(""),
+            &[Rule::MaxLength(MAX_STRING_LENGTH)],
+        )
+        .field(
+            "edition",
+            self.edition.as_deref().unwrap_or(""),
+            &[Rule::MaxLength(MAX_STRING_LENGTH)],
+        )
+        .field(
+            "genre",
+            self.genre.as_deref().unwrap_or(""),
+            &[Rule::MaxLength(MAX_STRING_LENGTH)],
+        );
+
+        if let Err(e) = self.published() {
+            v.error("published", format!("Invalid date: {}.", e));
+        }
+
+        if let Err(e) = self.isbn() {
+            v.error("isbn", format!("Invalid ISBN: {}.", e));
+        }
+
+        if self.pages.is_some_and(|p| p < 1) {
+            v.error("pages", "Must be at least 1.");
+        }
+    }
+
+    async fn references<C: ConnectionTrait>(&self, db: &C, v: &mut Validator) -> Result<(), DbErr> {
+        let author = Author::find_by_id(self.author_id)
+            .filter(author::Column::DeletedAt.is_null())
+            .one(db)
+            .await?;
+
+        if author.is_none() {
+            v.error("author_id", "No author found with the specified ID.");
+        }
+
+        Ok(())
+    }
+}
+
+/// The books matching the list filters, shared by the list and the export.
+pub(crate) fn list_filter(
+    user: &AuthenticatedUser,
+    published_from: Option<&str>,
+    published_to: Option<&str>,
+    genre: Option<&str>,
+    include_deleted: Option<bool>,
+) -> Result<Condition, ErrorResponse> {
+    let published_from = parse_date_filter("published_from", published_from)?;
+    let published_to = parse_date_filter("published_to", published_to)?;
+    let mut filter = Condition::all();
+
+    if !check_include_deleted(user, include_deleted)? {
+        filter = filter.add(book::Column::DeletedAt.is_null());
+    }
+
+    if let Some(from) = published_from {
+        filter = filter.add(book::Column::PublishedOn.gte(from.first_day()));
+    }
+
+    if let Some(to) = published_to {
+        filter = filter.add(book::Column::PublishedOn.lte(to.last_day()));
+    }
+
+    if let Some(genre) = genre {
+        filter = filter.add(book::Column::Genre.eq(genre.trim()));
+    }
+
+    Ok(filter)
 }
 
-#[get("/")]
+fn parse_date_filter(
+    name: &str,
+    value: Option<&str>,
+) -> Result<Option<PublicationDate>, ErrorResponse> {
+    match value {
+        None => Ok(None),
+        Some(v) => v.parse().map(Some).map_err(|e| {
+            ErrorResponse::new(Status::BadRequest, format!("Invalid {}: {}.", name, e))
+        }),
+    }
+}
+
+/// One operation of `POST /books/bulk`.
+#[derive(Deserialize, ToSchema)]
+#[serde(crate = "rocket::serde", tag = "op", rename_all = "snake_case")]
+pub enum BookOperation {
+    Create {
+        data: ReqBook,
+    },
+    Update {
+        id: i32,
+        data: ReqBook,
+        /// Works as the `If-Match` header.
+        if_match: Option<String>,
+    },
+    Delete {
+        id: i32,
+        /// Works as the `If-Match` header.
+        if_match: Option<String>,
+    },
+}
+
+#[rocket::async_trait]
+impl Operation for BookOperation {
+    type Output = ResBook;
+
+    async fn apply(
+        self,
+        txn: &DatabaseTransaction,
+        config: &AppConfig,
+        user: &AuthenticatedUser,
+    ) -> Result<Applied<ResBook>, ErrorResponse> {
+        match self {
+            BookOperation::Create { data } => {
+                let book = insert(txn, user, &data).await?;
+
+                Ok(Applied {
+                    status: Status::Created,
+                    id: book.id,
+                    data: Some(ResBook::from(&book)),
+                })
+            }
+            BookOperation::Update { id, data, if_match } => {
+                let before = find_book(txn, id, false).await?;
+                let preconditions = Preconditions::if_match(if_match);
+                let book = save(txn, config, user, &preconditions, before, &data).await?;
+
+                Ok(Applied {
+                    status: Status::Ok,
+                    id,
+                    data: Some(ResBook::from(&book)),
+                })
+            }
+            BookOperation::Delete { id, if_match } => {
+                let preconditions = Preconditions::if_match(if_match);
+                remove(txn, config, user, &preconditions, id).await?;
+
+                Ok(Applied {
+                    status: Status::Ok,
+                    id,
+                    data: None,
+                })
+            }
+        }
+    }
+}
+
+/// Relations `?include=` may name on books.
+const RELATIONS: [&str; 2] = ["author", "user"];
+
+/// Renders a book with whichever of its author and user were included.
+pub(super) fn embed_book(
+    embed: &Embed,
+    book: &book::Model,
+    author: Option<&author::Model>,
+    users: &HashMap<i32, ResUser>,
+) -> Result<Value, ErrorResponse> {
+    let mut res = embed.select("book", &ResBook::from(book))?;
+
+    if embed.includes("author") {
+        let author = author
+            .map(|a| embed.select("author", &ResAuthor::from(a)))
+            .transpose()?;
+        res.insert("author".to_string(), author.into());
+    }
+
+    if embed.includes("user") {
+        let user = users
+            .get(&book.user_id)
+            .map(|u| embed.select("user", u))
+            .transpose()?;
+        res.insert("user".to_string(), user.into());
+    }
+
+    Ok(Value::Object(res))
+}
+
+/// Finds a book by ID. Soft-deleted books are only found if `include_deleted` is set.
+pub(super) async fn find_book<C: ConnectionTrait>(
+    db: &C,
+    id: i32,
+    include_deleted: bool,
+) -> Result<book::Model, ErrorResponse> {
+    let mut query = Book::find_by_id(id);
+
+    if !include_deleted {
+        query = query.filter(book::Column::DeletedAt.is_null());
+    }
+
+    match query.one(db).await? {
+        Some(b) => Ok(b),
+        None => Err(ErrorResponse::new(
+            Status::NotFound,
+            "No book found with the specified ID.".to_string(),
+        )),
+    }
+}
+
+/// Lists books.
+///
+/// `include` embeds each book's `author` and `user`, and `fields[book]`,
+/// `fields[author]` and `fields[user]` limit the fields returned.
+#[utoipa::path(
+    context_path = "/v1/books",
+    tag = "books",
+    params(
+        ("genre" = Option<String>, Query, description = "Only books of this genre, ignoring case."),
+        ("include" = Option<String>, Query, description = "Comma-separated relations to embed: `author`, `user`."),
+        ("fields" = Option<HashMap<String, String>>, Query, style = DeepObject, description = "Comma-separated fields to return per type, e.g. `fields[book]=title,published`.")
+    ),
+    responses(
+        (status = 200, body = ResBookList),
+        (status = 400, response = Problem),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[get("/?<published_from>&<published_to>&<genre>&<include_deleted>&<include>&<fields>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn index(
     db: &State<DatabaseConnection>,
-    _user: AuthenticatedUser,
-) -> Response<Json<ResBookList>> {
+    user: AuthenticatedUser,
+    published_from: Option<&str>,
+    published_to: Option<&str>,
+    genre: Option<&str>,
+    include_deleted: Option<bool>,
+    include: Option<&str>,
+    fields: HashMap<&str, &str>,
+) -> Response<Json<Value>> {
     let db = db as &DatabaseConnection;
 
-    let books = Book::find()
-        .order_by_desc(book::Column::UpdatedAt)
-        .all(db)
-        .await?
+    let embed = Embed::parse(include, &fields, &RELATIONS)?;
+    let filter = list_filter(&user, published_from, published_to, genre, include_deleted)?;
+
+    let query = Book::find()
+        .filter(filter)
+        .order_by_desc(book::Column::UpdatedAt);
+
+    let rows = if embed.includes("author") {
+        query.find_also_related(Author).all(db).await?
+    } else {
+        query
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|b| (b, None))
+            .collect()
+    };
+
+    let users = if embed.includes("user") {
+        embed::users(db, rows.iter().map(|(b, _)| b.user_id)).await?
+    } else {
+        HashMap::new()
+    };
+
+    let books = rows
         .iter()
-        .map(ResBook::from)
-        .collect::<Vec<_>>();
+        .map(|(b, a)| embed_book(&embed, b, a.as_ref(), &users))
+        .collect::<Result<Vec<_>, _>>()?;
 
     Ok(SuccessResponse((
         Status::Ok,
-        Json(ResBookList {
-            total: books.len(),
-            books,
-        }),
+        Json(json!({ "total": books.len(), "books": books })),
     )))
 }
 
-#[post("/", data = "<req_book>")]
+/// Creates a book.
+///
+/// With `autofill=true`, the blank title, publication date, publisher, edition
+/// and pages are filled from the Open Library edition with the book's ISBN.
+#[utoipa::path(
+    context_path = "/v1/books",
+    tag = "books",
+    params(
+        ("autofill" = Option<bool>, Query, description = "Fills blank fields from Open Library by ISBN."),
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response replayed.")
+    ),
+    request_body = ReqBook,
+    responses(
+        (status = 201, body = ResBook),
+        (status = 401, response = Problem),
+        (status = 409, response = Problem),
+        (status = 422, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[post("/?<autofill>", data = "<req_book>")]
 pub async fn create(
     db: &State<DatabaseConnection>,
     user: AuthenticatedUser,
-    req_book: Json<ReqBook>,
+    autofill: Option<bool>,
+    req_book: Idempotent<ReqBook>,
 ) -> Response<Json<ResBook>> {
     let db = db as &DatabaseConnection;
+    let mut req_book = req_book.into_inner();
+
+    if autofill == Some(true) {
+        enrichment::autofill(db, &mut req_book).await?;
+    }
+
+    let book = insert(db, &user, &req_book).await?;
+
+    Ok(SuccessResponse((
+        Status::Created,
+        Json(ResBook::from(&book)),
+    )))
+}
+
+/// Validates and inserts a book, recording its first revision.
+pub(crate) async fn insert<C: ConnectionTrait + TransactionTrait>(
+    db: &C,
+    user: &AuthenticatedUser,
+    req_book: &ReqBook,
+) -> Result<book::Model, ErrorResponse> {
+    req_book.validate(db).await?;
+    let published = req_book
+        .published()
+        .ok()
+        .flatten()
+        .map(PublicationDate::to_columns);
 
     let book = book::ActiveModel {
         user_id: Set(user.id),
         author_id: Set(req_book.author_id.to_owned()),
         title: Set(req_book.title.to_owned()),
-        year: Set(req_book.year.to_owned()),
+        published_on: Set(published.as_ref().map(|p| p.0)),
+        published_precision: Set(published.map(|p| p.1)),
         cover: Set(req_book.cover.to_owned()),
+        isbn: Set(req_book.isbn().ok().flatten().map(|i| i.to_string())),
+        publisher: Set(ReqBook::text(&req_book.publisher)),
+        edition: Set(ReqBook::text(&req_book.edition)),
+        pages: Set(req_book.pages),
+        genre: Set(ReqBook::text(&req_book.genre)),
         ..Default::default()
     };
 
-    let book = book.insert(db).await?;
+    let txn = db.begin().await?;
 
-    Ok(SuccessResponse((
-        Status::Created,
-        Json(ResBook::from(&book)),
-    )))
+    let book = book.insert(&txn).await?;
+    revisions::record(&txn, user.id, RevisionAction::Create, None, Some(&book)).await?;
+
+    txn.commit().await?;
+
+    Ok(book)
+}
+
+/// Creates, updates and deletes books in one request, either all or nothing
+/// (`mode=atomic`, the default) or each on its own (`mode=best_effort`).
+#[utoipa::path(
+    context_path = "/v1/books",
+    tag = "books",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response replayed.")
+    ),
+    request_body = Vec<BookOperation>,
+    responses(
+        (status = 200, body = ResBulk<ResBook>),
+        (status = 207, body = ResBulk<ResBook>, description = "Some operations failed in best effort mode."),
+        (status = 401, response = Problem),
+        (status = 413, response = Problem),
+        (status = 409, response = Problem),
+        (status = 422, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[post("/bulk?<mode>", data = "<ops>")]
+pub async fn bulk(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    mode: Option<BulkMode>,
+    ops: Idempotent<Vec<BookOperation>>,
+) -> Response<Json<ResBulk<ResBook>>> {
+    super::bulk::run(
+        db as &DatabaseConnection,
+        config,
+        &user,
+        mode.unwrap_or_default(),
+        ops.into_inner(),
+    )
+    .await
 }
 
-#[get("/<id>")]
+/// Gets a book.
+///
+/// Takes `include` and `fields` as on the list.
+#[utoipa::path(
+    context_path = "/v1/books",
+    tag = "books",
+    params(
+        ("include" = Option<String>, Query, description = "Comma-separated relations to embed: `author`, `user`."),
+        ("fields" = Option<HashMap<String, String>>, Query, style = DeepObject, description = "Comma-separated fields to return per type, e.g. `fields[book]=title,published`.")
+    ),
+    responses(
+        (status = 200, body = ResBook, headers(("ETag" = String, description = "The version of the record."))),
+        (status = 304, description = "The book is unchanged. Never sent with `include`."),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem),
+        (status = 404, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[get("/<id>?<include_deleted>&<include>&<fields>")]
 pub async fn show(
     db: &State<DatabaseConnection>,
-    _user: AuthenticatedUser,
+    user: AuthenticatedUser,
+    preconditions: Preconditions,
     id: i32,
-) -> Response<Json<ResBook>> {
+    include_deleted: Option<bool>,
+    include: Option<&str>,
+    fields: HashMap<&str, &str>,
+) -> Response<Tagged<Json<Value>>> {
     let db = db as &DatabaseConnection;
 
-    let book = Book::find_by_id(id).one(db).await?;
+    let embed = Embed::parse(include, &fields, &RELATIONS)?;
+    let include_deleted = check_include_deleted(&user, include_deleted)?;
+    let book = find_book(db, id, include_deleted).await?;
 
-    let book = match book {
-        Some(b) => b,
-        None => {
-            return Err(ErrorResponse((
-                Status::NotFound,
-                "No book found with the specified ID.".to_string(),
-            )))
-        }
+    // Included records change without the book's version, so only a book
+    // without them can be known to be unchanged.
+    if !embed.includes_any() && preconditions.is_not_modified(&book.etag()) {
+        return Ok(SuccessResponse((
+            Status::NotModified,
+            Tagged::not_modified(&book),
+        )));
+    }
+
+    let author = if embed.includes("author") {
+        book.find_related(Author).one(db).await?
+    } else {
+        None
+    };
+
+    let users = if embed.includes("user") {
+        embed::users(db, [book.user_id]).await?
+    } else {
+        HashMap::new()
     };
 
     Ok(SuccessResponse((
         Status::Ok,
-        Json(ResBook::from(&book)),
+        Tagged::new(
+            Json(embed_book(&embed, &book, author.as_ref(), &users)?),
+            &book,
+        ),
     )))
 }
 
+/// Replaces a book.
+#[utoipa::path(
+    context_path = "/v1/books",
+    tag = "books",
+    request_body = ReqBook,
+    responses(
+        (status = 200, body = ResBook, headers(("ETag" = String, description = "The version of the record."))),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 412, response = Problem),
+        (status = 422, response = Problem),
+        (status = 428, response = Problem)
+    ),
+    security(("token" = []))
+)]
 #[put("/<id>", data = "<req_book>")]
 pub async fn update(
     db: &State<DatabaseConnection>,
-    _user: AuthenticatedUser,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    preconditions: Preconditions,
     id: i32,
     req_book: Json<ReqBook>,
-) -> Response<Json<ResBook>> {
+) -> Response<Tagged<Json<ResBook>>> {
     let db = db as &DatabaseConnection;
 
-    let mut book: book::ActiveModel = match Book::find_by_id(id).one(db).await? {
-        Some(b) => b.into(),
-        None => {
-            return Err(ErrorResponse((
-                Status::NotFound,
-                "No book found with the specified ID.".to_string(),
-            )))
-        }
-    };
+    let before = find_book(db, id, false).await?;
+    let book = save(db, config, &user, &preconditions, before, &req_book).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Tagged::new(Json(ResBook::from(&book)), &book),
+    )))
+}
+
+/// Updates only the fields present in the patch. See [`ReqPatch`].
+#[utoipa::path(
+    context_path = "/v1/books",
+    tag = "books",
+    request_body(content((Object = "application/merge-patch+json"), (Vec<Object> = "application/json-patch+json"))),
+    responses(
+        (status = 200, body = ResBook, headers(("ETag" = String, description = "The version of the record."))),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 409, response = Problem),
+        (status = 412, response = Problem),
+        (status = 422, response = Problem),
+        (status = 428, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[patch("/<id>", data = "<req_patch>")]
+pub async fn patch(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    preconditions: Preconditions,
+    id: i32,
+    req_patch: ReqPatch,
+) -> Response<Tagged<Json<ResBook>>> {
+    let db = db as &DatabaseConnection;
+
+    let before = find_book(db, id, false).await?;
+    let req_book = req_patch.apply(&ReqBook::from(&before))?;
+    let book = save(db, config, &user, &preconditions, before, &req_book).await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Tagged::new(Json(ResBook::from(&book)), &book),
+    )))
+}
+
+/// Writes `req_book` over `before`, provided nobody else changed it in the meantime.
+pub(crate) async fn save<C: ConnectionTrait + TransactionTrait>(
+    db: &C,
+    config: &AppConfig,
+    user: &AuthenticatedUser,
+    preconditions: &Preconditions,
+    before: book::Model,
+    req_book: &ReqBook,
+) -> Result<book::Model, ErrorResponse> {
+    preconditions.check_if_match(&before.etag(), config.require_if_match)?;
+
+    req_book.validate(db).await?;
+    let published = req_book
+        .published()
+        .ok()
+        .flatten()
+        .map(PublicationDate::to_columns);
+
+    let mut book: book::ActiveModel = before.clone().into();
 
     book.author_id = Set(req_book.author_id);
     book.title = Set(req_book.title.to_owned());
-    book.year = Set(req_book.year.to_owned());
+    book.published_on = Set(published.as_ref().map(|p| p.0));
+    book.published_precision = Set(published.map(|p| p.1));
     book.cover = Set(req_book.cover.to_owned());
+    book.isbn = Set(req_book.isbn().ok().flatten().map(|i| i.to_string()));
+    book.publisher = Set(ReqBook::text(&req_book.publisher));
+    book.edition = Set(ReqBook::text(&req_book.edition));
+    book.pages = Set(req_book.pages);
+    book.genre = Set(ReqBook::text(&req_book.genre));
 
     book.updated_at = Set(DateTimeUtc::from(SystemTime::now()));
+    book.version = Set(before.version + 1);
 
-    let book = book.update(db).await?;
+    let txn = db.begin().await?;
 
-    Ok(SuccessResponse((
-        Status::Ok,
-        Json(ResBook::from(&book)),
-    )))
+    // Only applies if nobody else updated the record since it was read.
+    let book = Book::update(book)
+        .filter(book::Column::Version.eq(before.version))
+        .exec(&txn)
+        .await
+        .map_err(guarded)?;
+    revisions::record(
+        &txn,
+        user.id,
+        RevisionAction::Update,
+        Some(&before),
+        Some(&book),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(book)
 }
 
+/// Deletes a book.
+#[utoipa::path(
+    context_path = "/v1/books",
+    tag = "books",
+    responses(
+        (status = 200, body = String),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 412, response = Problem),
+        (status = 428, response = Problem)
+    ),
+    security(("token" = []))
+)]
 #[delete("/<id>")]
 pub async fn delete(
     db: &State<DatabaseConnection>,
-    _user: AuthenticatedUser,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    preconditions: Preconditions,
     id: i32,
 ) -> Response<String> {
+    remove(db as &DatabaseConnection, config, &user, &preconditions, id).await?;
+
+    Ok(SuccessResponse((Status::Ok, "Book deleted.".to_string())))
+}
+
+/// Marks a live book as deleted.
+pub(super) async fn remove<C: ConnectionTrait + TransactionTrait>(
+    db: &C,
+    config: &AppConfig,
+    user: &AuthenticatedUser,
+    preconditions: &Preconditions,
+    id: i32,
+) -> Result<(), ErrorResponse> {
+    let before = find_book(db, id, false).await?;
+    preconditions.check_if_match(&before.etag(), config.require_if_match)?;
+
+    let mut book: book::ActiveModel = before.clone().into();
+    book.deleted_at = Set(Some(DateTimeUtc::from(SystemTime::now())));
+    book.version = Set(before.version + 1);
+
+    let txn = db.begin().await?;
+
+    let book = Book::update(book)
+        .filter(book::Column::Version.eq(before.version))
+        .exec(&txn)
+        .await
+        .map_err(guarded)?;
+    revisions::record(
+        &txn,
+        user.id,
+        RevisionAction::Delete,
+        Some(&before),
+        Some(&book),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(())
+}
+
+/// Restores a deleted book.
+#[utoipa::path(
+    context_path = "/v1/books",
+    tag = "books",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response replayed.")
+    ),
+    responses(
+        (status = 200, body = ResBook, headers(("ETag" = String, description = "The version of the record."))),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 409, response = Problem),
+        (status = 412, response = Problem),
+        (status = 428, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[post("/<id>/restore")]
+pub async fn restore(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    preconditions: Preconditions,
+    _idempotency: Idempotency,
+    id: i32,
+) -> Response<Tagged<Json<ResBook>>> {
     let db = db as &DatabaseConnection;
 
-    let book = match Book::find_by_id(id).one(db).await? {
-        Some(b) => b,
-        None => {
-            return Err(ErrorResponse((
-                Status::NotFound,
-                "No book found with the specified ID.".to_string(),
-            )))
-        }
-    };
+    let before = find_book(db, id, true).await?;
+    preconditions.check_if_match(&before.etag(), config.require_if_match)?;
 
-    book.delete(db).await?;
+    if before.deleted_at.is_none() {
+        return Ok(SuccessResponse((
+            Status::Ok,
+            Tagged::new(Json(ResBook::from(&before)), &before),
+        )));
+    }
 
-    Ok(SuccessResponse((Status::Ok, "Book deleted.".to_string())))
+    ensure_author_active(db, before.author_id).await?;
+
+    let mut book: book::ActiveModel = before.clone().into();
+    book.deleted_at = Set(None);
+    book.version = Set(before.version + 1);
+
+    let txn = db.begin().await?;
+
+    let book = Book::update(book)
+        .filter(book::Column::Version.eq(before.version))
+        .exec(&txn)
+        .await
+        .map_err(guarded)?;
+    revisions::record(
+        &txn,
+        user.id,
+        RevisionAction::Restore,
+        Some(&before),
+        Some(&book),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Tagged::new(Json(ResBook::from(&book)), &book),
+    )))
+}
+
+/// A book can only be live while its author is.
+pub(super) async fn ensure_author_active(
+    db: &DatabaseConnection,
+    author_id: i32,
+) -> Result<(), ErrorResponse> {
+    if Author::find_by_id(author_id)
+        .filter(author::Column::DeletedAt.is_null())
+        .one(db)
+        .await?
+        .is_none()
+    {
+        return Err(ErrorResponse::new(
+            Status::Conflict,
+            "The book's author is deleted. Restore the author first.".to_string(),
+        ));
+    }
+
+    Ok(())
 }
@@ -0,0 +1,199 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use rocket::{
+    http::Status,
+    serde::{json::Json, Serialize},
+};
+use sea_orm::*;
+use utoipa::ToSchema;
+
+use super::{error::ItemError, ErrorResponse, Response, SuccessResponse};
+use crate::auth::AuthenticatedUser;
+use crate::AppConfig;
+
+/// How a batch is applied.
+#[derive(FromFormField, Serialize, ToSchema, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "rocket::serde", rename_all = "snake_case")]
+pub enum BulkMode {
+    /// All operations in one transaction: the first failure rolls back the batch.
+    #[default]
+    #[field(value = "atomic")]
+    Atomic,
+    /// Each operation in its own transaction: failures don't stop the rest.
+    #[field(value = "best_effort")]
+    BestEffort,
+}
+
+/// One operation of a batch.
+#[rocket::async_trait]
+pub trait Operation: Send {
+    type Output: Serialize + Send;
+
+    async fn apply(
+        self,
+        txn: &DatabaseTransaction,
+        config: &AppConfig,
+        user: &AuthenticatedUser,
+    ) -> Result<Applied<Self::Output>, ErrorResponse>;
+}
+
+/// What an operation did: the status it would have had on its own, the record
+/// it touched and, unless it was deleted, the record as written.
+pub struct Applied<T> {
+    pub status: Status,
+    pub id: i32,
+    pub data: Option<T>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct ResBulkItem<T> {
+    /// The operation's position in the batch.
+    index: usize,
+    status: u16,
+    /// False if the operation failed, or succeeded but was rolled back with its batch.
+    applied: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ItemError>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct ResBulk<T> {
+    mode: BulkMode,
+    /// False if an atomic batch was rolled back.
+    committed: bool,
+    /// Operations whose changes were kept.
+    succeeded: usize,
+    failed: usize,
+    results: Vec<ResBulkItem<T>>,
+}
+
+/// Applies `ops` in order.
+///
+/// Answers 200 if every operation succeeded, 207 if some failed in best effort
+/// mode, or, if an atomic batch was rolled back, the failed operation's status.
+/// An atomic batch stops at the first failure, so later operations are not reported.
+pub async fn run<O: Operation>(
+    db: &DatabaseConnection,
+    config: &AppConfig,
+    user: &AuthenticatedUser,
+    mode: BulkMode,
+    ops: Vec<O>,
+) -> Response<Json<ResBulk<O::Output>>> {
+    if ops.len() > config.bulk_limit {
+        return Err(ErrorResponse::new(
+            Status::PayloadTooLarge,
+            format!(
+                "A batch may hold at most {} operations, this one has {}.",
+                config.bulk_limit,
+                ops.len()
+            ),
+        ));
+    }
+
+    let mut results = Vec::with_capacity(ops.len());
+    let mut committed = true;
+
+    match mode {
+        BulkMode::Atomic => {
+            let txn = db.begin().await?;
+
+            for (index, op) in ops.into_iter().enumerate() {
+                let result = op.apply(&txn, config, user).await;
+                let failed = result.is_err();
+
+                results.push(ResBulkItem::new(index, result));
+
+                if failed {
+                    committed = false;
+                    break;
+                }
+            }
+
+            if committed {
+                txn.commit().await?;
+            } else {
+                txn.rollback().await?;
+
+                // Nothing the batch wrote was kept.
+                for result in &mut results {
+                    result.applied = false;
+                    result.data = None;
+                }
+            }
+        }
+        BulkMode::BestEffort => {
+            for (index, op) in ops.into_iter().enumerate() {
+                let txn = db.begin().await?;
+                let result = op.apply(&txn, config, user).await;
+
+                if result.is_ok() {
+                    txn.commit().await?;
+                } else {
+                    txn.rollback().await?;
+                }
+
+                results.push(ResBulkItem::new(index, result));
+            }
+        }
+    }
+
+    let failed = results.iter().filter(|r| r.error.is_some()).count();
+
+    let status = match (failed, committed) {
+        (0, _) => Status::Ok,
+        (_, true) => Status::MultiStatus,
+        (_, false) => results
+            .last()
+            .and_then(|r| Status::from_code(r.status))
+            .unwrap_or(Status::UnprocessableEntity),
+    };
+
+    Ok(SuccessResponse((
+        status,
+        Json(ResBulk {
+            mode,
+            committed,
+            succeeded: results.iter().filter(|r| r.applied).count(),
+            failed,
+            results,
+        }),
+    )))
+}
+
+impl<T> ResBulkItem<T> {
+    fn new(index: usize, result: Result<Applied<T>, ErrorResponse>) -> Self {
+        match result {
+            Ok(applied) => Self {
+                index,
+                status: applied.status.code,
+                applied: true,
+                id: Some(applied.id),
+                data: applied.data,
+                error: None,
+            },
+            Err(err) => {
+                let error = ItemError::from(err);
+
+                Self {
+                    index,
+                    status: error.status,
+                    applied: false,
+                    id: None,
+                    data: None,
+                    error: Some(error),
+                }
+            }
+        }
+    }
+}
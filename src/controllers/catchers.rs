@@ -0,0 +1,71 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use rocket::{
+    http::{Header, Status},
+    request::Request,
+};
+
+use super::{error::GuardFailure, ErrorResponse};
+
+/// Reports the failing guard's reason if there is one, `detail` otherwise.
+fn problem(req: &Request<'_>, status: Status, detail: &str) -> ErrorResponse {
+    ErrorResponse::new(status, GuardFailure::of(req).unwrap_or(detail))
+}
+
+#[catch(400)]
+pub fn bad_request(req: &Request<'_>) -> ErrorResponse {
+    problem(
+        req,
+        Status::BadRequest,
+        "The request is malformed. Check that the body is valid JSON.",
+    )
+}
+
+#[catch(401)]
+pub fn unauthorized(req: &Request<'_>) -> ErrorResponse {
+    problem(req, Status::Unauthorized, "Authentication is required.")
+}
+
+/// A 401 that asks for HTTP Basic credentials, for catalogue readers.
+#[derive(Responder)]
+pub struct Challenge(ErrorResponse, Header<'static>);
+
+#[catch(401)]
+pub fn challenge(req: &Request<'_>) -> Challenge {
+    Challenge(
+        unauthorized(req),
+        Header::new(
+            "WWW-Authenticate",
+            "Basic realm=\"BookStore\", charset=\"UTF-8\"",
+        ),
+    )
+}
+
+#[catch(404)]
+pub fn not_found(req: &Request<'_>) -> ErrorResponse {
+    problem(req, Status::NotFound, "No route matches the requested URL.")
+}
+
+#[catch(422)]
+pub fn unprocessable_entity(req: &Request<'_>) -> ErrorResponse {
+    problem(
+        req,
+        Status::UnprocessableEntity,
+        "The request contains a missing or invalid value in the path, query or body.",
+    )
+}
+
+#[catch(500)]
+pub fn internal_server_error() -> ErrorResponse {
+    ErrorResponse::internal("Unhandled error, see the preceding log entries.")
+}
+
+#[catch(default)]
+pub fn default(status: Status, req: &Request<'_>) -> ErrorResponse {
+    problem(req, status, status.reason().unwrap_or("Unknown error."))
+}
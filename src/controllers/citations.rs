@@ -0,0 +1,198 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use rocket::{
+    http::{Accept, ContentType, Status},
+    State,
+};
+use sea_orm::*;
+use std::collections::HashSet;
+
+use super::{error::Problem, exports::either, ErrorResponse};
+use crate::auth::AuthenticatedUser;
+use crate::citation::{Citation, CitationFormat};
+use crate::entities::{author, book, prelude::*};
+use crate::publication::PublicationDate;
+use crate::AppConfig;
+
+/// Citations, with the media type of their format.
+#[derive(Responder)]
+pub struct Cited(String, ContentType);
+
+/// Resolves the format from `?format=`, or else the `Accept` header.
+fn format(name: Option<&str>, accept: Option<&Accept>) -> Result<CitationFormat, ErrorResponse> {
+    match name {
+        Some(name) => CitationFormat::from_name(name).ok_or_else(|| {
+            ErrorResponse::field(
+                "format",
+                format!(
+                    "The format must be {}.",
+                    either(CitationFormat::ALL.iter().map(|f| f.name().to_string()))
+                ),
+            )
+        }),
+        None => CitationFormat::negotiate(accept).ok_or_else(|| {
+            ErrorResponse::new(
+                Status::NotAcceptable,
+                format!(
+                    "Citations are available as {}.",
+                    either(CitationFormat::ALL.iter().map(|f| {
+                        let m = f.media_type();
+                        format!("{}/{}", m.top(), m.sub())
+                    }))
+                ),
+            )
+        }),
+    }
+}
+
+fn citation(b: book::Model, a: author::Model) -> Citation {
+    Citation {
+        id: b.id,
+        title: b.title,
+        author_given: a.firstname,
+        author_family: a.lastname,
+        published: PublicationDate::from_columns(b.published_on, b.published_precision.as_ref()),
+        publisher: b.publisher,
+        edition: b.edition,
+        isbn: b.isbn,
+        pages: b.pages,
+    }
+}
+
+/// The live books with `ids`, in that order, with their authors.
+async fn citations(db: &DatabaseConnection, ids: &[i32]) -> Result<Vec<Citation>, ErrorResponse> {
+    let mut found = Book::find()
+        .filter(book::Column::Id.is_in(ids.to_vec()))
+        .filter(book::Column::DeletedAt.is_null())
+        .find_also_related(Author)
+        .all(db)
+        .await?;
+
+    let missing = ids
+        .iter()
+        .filter(|id| !found.iter().any(|(b, a)| b.id == **id && a.is_some()))
+        .map(i32::to_string)
+        .collect::<Vec<_>>();
+
+    if !missing.is_empty() {
+        return Err(ErrorResponse::new(
+            Status::NotFound,
+            match (ids.len(), missing.as_slice()) {
+                (1, _) => "No book found with the specified ID.".to_string(),
+                (_, [id]) => format!("No book found with the ID {}.", id),
+                _ => format!("No books found with the IDs {}.", missing.join(", ")),
+            },
+        ));
+    }
+
+    found.sort_by_key(|(b, _)| ids.iter().position(|id| *id == b.id));
+
+    Ok(found
+        .into_iter()
+        .filter_map(|(b, a)| a.map(|a| citation(b, a)))
+        .collect())
+}
+
+fn cited(format: CitationFormat, citations: &[Citation]) -> Cited {
+    Cited(format.render(citations), ContentType(format.media_type()))
+}
+
+/// Cites a book.
+#[utoipa::path(
+    context_path = "/v1/books",
+    tag = "books",
+    params(
+        ("format" = Option<String>, Query, description = "`bibtex`, `ris`, `csl-json` or `apa`. Defaults to the format preferred by the Accept header, or BibTeX.")
+    ),
+    responses(
+        (status = 200, description = "The book's citation.", content(
+            (String = "application/x-bibtex"),
+            (String = "application/x-research-info-systems"),
+            (String = "application/vnd.citationstyles.csl+json"),
+            (String = "text/plain")
+        )),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 406, response = Problem),
+        (status = 422, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[get("/<id>/cite?<format>")]
+pub async fn cite(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    accept: Option<&Accept>,
+    id: i32,
+    format: Option<&str>,
+) -> Result<Cited, ErrorResponse> {
+    let format = self::format(format, accept)?;
+    let citations = citations(db, &[id]).await?;
+
+    Ok(cited(format, &citations))
+}
+
+/// Cites several books at once, in the order given.
+#[utoipa::path(
+    context_path = "/v1/books",
+    tag = "books",
+    params(
+        ("ids" = String, Query, description = "Comma-separated book IDs, e.g. `1,5,2`."),
+        ("format" = Option<String>, Query, description = "As for a single book.")
+    ),
+    responses(
+        (status = 200, description = "The books' citations: one entry each, or a CSL-JSON array.", content(
+            (String = "application/x-bibtex"),
+            (String = "application/x-research-info-systems"),
+            (String = "application/vnd.citationstyles.csl+json"),
+            (String = "text/plain")
+        )),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 406, response = Problem),
+        (status = 413, response = Problem),
+        (status = 422, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[get("/cite?<ids>&<format>")]
+pub async fn cite_many(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    _user: AuthenticatedUser,
+    accept: Option<&Accept>,
+    ids: &str,
+    format: Option<&str>,
+) -> Result<Cited, ErrorResponse> {
+    let format = self::format(format, accept)?;
+
+    let mut ids = ids
+        .split(',')
+        .map(|id| id.trim().parse::<i32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| ErrorResponse::field("ids", "Must be a comma-separated list of book IDs."))?;
+
+    // Repeats are cited once, where they first appear.
+    let mut seen = HashSet::new();
+    ids.retain(|id| seen.insert(*id));
+
+    if ids.len() > config.bulk_limit {
+        return Err(ErrorResponse::new(
+            Status::PayloadTooLarge,
+            format!(
+                "At most {} books may be cited at once, this request has {}.",
+                config.bulk_limit,
+                ids.len()
+            ),
+        ));
+    }
+
+    let citations = citations(db, &ids).await?;
+
+    Ok(cited(format, &citations))
+}
@@ -0,0 +1,141 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use rocket::{
+    http::{Header, Status},
+    request::{FromRequest, Outcome, Request},
+    response::{self, Responder},
+};
+use sea_orm::DbErr;
+use std::convert::Infallible;
+
+use super::ErrorResponse;
+use crate::entities::{author, book};
+
+/// A record whose `ETag` is derived from its `version` column.
+pub trait Versioned {
+    fn etag(&self) -> String;
+}
+
+impl Versioned for book::Model {
+    fn etag(&self) -> String {
+        format!("\"book-{}-v{}\"", self.id, self.version)
+    }
+}
+
+impl Versioned for author::Model {
+    fn etag(&self) -> String {
+        format!("\"author-{}-v{}\"", self.id, self.version)
+    }
+}
+
+/// The `If-Match` and `If-None-Match` request headers.
+pub struct Preconditions {
+    if_match: Option<String>,
+    if_none_match: Option<String>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Preconditions {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(Preconditions {
+            if_match: req.headers().get_one("If-Match").map(str::to_owned),
+            if_none_match: req.headers().get_one("If-None-Match").map(str::to_owned),
+        })
+    }
+}
+
+impl Preconditions {
+    /// Preconditions given other than by headers, e.g. as a GraphQL argument.
+    pub fn if_match(etag: Option<String>) -> Self {
+        Self {
+            if_match: etag,
+            if_none_match: None,
+        }
+    }
+
+    /// Fails with 412 Precondition Failed if `If-Match` does not match `etag`,
+    /// or with 428 Precondition Required if it is missing and `required`.
+    pub fn check_if_match(&self, etag: &str, required: bool) -> Result<(), ErrorResponse> {
+        match &self.if_match {
+            None if required => Err(ErrorResponse::new(
+                Status::PreconditionRequired,
+                "An If-Match header with the record's ETag is required.".to_string(),
+            )),
+            None => Ok(()),
+            // If-Match uses the strong comparison, so weak tags never match.
+            Some(tags) if matches(tags, etag, false) => Ok(()),
+            Some(_) => Err(precondition_failed()),
+        }
+    }
+
+    /// Whether `If-None-Match` matches `etag`, i.e. the client's copy is current.
+    pub fn is_not_modified(&self, etag: &str) -> bool {
+        match &self.if_none_match {
+            Some(tags) => matches(tags, etag, true),
+            None => false,
+        }
+    }
+}
+
+fn matches(tags: &str, etag: &str, weak: bool) -> bool {
+    tags.split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag == etag || (weak && tag.strip_prefix("W/") == Some(etag)))
+}
+
+pub fn precondition_failed() -> ErrorResponse {
+    ErrorResponse::new(
+        Status::PreconditionFailed,
+        "The record has been modified since it was fetched. Fetch it again and retry.".to_string(),
+    )
+}
+
+/// Maps the `RecordNotUpdated` of a version-guarded update to 412.
+pub fn guarded(err: DbErr) -> ErrorResponse {
+    match err {
+        DbErr::RecordNotUpdated => precondition_failed(),
+        err => err.into(),
+    }
+}
+
+/// A response carrying an `ETag`, without a body for 304 Not Modified.
+pub struct Tagged<T> {
+    body: Option<T>,
+    etag: String,
+}
+
+impl<T> Tagged<T> {
+    pub fn new(body: T, record: &impl Versioned) -> Self {
+        Self {
+            body: Some(body),
+            etag: record.etag(),
+        }
+    }
+
+    pub fn not_modified(record: &impl Versioned) -> Self {
+        Self {
+            body: None,
+            etag: record.etag(),
+        }
+    }
+}
+
+impl<'r, 'o: 'r, T: Responder<'r, 'o>> Responder<'r, 'o> for Tagged<T> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'o> {
+        let mut res = match self.body {
+            Some(body) => body.respond_to(req)?,
+            None => rocket::Response::build().finalize(),
+        };
+
+        res.set_header(Header::new("ETag", self.etag));
+
+        Ok(res)
+    }
+}
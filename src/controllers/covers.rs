@@ -0,0 +1,340 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use rocket::{
+    form::Form,
+    fs::TempFile,
+    http::{ContentType, Header, Status},
+    request::Request,
+    response::{self, Responder},
+    serde::json::Json,
+    tokio::{io::AsyncReadExt, task},
+    State,
+};
+use sea_orm::{prelude::DateTimeUtc, *};
+use sha2::{Digest, Sha256};
+use std::{sync::Arc, time::SystemTime};
+use utoipa::ToSchema;
+
+use super::{
+    books::{find_book, ResBook},
+    conditional::{guarded, Preconditions, Tagged, Versioned},
+    error::Problem,
+    v1, ErrorResponse, Response, SuccessResponse,
+};
+use crate::auth::AuthenticatedUser;
+use crate::covers::{self, THUMBNAIL_SIZES};
+use crate::entities::{book, prelude::Book, sea_orm_active_enums::RevisionAction};
+use crate::revisions;
+use crate::storage::{Storage, StorageError};
+use crate::AppConfig;
+
+impl From<StorageError> for ErrorResponse {
+    fn from(err: StorageError) -> Self {
+        ErrorResponse::internal(err)
+    }
+}
+
+#[derive(FromForm, ToSchema)]
+pub struct ReqCover<'r> {
+    #[schema(value_type = String, format = Binary)]
+    cover: TempFile<'r>,
+}
+
+/// A stored cover image, served with caching headers.
+pub struct CoverImage {
+    bytes: Vec<u8>,
+    content_type: ContentType,
+    etag: String,
+}
+
+impl<'r> Responder<'r, 'static> for CoverImage {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let not_modified = req
+            .headers()
+            .get("If-None-Match")
+            .flat_map(|v| v.split(','))
+            .any(|tag| tag.trim() == self.etag || tag.trim() == "*");
+
+        let mut res = if not_modified {
+            rocket::Response::build()
+                .status(Status::NotModified)
+                .finalize()
+        } else {
+            let mut res = self.bytes.respond_to(req)?;
+            res.set_header(self.content_type);
+            res
+        };
+
+        res.set_header(Header::new("Cache-Control", "public, max-age=86400"));
+        res.set_header(Header::new("ETag", self.etag));
+
+        Ok(res)
+    }
+}
+
+impl CoverImage {
+    fn new(bytes: Vec<u8>) -> Self {
+        let content_type = match covers::detect_format(&bytes) {
+            Some(image::ImageFormat::Png) => ContentType::PNG,
+            Some(image::ImageFormat::WebP) => ContentType::new("image", "webp"),
+            _ => ContentType::JPEG,
+        };
+
+        let etag = format!("\"{}\"", hex::encode(Sha256::digest(&bytes)));
+
+        Self {
+            bytes,
+            content_type,
+            etag,
+        }
+    }
+}
+
+/// Uploads a book's cover.
+#[utoipa::path(
+    context_path = "/v1/books",
+    tag = "covers",
+    request_body(content = ReqCover, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, body = ResBook, headers(("ETag" = String, description = "The version of the record."))),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 412, response = Problem),
+        (status = 413, response = Problem),
+        (status = 415, response = Problem),
+        (status = 422, response = Problem),
+        (status = 428, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[put("/<id>/cover", data = "<req_cover>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn upload(
+    db: &State<DatabaseConnection>,
+    storage: &State<Arc<dyn Storage>>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    preconditions: Preconditions,
+    id: i32,
+    req_cover: Form<ReqCover<'_>>,
+) -> Response<Tagged<Json<ResBook>>> {
+    let db = db as &DatabaseConnection;
+    let storage = storage.inner().as_ref();
+
+    let book = find_book(db, id, false).await?;
+    preconditions.check_if_match(&book.etag(), config.require_if_match)?;
+
+    let file = &req_cover.cover;
+
+    if file.len() > config.cover_max_size {
+        return Err(ErrorResponse::new(
+            Status::PayloadTooLarge,
+            format!(
+                "The cover image must not exceed {} bytes.",
+                config.cover_max_size
+            ),
+        ));
+    }
+
+    let declared_image = file
+        .content_type()
+        .map(|ct| ct.top() == "image" && ["jpeg", "png", "webp"].contains(&ct.sub().as_str()))
+        .unwrap_or(false);
+
+    let mut bytes = vec![];
+    file.open()
+        .await
+        .map_err(ErrorResponse::internal)?
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(ErrorResponse::internal)?;
+
+    if !declared_image || covers::detect_format(&bytes).is_none() {
+        return Err(ErrorResponse::new(
+            Status::UnsupportedMediaType,
+            "The cover must be a JPEG, PNG or WebP image.".to_string(),
+        ));
+    }
+
+    let thumbnails = {
+        let bytes = bytes.clone();
+        task::spawn_blocking(move || covers::thumbnails(&bytes))
+            .await
+            .map_err(ErrorResponse::internal)?
+            .map_err(|e| {
+                ErrorResponse::field("cover", format!("The image could not be read: {}", e))
+            })?
+    };
+
+    let before = book.clone();
+    let mut book: book::ActiveModel = book.into();
+    book.cover = Set(format!("{}/books/{}/cover", v1::PREFIX, id));
+    book.updated_at = Set(DateTimeUtc::from(SystemTime::now()));
+    book.version = Set(before.version + 1);
+
+    let txn = db.begin().await?;
+
+    // Only applies if nobody else updated the record since it was read.
+    let book = Book::update(book)
+        .filter(book::Column::Version.eq(before.version))
+        .exec(&txn)
+        .await
+        .map_err(guarded)?;
+    revisions::record(
+        &txn,
+        user.id,
+        RevisionAction::Update,
+        Some(&before),
+        Some(&book),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    // Stored only once the update has gone through, so a rejected upload
+    // leaves the current cover in place.
+    let content_type = file.content_type().unwrap().to_string();
+    storage
+        .put(&covers::original_key(id), bytes, &content_type)
+        .await?;
+
+    for (size, thumb) in thumbnails {
+        storage
+            .put(&covers::thumbnail_key(id, size), thumb, "image/jpeg")
+            .await?;
+    }
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Tagged::new(Json(ResBook::from(&book)), &book),
+    )))
+}
+
+/// Removes a book's cover.
+#[utoipa::path(
+    context_path = "/v1/books",
+    tag = "covers",
+    responses(
+        (status = 200, body = ResBook, headers(("ETag" = String, description = "The version of the record."))),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 412, response = Problem),
+        (status = 428, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[delete("/<id>/cover")]
+pub async fn delete(
+    db: &State<DatabaseConnection>,
+    storage: &State<Arc<dyn Storage>>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    preconditions: Preconditions,
+    id: i32,
+) -> Response<Tagged<Json<ResBook>>> {
+    let db = db as &DatabaseConnection;
+    let storage = storage.inner().as_ref();
+
+    let book = find_book(db, id, false).await?;
+    preconditions.check_if_match(&book.etag(), config.require_if_match)?;
+
+    let before = book.clone();
+    let mut book: book::ActiveModel = book.into();
+    book.cover = Set("".to_string());
+    book.updated_at = Set(DateTimeUtc::from(SystemTime::now()));
+    book.version = Set(before.version + 1);
+
+    let txn = db.begin().await?;
+
+    // Only applies if nobody else updated the record since it was read.
+    let book = Book::update(book)
+        .filter(book::Column::Version.eq(before.version))
+        .exec(&txn)
+        .await
+        .map_err(guarded)?;
+    revisions::record(
+        &txn,
+        user.id,
+        RevisionAction::Update,
+        Some(&before),
+        Some(&book),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    // Removed only once the update has gone through, so a rejected delete
+    // leaves the cover in place.
+    storage.delete(&covers::original_key(id)).await?;
+
+    for (size, _) in THUMBNAIL_SIZES {
+        storage.delete(&covers::thumbnail_key(id, size)).await?;
+    }
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Tagged::new(Json(ResBook::from(&book)), &book),
+    )))
+}
+
+/// Gets a book's cover.
+#[utoipa::path(
+    context_path = "/v1/books",
+    tag = "covers",
+    responses(
+        (status = 200, description = "The cover image.", content(("image/jpeg"), ("image/png"), ("image/webp"))),
+        (status = 304, description = "The cover is unchanged."),
+        (status = 404, response = Problem)
+    )
+)]
+#[get("/<id>/cover")]
+pub async fn original(
+    storage: &State<Arc<dyn Storage>>,
+    id: i32,
+) -> Result<CoverImage, ErrorResponse> {
+    load(storage.inner().as_ref(), &covers::original_key(id)).await
+}
+
+/// Gets a thumbnail of a book's cover.
+#[utoipa::path(
+    context_path = "/v1/books",
+    tag = "covers",
+    responses(
+        (status = 200, description = "The thumbnail.", content_type = "image/jpeg"),
+        (status = 304, description = "The thumbnail is unchanged."),
+        (status = 404, response = Problem)
+    )
+)]
+#[get("/<id>/cover/<size>")]
+pub async fn thumbnail(
+    storage: &State<Arc<dyn Storage>>,
+    id: i32,
+    size: &str,
+) -> Result<CoverImage, ErrorResponse> {
+    if !THUMBNAIL_SIZES.iter().any(|&(name, _)| name == size) {
+        return Err(ErrorResponse::new(
+            Status::NotFound,
+            format!(
+                "Unknown cover size. Available sizes: {}.",
+                THUMBNAIL_SIZES.map(|(name, _)| name).join(", ")
+            ),
+        ));
+    }
+
+    load(storage.inner().as_ref(), &covers::thumbnail_key(id, size)).await
+}
+
+async fn load(storage: &dyn Storage, key: &str) -> Result<CoverImage, ErrorResponse> {
+    match storage.get(key).await? {
+        Some(bytes) => Ok(CoverImage::new(bytes)),
+        None => Err(ErrorResponse::new(
+            Status::NotFound,
+            "No cover found for the specified book.".to_string(),
+        )),
+    }
+}
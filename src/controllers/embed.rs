@@ -0,0 +1,151 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use rocket::{http::Status, serde::Serialize};
+use sea_orm::*;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+use super::{auth::ResUser, ErrorResponse};
+use crate::entities::{author_alias, book, prelude::*, user};
+
+/// The record types `?fields[...]` may name.
+const KINDS: [&str; 3] = ["book", "author", "user"];
+
+/// The `include` and `fields` query parameters, e.g.
+/// `?include=author,user&fields[book]=title,published&fields[author]=lastname`.
+pub struct Embed<'a> {
+    include: Vec<&'a str>,
+    fields: HashMap<&'a str, Vec<&'a str>>,
+}
+
+impl<'a> Embed<'a> {
+    /// Parses the parameters, allowing only `relations` to be included.
+    pub fn parse(
+        include: Option<&'a str>,
+        fields: &HashMap<&'a str, &'a str>,
+        relations: &[&str],
+    ) -> Result<Self, ErrorResponse> {
+        let include = list(include.unwrap_or(""));
+
+        if let Some(r) = include.iter().find(|r| !relations.contains(r)) {
+            return Err(ErrorResponse::new(
+                Status::BadRequest,
+                format!(
+                    "Cannot include \"{}\". Expected one of: {}.",
+                    r,
+                    relations.join(", ")
+                ),
+            ));
+        }
+
+        if let Some(kind) = fields.keys().find(|k| !KINDS.contains(k)) {
+            return Err(ErrorResponse::new(
+                Status::BadRequest,
+                format!(
+                    "Unknown type \"{}\" in fields. Expected one of: {}.",
+                    kind,
+                    KINDS.join(", ")
+                ),
+            ));
+        }
+
+        Ok(Self {
+            include,
+            fields: fields.iter().map(|(k, v)| (*k, list(v))).collect(),
+        })
+    }
+
+    pub fn includes(&self, relation: &str) -> bool {
+        self.include.contains(&relation)
+    }
+
+    /// Whether any relation is included.
+    pub fn includes_any(&self) -> bool {
+        !self.include.is_empty()
+    }
+
+    /// Serializes `record`, keeping only its `id` and the fields requested for
+    /// `kind`, or every field if none were. Unknown field names are ignored.
+    pub fn select<T: Serialize>(
+        &self,
+        kind: &str,
+        record: &T,
+    ) -> Result<Map<String, Value>, ErrorResponse> {
+        let mut map = match serde_json::to_value(record).map_err(ErrorResponse::internal)? {
+            Value::Object(map) => map,
+            _ => return Err(ErrorResponse::internal("records serialize to objects")),
+        };
+
+        if let Some(fields) = self.fields.get(kind) {
+            map.retain(|k, _| k == "id" || fields.contains(&k.as_str()));
+        }
+
+        Ok(map)
+    }
+}
+
+fn list(s: &str) -> Vec<&str> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Loads the given users in a single query.
+pub async fn users(
+    db: &DatabaseConnection,
+    ids: impl IntoIterator<Item = i32>,
+) -> Result<HashMap<i32, ResUser>, DbErr> {
+    Ok(User::find()
+        .filter(user::Column::Id.is_in(ids.into_iter().collect::<Vec<_>>()))
+        .all(db)
+        .await?
+        .iter()
+        .map(|u| (u.id, ResUser::from(u)))
+        .collect())
+}
+
+/// Loads the live books of the given authors in a single query, most recently
+/// updated first.
+pub async fn books_by_author(
+    db: &DatabaseConnection,
+    author_ids: impl IntoIterator<Item = i32>,
+) -> Result<HashMap<i32, Vec<book::Model>>, DbErr> {
+    let mut books = HashMap::<i32, Vec<book::Model>>::new();
+
+    for b in Book::find()
+        .filter(book::Column::AuthorId.is_in(author_ids.into_iter().collect::<Vec<_>>()))
+        .filter(book::Column::DeletedAt.is_null())
+        .order_by_desc(book::Column::UpdatedAt)
+        .all(db)
+        .await?
+    {
+        books.entry(b.author_id).or_default().push(b);
+    }
+
+    Ok(books)
+}
+
+/// Loads the aliases of the given authors in a single query, oldest first.
+pub async fn aliases_by_author(
+    db: &DatabaseConnection,
+    author_ids: impl IntoIterator<Item = i32>,
+) -> Result<HashMap<i32, Vec<author_alias::Model>>, DbErr> {
+    let mut aliases = HashMap::<i32, Vec<author_alias::Model>>::new();
+
+    for a in AuthorAlias::find()
+        .filter(author_alias::Column::AuthorId.is_in(author_ids.into_iter().collect::<Vec<_>>()))
+        .order_by_asc(author_alias::Column::Id)
+        .all(db)
+        .await?
+    {
+        aliases.entry(a.author_id).or_default().push(a);
+    }
+
+    Ok(aliases)
+}
@@ -0,0 +1,303 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use rocket::{
+    http::Status,
+    serde::{json::Json, Serialize},
+    State,
+};
+use sea_orm::*;
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+
+use super::{
+    authors::{self, ReqAuthor},
+    books::{self, ReqBook, ResBook},
+    conditional::{Preconditions, Tagged, Versioned},
+    error::Problem,
+    ErrorResponse, Response, SuccessResponse,
+};
+use crate::auth::AuthenticatedUser;
+use crate::entities::{book, prelude::*};
+use crate::idempotency::Idempotency;
+use crate::isbn::Isbn;
+use crate::openlibrary::{self, Metadata};
+use crate::AppConfig;
+
+/// The fields enrichment may change. `author_bio` is the bio of the book's author.
+const FIELDS: [&str; 6] = [
+    "title",
+    "published",
+    "publisher",
+    "edition",
+    "pages",
+    "author_bio",
+];
+
+#[derive(Serialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct ResChange {
+    pub field: String,
+    #[schema(value_type = Object)]
+    pub current: Value,
+    #[schema(value_type = Object)]
+    pub proposed: Value,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct ResEnrichment {
+    /// The Open Library edition the changes come from, e.g. `/books/OL7353617M`.
+    pub source: String,
+    /// The fields Open Library has other values for.
+    pub changes: Vec<ResChange>,
+    /// The fields changed by this request, none unless `apply` is set.
+    pub applied: Vec<String>,
+    pub book: ResBook,
+}
+
+/// The Open Library edition with the ISBN of `book`.
+async fn metadata<C: ConnectionTrait>(
+    db: &C,
+    book: &book::Model,
+) -> Result<Metadata, ErrorResponse> {
+    let isbn = book
+        .isbn
+        .as_deref()
+        .and_then(|i| i.parse::<Isbn>().ok())
+        .ok_or_else(|| ErrorResponse::field("isbn", "The book has no ISBN to look up."))?;
+
+    openlibrary::lookup(db, &isbn).await?.ok_or_else(|| {
+        ErrorResponse::new(
+            Status::NotFound,
+            format!("No Open Library edition found with the ISBN {}.", isbn),
+        )
+    })
+}
+
+/// The fields of `book` and its author's bio that differ from `metadata`.
+fn changes(book: &ResBook, bio: Option<&str>, metadata: &Metadata) -> Vec<ResChange> {
+    let proposed = [
+        ("title", json!(book.title), Some(json!(metadata.title))),
+        (
+            "published",
+            json!(book.published),
+            metadata.published.map(|p| json!(p.to_string())),
+        ),
+        (
+            "publisher",
+            json!(book.publisher),
+            metadata.publisher.as_ref().map(|p| json!(p)),
+        ),
+        (
+            "edition",
+            json!(book.edition),
+            metadata.edition.as_ref().map(|e| json!(e)),
+        ),
+        ("pages", json!(book.pages), metadata.pages.map(|p| json!(p))),
+        (
+            "author_bio",
+            json!(bio),
+            bio.and(metadata.author_bio.as_ref()).map(|b| json!(b)),
+        ),
+    ];
+
+    proposed
+        .into_iter()
+        .filter_map(|(field, current, proposed)| match proposed {
+            Some(proposed) if proposed != current => Some(ResChange {
+                field: field.to_string(),
+                current,
+                proposed,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Compares a book with its Open Library edition, found by ISBN, and
+/// optionally applies the differences.
+///
+/// Without `apply`, only the proposed changes are returned. With `apply=true`,
+/// they are saved, or only those named in `fields`. `author_bio` changes the
+/// author's bio, for every book by them.
+#[utoipa::path(
+    context_path = "/v1/books",
+    tag = "books",
+    params(
+        ("apply" = Option<bool>, Query, description = "Saves the changes rather than only proposing them."),
+        ("fields" = Option<String>, Query, description = "Comma-separated fields to apply, e.g. `published,pages`. Defaults to all: `title`, `published`, `publisher`, `edition`, `pages`, `author_bio`."),
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response replayed.")
+    ),
+    responses(
+        (status = 200, body = ResEnrichment, headers(("ETag" = String, description = "The version of the book."))),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 412, response = Problem),
+        (status = 422, response = Problem),
+        (status = 428, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[post("/<id>/enrich?<apply>&<fields>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn enrich(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    preconditions: Preconditions,
+    _idempotency: Idempotency,
+    id: i32,
+    apply: Option<bool>,
+    fields: Option<&str>,
+) -> Response<Tagged<Json<ResEnrichment>>> {
+    let db = db as &DatabaseConnection;
+
+    let selected = match fields {
+        Some(fields) => {
+            let fields = fields.split(',').map(str::trim).collect::<Vec<_>>();
+
+            if let Some(unknown) = fields.iter().find(|f| !FIELDS.contains(f)) {
+                return Err(ErrorResponse::field(
+                    "fields",
+                    format!(
+                        "Unknown field {}. The fields are {}.",
+                        unknown,
+                        FIELDS.join(", ")
+                    ),
+                ));
+            }
+
+            fields
+        }
+        None => FIELDS.to_vec(),
+    };
+
+    let book = books::find_book(db, id, false).await?;
+    let author = Author::find_by_id(book.author_id).one(db).await?;
+    let metadata = metadata(db, &book).await?;
+
+    let changes = changes(
+        &ResBook::from(&book),
+        author.as_ref().map(|a| a.bio.as_str()),
+        &metadata,
+    );
+
+    if apply != Some(true) {
+        return Ok(SuccessResponse((
+            Status::Ok,
+            Tagged::new(
+                Json(ResEnrichment {
+                    source: metadata.source,
+                    changes,
+                    applied: vec![],
+                    book: ResBook::from(&book),
+                }),
+                &book,
+            ),
+        )));
+    }
+
+    preconditions.check_if_match(&book.etag(), config.require_if_match)?;
+
+    let applied = changes
+        .iter()
+        .filter(|c| selected.contains(&c.field.as_str()))
+        .collect::<Vec<_>>();
+
+    let mut req_book = ReqBook::from(&book);
+    let mut bio = None;
+
+    for change in &applied {
+        let text = change.proposed.as_str().map(str::to_string);
+
+        match change.field.as_str() {
+            "title" => req_book.title = text.unwrap_or_default(),
+            "published" => req_book.published = text,
+            "publisher" => req_book.publisher = text,
+            "edition" => req_book.edition = text,
+            "pages" => req_book.pages = change.proposed.as_i64().map(|p| p as i32),
+            _ => bio = text,
+        }
+    }
+
+    let txn = db.begin().await?;
+
+    let book = match applied.iter().any(|c| c.field != "author_bio") {
+        true => books::save(&txn, config, &user, &preconditions, book, &req_book).await?,
+        false => book,
+    };
+
+    if let (Some(bio), Some(author)) = (bio, author) {
+        let req_author = ReqAuthor {
+            bio,
+            ..ReqAuthor::from(&author)
+        };
+        let preconditions = Preconditions::if_match(Some(author.etag()));
+
+        authors::save(&txn, config, &user, &preconditions, author, &req_author).await?;
+    }
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Tagged::new(
+            Json(ResEnrichment {
+                source: metadata.source,
+                applied: applied.iter().map(|c| c.field.clone()).collect(),
+                changes,
+                book: ResBook::from(&book),
+            }),
+            &book,
+        ),
+    )))
+}
+
+/// Fills the blank title, publication date, publisher, edition and pages of
+/// `req_book` from the Open Library edition with its ISBN, if there is one.
+/// Values already given are kept.
+pub(super) async fn autofill<C: ConnectionTrait>(
+    db: &C,
+    req_book: &mut ReqBook,
+) -> Result<(), ErrorResponse> {
+    let Some(isbn) = req_book
+        .isbn
+        .as_deref()
+        .and_then(|i| i.parse::<Isbn>().ok())
+    else {
+        return Ok(());
+    };
+
+    let Some(metadata) = openlibrary::lookup(db, &isbn).await? else {
+        return Ok(());
+    };
+
+    let blank = |value: &Option<String>| value.as_deref().is_none_or(|v| v.trim().is_empty());
+
+    if req_book.title.trim().is_empty() {
+        req_book.title = metadata.title;
+    }
+
+    if blank(&req_book.published) {
+        req_book.published = metadata.published.map(|p| p.to_string());
+    }
+
+    if blank(&req_book.publisher) {
+        req_book.publisher = metadata.publisher;
+    }
+
+    if blank(&req_book.edition) {
+        req_book.edition = metadata.edition;
+    }
+
+    if req_book.pages.is_none() {
+        req_book.pages = metadata.pages;
+    }
+
+    Ok(())
+}
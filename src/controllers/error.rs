@@ -0,0 +1,281 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use async_graphql::ErrorExtensions;
+use rocket::{
+    http::{ContentType, Header, Status},
+    request::Request,
+    response::{self, Responder},
+    serde::{json::serde_json, Serialize},
+};
+use sea_orm::{DbErr, RuntimeErr, SqlxError, SqlxMySqlError};
+use std::{fmt::Display, io::Cursor};
+use utoipa::{ToResponse, ToSchema};
+
+use crate::fairings::request_id::CurrentRequestId;
+
+/// MySQL error numbers we translate into client errors.
+const ER_DUP_ENTRY: u16 = 1062;
+const ER_ROW_IS_REFERENCED: u16 = 1451;
+const ER_NO_REFERENCED_ROW: u16 = 1452;
+
+/// An error reported to the client as an RFC 7807 `application/problem+json` body.
+#[derive(Debug)]
+pub struct ErrorResponse {
+    status: Status,
+    kind: Option<&'static str>,
+    detail: String,
+    errors: Vec<FieldError>,
+    /// Logged server-side, never sent to the client.
+    internal: Option<String>,
+    /// Sent as the `Location` header.
+    location: Option<String>,
+}
+
+/// A problem with a single field of the request.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Why a request or data guard failed. Rocket doesn't hand guard errors to
+/// catchers, so guards record them here for the catchers to report.
+pub struct GuardFailure(Option<String>);
+
+impl GuardFailure {
+    /// Records `reason`, unless an earlier guard already failed.
+    pub fn record(req: &Request<'_>, reason: impl Into<String>) {
+        req.local_cache(|| GuardFailure(Some(reason.into())));
+    }
+
+    pub fn of<'r>(req: &'r Request<'_>) -> Option<&'r str> {
+        req.local_cache(|| GuardFailure(None)).0.as_deref()
+    }
+}
+
+/// An RFC 7807 problem, as sent for every error.
+#[derive(Serialize, ToSchema, ToResponse)]
+#[serde(crate = "rocket::serde")]
+#[response(content_type = "application/problem+json")]
+pub struct Problem<'a> {
+    #[serde(rename = "type")]
+    kind: &'a str,
+    title: &'a str,
+    status: u16,
+    detail: &'a str,
+    instance: String,
+    request_id: &'a str,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    errors: &'a [FieldError],
+}
+
+/// The error of one operation in a batch, reported in the batch's response
+/// rather than as a response of its own.
+#[derive(Serialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct ItemError {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    title: &'static str,
+    pub status: u16,
+    detail: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<FieldError>,
+}
+
+impl ErrorResponse {
+    pub fn new(status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            status,
+            kind: None,
+            detail: detail.into(),
+            errors: vec![],
+            internal: None,
+            location: None,
+        }
+    }
+
+    /// A 422 listing the fields that failed validation.
+    pub fn validation(errors: Vec<FieldError>) -> Self {
+        Self {
+            kind: Some("/problems/validation-error"),
+            errors,
+            ..Self::new(
+                Status::UnprocessableEntity,
+                "One or more fields are invalid.",
+            )
+        }
+    }
+
+    /// A 422 for a single invalid field.
+    pub fn field(field: &str, message: impl Into<String>) -> Self {
+        Self::validation(vec![FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        }])
+    }
+
+    /// A 500 whose cause is logged but not disclosed.
+    pub fn internal(err: impl Display) -> Self {
+        Self {
+            internal: Some(err.to_string()),
+            ..Self::new(Status::InternalServerError, "An internal error occurred.")
+        }
+    }
+
+    /// A 308 to where the resource now is, e.g. the author a merged author
+    /// was merged into.
+    pub fn moved(location: String, detail: impl Into<String>) -> Self {
+        Self {
+            location: Some(location),
+            ..Self::new(Status::PermanentRedirect, detail)
+        }
+    }
+
+    /// The error as messages, by field where it has them, for reports that
+    /// aren't a response of their own.
+    pub fn into_messages(self) -> Vec<(Option<String>, String)> {
+        if let Some(internal) = &self.internal {
+            error!("Import row failed: {}", internal);
+        }
+
+        if self.errors.is_empty() {
+            return vec![(None, self.detail)];
+        }
+
+        self.errors
+            .into_iter()
+            .map(|e| (Some(e.field), e.message))
+            .collect()
+    }
+
+    fn with_kind(self, kind: &'static str) -> Self {
+        Self {
+            kind: Some(kind),
+            ..self
+        }
+    }
+}
+
+fn mysql_error_number(err: &DbErr) -> Option<u16> {
+    match err {
+        DbErr::Exec(RuntimeErr::SqlxError(SqlxError::Database(e)))
+        | DbErr::Query(RuntimeErr::SqlxError(SqlxError::Database(e))) => {
+            e.try_downcast_ref::<SqlxMySqlError>().map(|e| e.number())
+        }
+        _ => None,
+    }
+}
+
+/// Whether `err` is a unique constraint violation.
+pub fn is_duplicate_entry(err: &DbErr) -> bool {
+    mysql_error_number(err) == Some(ER_DUP_ENTRY)
+}
+
+impl From<DbErr> for ErrorResponse {
+    fn from(err: DbErr) -> Self {
+        match (mysql_error_number(&err), &err) {
+            (Some(ER_DUP_ENTRY), _) => ErrorResponse::new(
+                Status::Conflict,
+                "A record with the same unique value already exists.",
+            )
+            .with_kind("/problems/duplicate"),
+            (Some(ER_ROW_IS_REFERENCED), _) => ErrorResponse::new(
+                Status::Conflict,
+                "The record is still referenced by other records.",
+            )
+            .with_kind("/problems/still-referenced"),
+            (Some(ER_NO_REFERENCED_ROW), _) => ErrorResponse::new(
+                Status::UnprocessableEntity,
+                "The request references a record that does not exist.",
+            )
+            .with_kind("/problems/invalid-reference"),
+            (_, DbErr::RecordNotFound(_)) => {
+                ErrorResponse::new(Status::NotFound, "The requested record does not exist.")
+            }
+            _ => ErrorResponse::internal(err),
+        }
+    }
+}
+
+impl From<ErrorResponse> for ItemError {
+    fn from(err: ErrorResponse) -> Self {
+        if let Some(internal) = &err.internal {
+            error!("Batch operation failed: {}", internal);
+        }
+
+        Self {
+            kind: err.kind.unwrap_or("about:blank"),
+            title: err.status.reason().unwrap_or("Unknown Error"),
+            status: err.status.code,
+            detail: err.detail,
+            errors: err.errors,
+        }
+    }
+}
+
+/// Reports the error in the `extensions` of a GraphQL error instead.
+impl From<ErrorResponse> for async_graphql::Error {
+    fn from(err: ErrorResponse) -> Self {
+        if let Some(internal) = &err.internal {
+            error!("GraphQL operation failed: {}", internal);
+        }
+
+        let errors = serde_json::to_value(&err.errors)
+            .ok()
+            .and_then(|v| async_graphql::Value::from_json(v).ok());
+
+        async_graphql::Error::new(err.detail).extend_with(|_, e| {
+            e.set("status", err.status.code);
+            e.set("type", err.kind.unwrap_or("about:blank"));
+
+            if let Some(errors) = errors.filter(|_| !err.errors.is_empty()) {
+                e.set("errors", errors);
+            }
+        })
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ErrorResponse {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let request_id = CurrentRequestId::of(req);
+
+        if let Some(internal) = &self.internal {
+            error!(
+                "[{}] {} {} failed: {}",
+                request_id,
+                req.method(),
+                req.uri(),
+                internal
+            );
+        }
+
+        let body = serde_json::to_string(&Problem {
+            kind: self.kind.unwrap_or("about:blank"),
+            title: self.status.reason().unwrap_or("Unknown Error"),
+            status: self.status.code,
+            detail: &self.detail,
+            instance: req.uri().path().to_string(),
+            request_id,
+            errors: &self.errors,
+        })
+        .map_err(|_| Status::InternalServerError)?;
+
+        let mut res = rocket::Response::build();
+
+        if let Some(location) = self.location {
+            res.header(Header::new("Location", location));
+        }
+
+        res.status(self.status)
+            .header(ContentType::new("application", "problem+json"))
+            .sized_body(body.len(), Cursor::new(body))
+            .ok()
+    }
+}
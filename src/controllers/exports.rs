@@ -0,0 +1,414 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use rocket::{
+    futures::Stream,
+    http::{Accept, Status},
+    serde::Serialize,
+    State,
+};
+use sea_orm::{prelude::DateTimeUtc, *};
+
+use super::{authors, books, error::Problem, ErrorResponse};
+use crate::auth::AuthenticatedUser;
+use crate::entities::{author, book, prelude::*};
+use crate::exports::{self, Export, ExportFormat, Exportable};
+use crate::marc;
+use crate::onix;
+use crate::publication::PublicationDate;
+use crate::AppConfig;
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ExportBook {
+    id: i32,
+    isbn: Option<String>,
+    title: String,
+    author_id: i32,
+    author: String,
+    #[serde(skip)]
+    author_firstname: String,
+    #[serde(skip)]
+    author_lastname: String,
+    published: Option<String>,
+    publisher: Option<String>,
+    edition: Option<String>,
+    pages: Option<i32>,
+    genre: Option<String>,
+    cover: String,
+    created_at: DateTimeUtc,
+    updated_at: DateTimeUtc,
+    deleted_at: Option<DateTimeUtc>,
+}
+
+#[rocket::async_trait]
+impl Exportable for ExportBook {
+    const NAME: &'static str = "books";
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "isbn",
+        "title",
+        "author_id",
+        "author",
+        "published",
+        "publisher",
+        "edition",
+        "pages",
+        "genre",
+        "cover",
+        "created_at",
+        "updated_at",
+        "deleted_at",
+    ];
+    const FORMATS: &'static [ExportFormat] = &[
+        ExportFormat::Csv,
+        ExportFormat::Jsonl,
+        ExportFormat::Xlsx,
+        ExportFormat::Onix,
+        ExportFormat::Marcxml,
+    ];
+
+    async fn page(
+        db: &DatabaseConnection,
+        filter: Condition,
+        after: i32,
+        limit: u64,
+    ) -> Result<Vec<Self>, DbErr> {
+        Ok(Book::find()
+            .filter(filter)
+            .filter(book::Column::Id.gt(after))
+            .order_by_asc(book::Column::Id)
+            .limit(limit)
+            .find_also_related(Author)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|(b, a)| {
+                let (firstname, lastname) =
+                    a.map_or_else(Default::default, |a| (a.firstname, a.lastname));
+
+                ExportBook {
+                    id: b.id,
+                    isbn: b.isbn,
+                    title: b.title,
+                    author_id: b.author_id,
+                    author: format!("{} {}", firstname, lastname).trim().to_string(),
+                    author_firstname: firstname,
+                    author_lastname: lastname,
+                    published: PublicationDate::from_columns(
+                        b.published_on,
+                        b.published_precision.as_ref(),
+                    )
+                    .map(|d| d.to_string()),
+                    publisher: b.publisher,
+                    edition: b.edition,
+                    pages: b.pages,
+                    genre: b.genre,
+                    cover: b.cover,
+                    created_at: b.created_at,
+                    updated_at: b.updated_at,
+                    deleted_at: b.deleted_at,
+                }
+            })
+            .collect())
+    }
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn onix(&self, base_url: &str) -> Option<onix::Product> {
+        Some(onix::Product {
+            record_reference: format!("bookstore.book.{}", self.id),
+            deleted: self.deleted_at.is_some(),
+            isbn: self.isbn.clone(),
+            title: self.title.clone(),
+            author_firstname: self.author_firstname.clone(),
+            author_lastname: self.author_lastname.clone(),
+            edition: self.edition.clone(),
+            pages: self.pages,
+            publisher: self.publisher.clone(),
+            published: self.published.clone(),
+            cover: Some(self.cover.as_str())
+                .filter(|c| !c.is_empty())
+                .map(|c| exports::absolute_url(base_url, c)),
+            ..Default::default()
+        })
+    }
+
+    fn marc(&self, base_url: &str) -> Option<marc::Record> {
+        let year = self.published.as_deref().and_then(|p| p.get(..4));
+
+        // 008: when the record was created, the kind of date and the year,
+        // then unknown place and language.
+        let fixed = format!(
+            "{}{}{}    xx {:17}und d",
+            self.created_at.format("%y%m%d"),
+            if year.is_some() { 's' } else { 'n' },
+            year.unwrap_or("uuuu"),
+            "",
+        );
+
+        // Indicator 1 of 100 is 1 for "Last, First" and 0 for a single name.
+        let (name, kind) = match self.author_firstname.as_str() {
+            "" => (self.author_lastname.clone(), "0 "),
+            first => (format!("{}, {}", self.author_lastname, first), "1 "),
+        };
+
+        let record = marc::Record::book(self.deleted_at.is_some())
+            .control("001", format!("bookstore.book.{}", self.id))
+            .control("005", self.updated_at.format("%Y%m%d%H%M%S.0").to_string())
+            .control("008", fixed)
+            .data("020", "  ", &[('a', self.isbn.clone().unwrap_or_default())])
+            .data("100", kind, &[('a', name)])
+            .data("245", "10", &[('a', self.title.clone())])
+            .data(
+                "250",
+                "  ",
+                &[('a', self.edition.clone().unwrap_or_default())],
+            )
+            .data(
+                "264",
+                " 1",
+                &[
+                    ('b', self.publisher.clone().unwrap_or_default()),
+                    ('c', year.unwrap_or_default().to_string()),
+                ],
+            )
+            .data(
+                "300",
+                "  ",
+                &[(
+                    'a',
+                    self.pages.map_or(String::new(), |p| format!("{} pages", p)),
+                )],
+            );
+
+        Some(match self.cover.as_str() {
+            "" => record,
+            cover => record.data(
+                "856",
+                "42",
+                &[
+                    ('3', "Cover image".to_string()),
+                    ('u', exports::absolute_url(base_url, cover)),
+                ],
+            ),
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ExportAuthor {
+    id: i32,
+    firstname: String,
+    lastname: String,
+    sort_name: String,
+    bio: String,
+    born: Option<String>,
+    died: Option<String>,
+    nationality: Option<String>,
+    website: Option<String>,
+    photo: Option<String>,
+    created_at: DateTimeUtc,
+    updated_at: DateTimeUtc,
+    deleted_at: Option<DateTimeUtc>,
+}
+
+#[rocket::async_trait]
+impl Exportable for ExportAuthor {
+    const NAME: &'static str = "authors";
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "firstname",
+        "lastname",
+        "sort_name",
+        "bio",
+        "born",
+        "died",
+        "nationality",
+        "website",
+        "photo",
+        "created_at",
+        "updated_at",
+        "deleted_at",
+    ];
+
+    async fn page(
+        db: &DatabaseConnection,
+        filter: Condition,
+        after: i32,
+        limit: u64,
+    ) -> Result<Vec<Self>, DbErr> {
+        Ok(Author::find()
+            .filter(filter)
+            .filter(author::Column::Id.gt(after))
+            .order_by_asc(author::Column::Id)
+            .limit(limit)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|a| ExportAuthor {
+                id: a.id,
+                born: PublicationDate::from_columns(a.born_on, a.born_precision.as_ref())
+                    .map(|d| d.to_string()),
+                died: PublicationDate::from_columns(a.died_on, a.died_precision.as_ref())
+                    .map(|d| d.to_string()),
+                firstname: a.firstname,
+                lastname: a.lastname,
+                sort_name: a.sort_name,
+                bio: a.bio,
+                nationality: a.nationality,
+                website: a.website,
+                photo: a.photo,
+                created_at: a.created_at,
+                updated_at: a.updated_at,
+                deleted_at: a.deleted_at,
+            })
+            .collect())
+    }
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+/// Resolves the format of a `T` export from `?format=`, or else the `Accept`
+/// header.
+fn format<T: Exportable>(
+    name: Option<&str>,
+    accept: Option<&Accept>,
+) -> Result<ExportFormat, ErrorResponse> {
+    match name {
+        Some(name) => ExportFormat::from_name(name, T::FORMATS).ok_or_else(|| {
+            ErrorResponse::field(
+                "format",
+                format!(
+                    "The format must be {}.",
+                    either(T::FORMATS.iter().map(|f| f.name().to_string()))
+                ),
+            )
+        }),
+        None => ExportFormat::negotiate(accept, T::FORMATS).ok_or_else(|| {
+            ErrorResponse::new(
+                Status::NotAcceptable,
+                format!(
+                    "Exports of {} are available as {}.",
+                    T::NAME,
+                    either(T::FORMATS.iter().map(|f| {
+                        let m = f.media_type();
+                        format!("{}/{}", m.top(), m.sub())
+                    }))
+                ),
+            )
+        }),
+    }
+}
+
+/// Lists `options` as "a, b or c".
+pub(super) fn either(options: impl Iterator<Item = String>) -> String {
+    let options = options.collect::<Vec<_>>();
+
+    match options.split_last() {
+        Some((last, [])) => last.to_owned(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/v1/export",
+    tag = "exports",
+    params(
+        ("format" = Option<String>, Query, description = "`csv`, `jsonl`, `xlsx`, `onix` (ONIX 3.0) or `marcxml`. Defaults to the format preferred by the Accept header, or CSV."),
+        ("published_from" = Option<String>, Query, description = "As for the list of books."),
+        ("published_to" = Option<String>, Query, description = "As for the list of books."),
+        ("genre" = Option<String>, Query, description = "As for the list of books."),
+        ("include_deleted" = Option<bool>, Query, description = "Admins only.")
+    ),
+    responses(
+        (status = 200, description = "Every matching book, by ID.", content(
+            (String = "text/csv"),
+            (String = "application/x-ndjson"),
+            (Vec<u8> = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+            (String = "application/xml"),
+            (String = "application/marcxml+xml")
+        )),
+        (status = 400, response = Problem),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem),
+        (status = 406, response = Problem),
+        (status = 422, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[get("/books?<format>&<published_from>&<published_to>&<genre>&<include_deleted>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn export_books(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    accept: Option<&Accept>,
+    format: Option<&str>,
+    published_from: Option<&str>,
+    published_to: Option<&str>,
+    genre: Option<&str>,
+    include_deleted: Option<bool>,
+) -> Result<Export<impl Stream<Item = Vec<u8>>>, ErrorResponse> {
+    let format = self::format::<ExportBook>(format, accept)?;
+    let filter = books::list_filter(&user, published_from, published_to, genre, include_deleted)?;
+
+    Ok(exports::export::<ExportBook>(
+        db.inner().clone(),
+        format,
+        filter,
+        config.base_url.clone(),
+    ))
+}
+
+#[utoipa::path(
+    context_path = "/v1/export",
+    tag = "exports",
+    params(
+        ("format" = Option<String>, Query, description = "`csv`, `jsonl` or `xlsx`. Defaults to the format preferred by the Accept header, or CSV."),
+        ("q" = Option<String>, Query, description = "Words that must each appear in the author's first name, last name, sort name or one of their aliases."),
+        ("include_deleted" = Option<bool>, Query, description = "Admins only.")
+    ),
+    responses(
+        (status = 200, description = "Every matching author, by ID.", content(
+            (String = "text/csv"),
+            (String = "application/x-ndjson"),
+            (Vec<u8> = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")
+        )),
+        (status = 401, response = Problem),
+        (status = 403, response = Problem),
+        (status = 406, response = Problem),
+        (status = 422, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[get("/authors?<format>&<q>&<include_deleted>")]
+pub async fn export_authors(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    accept: Option<&Accept>,
+    format: Option<&str>,
+    q: Option<&str>,
+    include_deleted: Option<bool>,
+) -> Result<Export<impl Stream<Item = Vec<u8>>>, ErrorResponse> {
+    let format = self::format::<ExportAuthor>(format, accept)?;
+    let filter = authors::list_filter(&user, q, include_deleted)?;
+
+    Ok(exports::export::<ExportAuthor>(
+        db.inner().clone(),
+        format,
+        filter,
+        config.base_url.clone(),
+    ))
+}
@@ -0,0 +1,32 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use async_graphql::http::GraphiQLSource;
+use rocket::{response::content::RawHtml, serde::json::Json, State};
+use sea_orm::DatabaseConnection;
+
+use crate::auth::AuthenticatedUser;
+use crate::graphql::{self, BookStoreSchema};
+use crate::idempotency::Idempotent;
+
+#[post("/", data = "<request>")]
+pub async fn execute(
+    schema: &State<BookStoreSchema>,
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    request: Idempotent<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    let request = graphql::prepare(request.into_inner(), db, user);
+
+    Json(schema.execute(request).await)
+}
+
+/// An in-browser IDE for the schema. Only mounted in debug builds.
+#[get("/")]
+pub fn graphiql() -> RawHtml<String> {
+    RawHtml(GraphiQLSource::build().endpoint(graphql::PATH).finish())
+}
@@ -0,0 +1,221 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use rocket::{
+    http::{ContentType, Header, Status},
+    request::Request,
+    response::{self, Responder},
+    serde::{json::Json, Serialize},
+    State,
+};
+use sea_orm::{prelude::DateTimeUtc, *};
+use std::io::Cursor;
+use utoipa::ToSchema;
+
+use super::{error::Problem, v1, ErrorResponse, Response, SuccessResponse};
+use crate::auth::AuthenticatedUser;
+use crate::entities::{
+    import_job,
+    prelude::*,
+    sea_orm_active_enums::{ImportFormat, ImportStatus},
+};
+use crate::idempotency::IdempotentBytes;
+use crate::imports::{self, RowError};
+use crate::AppConfig;
+
+#[derive(Serialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct ResImport {
+    id: i32,
+    format: String,
+    dry_run: bool,
+    /// `pending`, `running`, `completed` or `failed`.
+    status: String,
+    total_rows: i32,
+    processed_rows: i32,
+    failed_rows: i32,
+    books_created: i32,
+    /// Books matched by ISBN and updated.
+    books_updated: i32,
+    authors_created: i32,
+    authors_matched: i32,
+    /// Where the per-row error report can be downloaded as CSV.
+    error_report: String,
+    #[schema(value_type = String, format = DateTime)]
+    created_at: DateTimeUtc,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    finished_at: Option<DateTimeUtc>,
+}
+
+impl From<&import_job::Model> for ResImport {
+    fn from(j: &import_job::Model) -> Self {
+        Self {
+            id: j.id,
+            format: j.format.to_value(),
+            dry_run: j.dry_run,
+            status: j.status.to_value(),
+            total_rows: j.total_rows,
+            processed_rows: j.processed_rows,
+            failed_rows: j.failed_rows,
+            books_created: j.books_created,
+            books_updated: j.books_updated,
+            authors_created: j.authors_created,
+            authors_matched: j.authors_matched,
+            error_report: format!("{}/imports/{}/errors", v1::PREFIX, j.id),
+            created_at: j.created_at,
+            finished_at: j.finished_at,
+        }
+    }
+}
+
+/// The per-row errors of an import, served as a CSV download.
+pub struct ErrorReport {
+    id: i32,
+    csv: Vec<u8>,
+}
+
+impl<'r> Responder<'r, 'static> for ErrorReport {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        rocket::Response::build()
+            .header(ContentType::CSV)
+            .header(Header::new(
+                "Content-Disposition",
+                format!("attachment; filename=\"import-{}-errors.csv\"", self.id),
+            ))
+            .sized_body(self.csv.len(), Cursor::new(self.csv))
+            .ok()
+    }
+}
+
+#[utoipa::path(
+    context_path = "/v1/imports",
+    tag = "imports",
+    params(
+        ("format" = Option<String>, Query, description = "`csv`, `jsonl`, `onix` or `marc` (MARC21 or MARCXML). Defaults to the format given by the Content-Type."),
+        ("dry_run" = Option<bool>, Query, description = "Check every row without saving anything."),
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response replayed.")
+    ),
+    request_body(content = String, description = "A CSV file with a header row, one JSON object per line, an ONIX 3.0 message, or MARC21 or MARCXML records.", content_type = "text/csv"),
+    responses(
+        (status = 202, body = ResImport, description = "The import was queued."),
+        (status = 401, response = Problem),
+        (status = 409, response = Problem),
+        (status = 413, response = Problem),
+        (status = 415, response = Problem),
+        (status = 422, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[post("/?<format>&<dry_run>", data = "<file>")]
+pub async fn create(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    content_type: Option<&ContentType>,
+    format: Option<&str>,
+    dry_run: Option<bool>,
+    file: IdempotentBytes,
+) -> Response<Json<ResImport>> {
+    let db = db as &DatabaseConnection;
+
+    let format = match format {
+        Some(name) => ImportFormat::from_name(name)
+            .ok_or_else(|| ErrorResponse::field("format", "The format must be csv, jsonl, onix or marc."))?,
+        None => content_type
+            .and_then(|ct| ImportFormat::from_media_type(ct.top().as_str(), ct.sub().as_str()))
+            .ok_or_else(|| {
+                ErrorResponse::new(
+                    Status::UnsupportedMediaType,
+                    "Send text/csv, application/x-ndjson, application/xml, application/marc or application/marcxml+xml, or set the format parameter.",
+                )
+            })?,
+    };
+
+    let rows = imports::parse(&format, &file.0)
+        .map_err(|message| ErrorResponse::field("file", message))?;
+
+    let job = import_job::ActiveModel {
+        user_id: Set(user.id),
+        format: Set(format),
+        dry_run: Set(dry_run.unwrap_or(false)),
+        status: Set(ImportStatus::Pending),
+        total_rows: Set(rows.len() as i32),
+        errors: Set(serde_json::json!([])),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    imports::spawn(db.clone(), config.inner().clone(), user, job.clone(), rows);
+
+    Ok(SuccessResponse((
+        Status::Accepted,
+        Json(ResImport::from(&job)),
+    )))
+}
+
+#[utoipa::path(
+    context_path = "/v1/imports",
+    tag = "imports",
+    responses(
+        (status = 200, body = ResImport),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[get("/<id>")]
+pub async fn show(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResImport>> {
+    let job = find_import(db, &user, id).await?;
+
+    Ok(SuccessResponse((Status::Ok, Json(ResImport::from(&job)))))
+}
+
+#[utoipa::path(
+    context_path = "/v1/imports",
+    tag = "imports",
+    responses(
+        (status = 200, description = "One line per problem: `line`, `field` and `message`.", content_type = "text/csv", body = String),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[get("/<id>/errors")]
+pub async fn errors(
+    db: &State<DatabaseConnection>,
+    user: AuthenticatedUser,
+    id: i32,
+) -> Result<ErrorReport, ErrorResponse> {
+    let job = find_import(db, &user, id).await?;
+    let errors: Vec<RowError> =
+        serde_json::from_value(job.errors).map_err(ErrorResponse::internal)?;
+
+    Ok(ErrorReport {
+        id,
+        csv: imports::error_report(&errors).map_err(ErrorResponse::internal)?,
+    })
+}
+
+/// Finds an import of `user`'s. Admins can see everyone's.
+async fn find_import(
+    db: &DatabaseConnection,
+    user: &AuthenticatedUser,
+    id: i32,
+) -> Result<import_job::Model, ErrorResponse> {
+    match ImportJob::find_by_id(id).one(db).await? {
+        Some(job) if job.user_id == user.id || user.is_admin() => Ok(job),
+        _ => Err(ErrorResponse::new(
+            Status::NotFound,
+            "No import found with the specified ID.",
+        )),
+    }
+}
@@ -0,0 +1,428 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use rocket::{
+    http::Status,
+    serde::{json::Json, Deserialize, Serialize},
+    tokio::task,
+    State,
+};
+use sea_orm::{prelude::DateTimeUtc, sea_query::Expr, *};
+use std::{
+    collections::{HashMap, HashSet},
+    time::SystemTime,
+};
+use utoipa::ToSchema;
+
+use super::{
+    authors::{find_author, move_books, ResAuthor},
+    conditional::{guarded, Preconditions, Versioned},
+    error::Problem,
+    ErrorResponse, Response, SuccessResponse,
+};
+use crate::auth::AuthenticatedUser;
+use crate::entities::{
+    author, author_alias, book,
+    prelude::*,
+    sea_orm_active_enums::{AliasKind, RevisionAction},
+};
+use crate::idempotency::Idempotent;
+use crate::names::{self, Name};
+use crate::revisions::{self, AuthorWithAliases};
+use crate::validation::MAX_STRING_LENGTH;
+use crate::AppConfig;
+
+/// How similar names with different surnames must be to be listed, unless
+/// `threshold` is given.
+const DEFAULT_THRESHOLD: f64 = 0.5;
+
+/// How many pairs are listed at once, unless `limit` is given, and at most.
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+
+#[derive(Serialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct ResDuplicate {
+    /// The older of the two.
+    pub author: ResAuthor,
+    pub duplicate: ResAuthor,
+    /// The share of trigrams the closest of their names and aliases have in common.
+    pub similarity: f64,
+    /// `same_name`, `initials` or `similar`.
+    pub reason: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct ResDuplicateList {
+    /// The number of pairs found, not only those listed.
+    pub total: usize,
+    pub duplicates: Vec<ResDuplicate>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct ReqMerge {
+    /// The authors to merge into this one.
+    pub(crate) authors: Vec<i32>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct ResMerge {
+    pub author: ResAuthor,
+    /// The aliases of the author, now including the names of those merged.
+    pub aliases: Vec<String>,
+    pub merged: Vec<i32>,
+    pub books_moved: usize,
+}
+
+/// The name an author is kept as an alias by, cut to fit the column.
+fn full_name(author: &author::Model) -> String {
+    format!("{} {}", author.firstname, author.lastname)
+        .trim()
+        .chars()
+        .take(MAX_STRING_LENGTH)
+        .collect()
+}
+
+/// Lists pairs of authors who may be the same person, most likely first.
+///
+/// Names are compared ignoring case, punctuation and spacing. With the same
+/// surname, the given names must agree, initials matching the names they
+/// start; with different surnames, the names must be at least `threshold`
+/// similar by trigrams. Aliases count as names. `limit` and `offset` page
+/// through the pairs.
+#[utoipa::path(
+    context_path = "/v1/authors",
+    tag = "authors",
+    params(
+        ("threshold" = Option<f64>, Query, description = "The trigram similarity, from 0 to 1, of names with different surnames to list. Defaults to 0.5."),
+        ("limit" = Option<usize>, Query, description = "How many pairs to list, from 1 to 500. Defaults to 50."),
+        ("offset" = Option<usize>, Query, description = "How many pairs to skip. Defaults to 0.")
+    ),
+    responses(
+        (status = 200, body = ResDuplicateList),
+        (status = 401, response = Problem),
+        (status = 422, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[get("/duplicates?<threshold>&<limit>&<offset>")]
+pub async fn duplicates(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    threshold: Option<f64>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Response<Json<ResDuplicateList>> {
+    let db = db as &DatabaseConnection;
+
+    let threshold = threshold.unwrap_or(DEFAULT_THRESHOLD);
+
+    if !(threshold > 0.0 && threshold <= 1.0) {
+        return Err(ErrorResponse::field(
+            "threshold",
+            "Must be greater than 0 and at most 1.",
+        ));
+    }
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(ErrorResponse::field(
+            "limit",
+            format!("Must be from 1 to {}.", MAX_LIMIT),
+        ));
+    }
+
+    let authors = Author::find()
+        .filter(author::Column::DeletedAt.is_null())
+        .order_by_asc(author::Column::Id)
+        .all(db)
+        .await?;
+
+    let mut aliases = HashMap::<i32, Vec<Name>>::new();
+
+    for alias in AuthorAlias::find().all(db).await? {
+        aliases
+            .entry(alias.author_id)
+            .or_default()
+            .push(Name::parse(&alias.name));
+    }
+
+    let names = authors
+        .iter()
+        .map(|a| {
+            let mut names = vec![Name::new(&a.firstname, &a.lastname)];
+            names.extend(aliases.remove(&a.id).unwrap_or_default());
+            names
+        })
+        .collect::<Vec<_>>();
+
+    // Comparing names is CPU-bound, so it is kept off the async workers.
+    let pairs = task::spawn_blocking(move || {
+        let mut pairs = names::candidates(&names, threshold)
+            .into_iter()
+            .filter_map(|(i, j)| {
+                names[i]
+                    .iter()
+                    .flat_map(|x| names[j].iter().filter_map(|y| x.compare(y, threshold)))
+                    .min_by(|(r1, s1), (r2, s2)| r1.cmp(r2).then(s2.total_cmp(s1)))
+                    .map(|(reason, similarity)| (i, j, reason, similarity))
+            })
+            .collect::<Vec<_>>();
+
+        pairs.sort_by(|(i1, j1, r1, s1), (i2, j2, r2, s2)| {
+            r1.cmp(r2)
+                .then(s2.total_cmp(s1))
+                .then((i1, j1).cmp(&(i2, j2)))
+        });
+        pairs
+    })
+    .await
+    .map_err(ErrorResponse::internal)?;
+
+    let total = pairs.len();
+
+    let duplicates = pairs
+        .into_iter()
+        .skip(offset.unwrap_or(0))
+        .take(limit)
+        .map(|(i, j, reason, similarity)| ResDuplicate {
+            author: ResAuthor::from(&authors[i]),
+            duplicate: ResAuthor::from(&authors[j]),
+            similarity: (similarity * 100.0).round() / 100.0,
+            reason: reason.name().to_string(),
+        })
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResDuplicateList { total, duplicates }),
+    )))
+}
+
+/// Merges other authors into this one.
+///
+/// Their books, deleted ones too, move to this author, their names and
+/// aliases become this author's aliases, and they are marked as deleted.
+/// Reading a merged author redirects here.
+#[utoipa::path(
+    context_path = "/v1/authors",
+    tag = "authors",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response replayed.")
+    ),
+    request_body = ReqMerge,
+    responses(
+        (status = 200, body = ResMerge),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 412, response = Problem),
+        (status = 413, response = Problem),
+        (status = 422, response = Problem),
+        (status = 428, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[post("/<id>/merge", data = "<req_merge>")]
+pub async fn merge(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    preconditions: Preconditions,
+    id: i32,
+    req_merge: Idempotent<ReqMerge>,
+) -> Response<Json<ResMerge>> {
+    let db = db as &DatabaseConnection;
+
+    let mut ids = req_merge.authors.clone();
+    let mut seen = HashSet::new();
+    ids.retain(|id| seen.insert(*id));
+
+    if ids.is_empty() {
+        return Err(ErrorResponse::field(
+            "authors",
+            "List at least one author to merge.",
+        ));
+    }
+
+    if ids.contains(&id) {
+        return Err(ErrorResponse::field(
+            "authors",
+            "An author can't be merged into themselves.",
+        ));
+    }
+
+    if ids.len() > config.bulk_limit {
+        return Err(ErrorResponse::new(
+            Status::PayloadTooLarge,
+            format!(
+                "At most {} authors may be merged at once, this request has {}.",
+                config.bulk_limit,
+                ids.len()
+            ),
+        ));
+    }
+
+    let survivor = find_author(db, id, false).await?;
+    preconditions.check_if_match(&survivor.etag(), config.require_if_match)?;
+
+    let merged = Author::find()
+        .filter(author::Column::Id.is_in(ids.clone()))
+        .filter(author::Column::DeletedAt.is_null())
+        .all(db)
+        .await?;
+
+    let missing = ids
+        .iter()
+        .filter(|id| !merged.iter().any(|a| a.id == **id))
+        .map(i32::to_string)
+        .collect::<Vec<_>>();
+
+    if !missing.is_empty() {
+        return Err(ErrorResponse::field(
+            "authors",
+            match missing.as_slice() {
+                [id] => format!("No author found with the ID {}.", id),
+                _ => format!("No authors found with the IDs {}.", missing.join(", ")),
+            },
+        ));
+    }
+
+    let txn = db.begin().await?;
+
+    let books = Book::find()
+        .filter(book::Column::AuthorId.is_in(ids.clone()))
+        .all(&txn)
+        .await?;
+
+    move_books(&txn, &user, &books, id).await.map_err(guarded)?;
+
+    // The names of the merged authors and their aliases, each kept once,
+    // ignoring case, as the unique index on aliases does.
+    let existing = AuthorAlias::find()
+        .filter(author_alias::Column::AuthorId.is_in(ids.iter().copied().chain([id])))
+        .order_by_asc(author_alias::Column::Id)
+        .all(&txn)
+        .await?;
+
+    let mut known = HashSet::from([full_name(&survivor).to_lowercase()]);
+    known.extend(
+        existing
+            .iter()
+            .filter(|a| a.author_id == id)
+            .map(|a| a.name.to_lowercase()),
+    );
+
+    let names = merged
+        .iter()
+        .map(|a| (full_name(a), AliasKind::Alias))
+        .chain(
+            existing
+                .iter()
+                .filter(|a| a.author_id != id)
+                .map(|a| (a.name.clone(), a.kind.clone())),
+        )
+        .filter(|(name, _)| !name.is_empty() && known.insert(name.to_lowercase()))
+        .collect::<Vec<_>>();
+
+    AuthorAlias::delete_many()
+        .filter(author_alias::Column::AuthorId.is_in(ids.clone()))
+        .exec(&txn)
+        .await?;
+
+    if !names.is_empty() {
+        AuthorAlias::insert_many(
+            names
+                .into_iter()
+                .map(|(name, kind)| author_alias::ActiveModel {
+                    author_id: Set(id),
+                    name: Set(name),
+                    kind: Set(kind),
+                    ..Default::default()
+                }),
+        )
+        .exec(&txn)
+        .await?;
+    }
+
+    let now = DateTimeUtc::from(SystemTime::now());
+
+    for before in &merged {
+        let mut author: author::ActiveModel = before.clone().into();
+        author.deleted_at = Set(Some(now));
+        author.merged_into_id = Set(Some(id));
+        author.version = Set(before.version + 1);
+
+        let author = Author::update(author)
+            .filter(author::Column::Version.eq(before.version))
+            .exec(&txn)
+            .await
+            .map_err(guarded)?;
+        revisions::record(
+            &txn,
+            user.id,
+            RevisionAction::Delete,
+            Some(&AuthorWithAliases::new(before, &existing)),
+            Some(&AuthorWithAliases::new(&author, &[])),
+        )
+        .await?;
+    }
+
+    // The survivor's new aliases are a change to them too.
+    let mut author: author::ActiveModel = survivor.clone().into();
+    author.updated_at = Set(now);
+    author.version = Set(survivor.version + 1);
+
+    let author = Author::update(author)
+        .filter(author::Column::Version.eq(survivor.version))
+        .exec(&txn)
+        .await
+        .map_err(guarded)?;
+    revisions::record(
+        &txn,
+        user.id,
+        RevisionAction::Update,
+        Some(&AuthorWithAliases::new(&survivor, &existing)),
+        Some(&AuthorWithAliases::load(&txn, &author).await?),
+    )
+    .await?;
+
+    // Authors merged into these earlier now redirect to the survivor directly.
+    Author::update_many()
+        .col_expr(author::Column::MergedIntoId, Expr::value(id))
+        .col_expr(
+            author::Column::Version,
+            Expr::col(author::Column::Version).add(1),
+        )
+        .filter(author::Column::MergedIntoId.is_in(ids.clone()))
+        .exec(&txn)
+        .await?;
+
+    let aliases = author
+        .find_related(AuthorAlias)
+        .order_by_asc(author_alias::Column::Id)
+        .all(&txn)
+        .await?
+        .into_iter()
+        .map(|a| a.name)
+        .collect();
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResMerge {
+            author: ResAuthor::from(&author),
+            aliases,
+            merged: ids,
+            books_moved: books.len(),
+        }),
+    )))
+}
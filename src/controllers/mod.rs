@@ -6,22 +6,47 @@
  * @link   https://github.com/AfaanBilal/bookstore
  */
 use rocket::http::Status;
-use sea_orm::DbErr;
+
+use crate::auth::AuthenticatedUser;
 
 pub mod auth;
 pub mod authors;
 pub mod books;
+pub mod bulk;
+pub mod catchers;
+pub mod citations;
+pub mod conditional;
+pub mod covers;
+pub mod embed;
+pub mod enrichment;
+pub mod error;
+pub mod exports;
+pub mod graphql;
+pub mod imports;
+pub mod merges;
+pub mod opds;
+pub mod patch;
+pub mod revisions;
+pub mod v1;
 
 #[derive(Responder)]
 pub struct SuccessResponse<T>(pub (Status, T));
 
-#[derive(Responder)]
-pub struct ErrorResponse(pub (Status, String));
+pub use error::ErrorResponse;
 
 pub type Response<T> = Result<SuccessResponse<T>, ErrorResponse>;
 
-impl From<DbErr> for ErrorResponse {
-    fn from(err: DbErr) -> Self {
-        ErrorResponse((Status::InternalServerError, err.to_string()))
+/// Resolves the `include_deleted` query parameter, which only admins may set.
+pub fn include_deleted(
+    user: &AuthenticatedUser,
+    include_deleted: Option<bool>,
+) -> Result<bool, ErrorResponse> {
+    match include_deleted {
+        Some(true) if !user.is_admin() => Err(ErrorResponse::new(
+            Status::Forbidden,
+            "Only admins may include deleted records.".to_string(),
+        )),
+        Some(include) => Ok(include),
+        None => Ok(false),
     }
 }
@@ -0,0 +1,812 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use chrono::Utc;
+use rocket::{
+    http::{ContentType, RawStr, Status},
+    State,
+};
+use sea_orm::*;
+
+use super::{authors, error::Problem, v1, ErrorResponse};
+use crate::auth::ReaderUser;
+use crate::entities::{author, book, prelude::*};
+use crate::opds::{Feed, Flavor, Genre, Image, Kind, Link, Navigation, Publication, PAGE_SIZE};
+use crate::publication::PublicationDate;
+
+/// Where the OPDS 1.2 (Atom) catalogue is mounted, below [`v1::PREFIX`].
+pub const ATOM_BASE: &str = "/opds";
+
+/// Where the OPDS 2.0 (JSON) catalogue is mounted, below [`v1::PREFIX`].
+pub const JSON_BASE: &str = "/opds2";
+
+/// A feed or entry, with its OPDS media type.
+#[derive(Responder)]
+pub struct Catalog(String, ContentType);
+
+impl Catalog {
+    fn new(flavor: Flavor, kind: Kind, body: String) -> Self {
+        Catalog(
+            body,
+            ContentType::parse_flexible(flavor.media_type(kind)).unwrap(),
+        )
+    }
+}
+
+/// Builds the links of one flavor of the catalogue, carrying on an API key
+/// given in the query, as readers will not add it to the links they follow.
+struct Hrefs {
+    base: &'static str,
+    api_key: Option<String>,
+}
+
+impl Hrefs {
+    fn new(flavor: Flavor, reader: &ReaderUser) -> Self {
+        Self {
+            base: match flavor {
+                Flavor::Atom => ATOM_BASE,
+                Flavor::Json => JSON_BASE,
+            },
+            api_key: reader.api_key.clone(),
+        }
+    }
+
+    fn to(&self, path: &str) -> String {
+        let href = format!("{}{}{}", v1::PREFIX, self.base, path);
+
+        match &self.api_key {
+            Some(key) => format!(
+                "{}{}api_key={}",
+                href,
+                if href.contains('?') { '&' } else { '?' },
+                RawStr::new(key).percent_encode()
+            ),
+            None => href,
+        }
+    }
+
+    /// The links every feed has, to itself and to the start.
+    fn links(&self, path: &str, kind: Kind) -> Vec<Link> {
+        vec![
+            Link::new("self", &self.to(path), kind),
+            Link::new("start", &self.to("/"), Kind::Navigation),
+        ]
+    }
+
+    /// Links to the neighbouring pages of a paged feed at `path`.
+    fn pages(&self, path: &str, kind: Kind, page: u64, more: bool) -> Vec<Link> {
+        let mut links = self.links(&format!("{}?page={}", path, page), kind);
+        links.push(Link::new("up", &self.to("/"), Kind::Navigation));
+
+        if page > 1 {
+            links.push(Link::new(
+                "previous",
+                &self.to(&format!("{}?page={}", path, page - 1)),
+                kind,
+            ));
+        }
+
+        if more {
+            links.push(Link::new(
+                "next",
+                &self.to(&format!("{}?page={}", path, page + 1)),
+                kind,
+            ));
+        }
+
+        links
+    }
+}
+
+fn check_page(page: Option<u64>) -> Result<u64, ErrorResponse> {
+    match page.unwrap_or(1) {
+        0 => Err(ErrorResponse::field("page", "Must be at least 1.")),
+        page => Ok(page),
+    }
+}
+
+fn name(a: &author::Model) -> String {
+    format!("{} {}", a.firstname, a.lastname).trim().to_string()
+}
+
+/// The path of a genre's feed.
+fn genre_path(genre: &str) -> String {
+    format!("/genres/{}", RawStr::new(genre).percent_encode())
+}
+
+fn publication(hrefs: &Hrefs, b: &book::Model, a: &author::Model) -> Publication {
+    // Uploaded covers have JPEG thumbnails; covers hosted elsewhere do not.
+    let (image, thumbnail) = match b.cover.as_str() {
+        "" => (None, None),
+        cover if cover.starts_with(v1::PREFIX) => (
+            Some(Image {
+                href: format!("{}/large", cover),
+                media_type: Some("image/jpeg"),
+            }),
+            Some(Image {
+                href: format!("{}/small", cover),
+                media_type: Some("image/jpeg"),
+            }),
+        ),
+        cover => (
+            Some(Image {
+                href: cover.to_string(),
+                media_type: None,
+            }),
+            Some(Image {
+                href: cover.to_string(),
+                media_type: None,
+            }),
+        ),
+    };
+
+    Publication {
+        id: b.id,
+        title: b.title.clone(),
+        author: name(a),
+        author_href: hrefs.to(&format!("/authors/{}", a.id)),
+        isbn: b.isbn.clone(),
+        publisher: b.publisher.clone(),
+        published: PublicationDate::from_columns(b.published_on, b.published_precision.as_ref())
+            .map(|d| d.to_string()),
+        pages: b.pages,
+        genre: b.genre.as_ref().map(|g| Genre {
+            name: g.clone(),
+            href: hrefs.to(&genre_path(g)),
+        }),
+        updated: b.updated_at,
+        acquisition: hrefs.to(&format!("/books/{}", b.id)),
+        image,
+        thumbnail,
+    }
+}
+
+fn root(hrefs: &Hrefs) -> Feed {
+    Feed {
+        id: "urn:bookstore:opds".to_string(),
+        title: "BookStore".to_string(),
+        updated: Utc::now(),
+        kind: Kind::Navigation,
+        page: None,
+        links: hrefs.links("/", Kind::Navigation),
+        navigation: vec![
+            Navigation {
+                id: "urn:bookstore:opds:new".to_string(),
+                title: "Newest".to_string(),
+                summary: "The latest additions to the catalogue.".to_string(),
+                href: hrefs.to("/new"),
+                kind: Kind::Acquisition,
+            },
+            Navigation {
+                id: "urn:bookstore:opds:authors".to_string(),
+                title: "Authors".to_string(),
+                summary: "Books by author.".to_string(),
+                href: hrefs.to("/authors"),
+                kind: Kind::Navigation,
+            },
+            Navigation {
+                id: "urn:bookstore:opds:genres".to_string(),
+                title: "Genres".to_string(),
+                summary: "Books by genre.".to_string(),
+                href: hrefs.to("/genres"),
+                kind: Kind::Navigation,
+            },
+        ],
+        publications: vec![],
+    }
+}
+
+async fn newest(db: &DatabaseConnection, hrefs: &Hrefs, page: u64) -> Result<Feed, ErrorResponse> {
+    let mut books = Book::find()
+        .filter(book::Column::DeletedAt.is_null())
+        .order_by_desc(book::Column::CreatedAt)
+        .order_by_desc(book::Column::Id)
+        .offset((page - 1) * PAGE_SIZE)
+        .limit(PAGE_SIZE + 1)
+        .find_also_related(Author)
+        .all(db)
+        .await?;
+
+    let more = books.len() as u64 > PAGE_SIZE;
+    books.truncate(PAGE_SIZE as usize);
+
+    Ok(Feed {
+        id: "urn:bookstore:opds:new".to_string(),
+        title: "Newest".to_string(),
+        updated: books.first().map_or_else(Utc::now, |(b, _)| b.created_at),
+        kind: Kind::Acquisition,
+        page: Some(page),
+        links: hrefs.pages("/new", Kind::Acquisition, page, more),
+        navigation: vec![],
+        publications: books
+            .iter()
+            .filter_map(|(b, a)| a.as_ref().map(|a| publication(hrefs, b, a)))
+            .collect(),
+    })
+}
+
+async fn authors(db: &DatabaseConnection, hrefs: &Hrefs, page: u64) -> Result<Feed, ErrorResponse> {
+    let mut authors = Author::find()
+        .filter(author::Column::DeletedAt.is_null())
+        .order_by_asc(author::Column::SortName)
+        .order_by_asc(author::Column::Id)
+        .offset((page - 1) * PAGE_SIZE)
+        .limit(PAGE_SIZE + 1)
+        .all(db)
+        .await?;
+
+    let more = authors.len() as u64 > PAGE_SIZE;
+    authors.truncate(PAGE_SIZE as usize);
+
+    Ok(Feed {
+        id: "urn:bookstore:opds:authors".to_string(),
+        title: "Authors".to_string(),
+        updated: Utc::now(),
+        kind: Kind::Navigation,
+        page: Some(page),
+        links: hrefs.pages("/authors", Kind::Navigation, page, more),
+        navigation: authors
+            .iter()
+            .map(|a| Navigation {
+                id: format!("urn:bookstore:author:{}", a.id),
+                title: name(a),
+                summary: a.bio.clone(),
+                href: hrefs.to(&format!("/authors/{}", a.id)),
+                kind: Kind::Acquisition,
+            })
+            .collect(),
+        publications: vec![],
+    })
+}
+
+async fn author(db: &DatabaseConnection, hrefs: &Hrefs, id: i32) -> Result<Feed, ErrorResponse> {
+    let (author, books) = authors::books_of(db, id, false).await?;
+
+    let mut links = hrefs.links(&format!("/authors/{}", id), Kind::Acquisition);
+    links.push(Link::new("up", &hrefs.to("/authors"), Kind::Navigation));
+
+    Ok(Feed {
+        id: format!("urn:bookstore:author:{}", id),
+        title: name(&author),
+        updated: books
+            .iter()
+            .map(|b| b.updated_at)
+            .max()
+            .unwrap_or(author.updated_at),
+        kind: Kind::Acquisition,
+        page: None,
+        links,
+        navigation: vec![],
+        publications: books
+            .iter()
+            .map(|b| publication(hrefs, b, &author))
+            .collect(),
+    })
+}
+
+async fn genres(db: &DatabaseConnection, hrefs: &Hrefs, page: u64) -> Result<Feed, ErrorResponse> {
+    let mut genres = Book::find()
+        .select_only()
+        .column(book::Column::Genre)
+        .column_as(book::Column::Id.count(), "books")
+        .filter(book::Column::DeletedAt.is_null())
+        .filter(book::Column::Genre.is_not_null())
+        .group_by(book::Column::Genre)
+        .order_by_asc(book::Column::Genre)
+        .offset((page - 1) * PAGE_SIZE)
+        .limit(PAGE_SIZE + 1)
+        .into_tuple::<(String, i64)>()
+        .all(db)
+        .await?;
+
+    let more = genres.len() as u64 > PAGE_SIZE;
+    genres.truncate(PAGE_SIZE as usize);
+
+    Ok(Feed {
+        id: "urn:bookstore:opds:genres".to_string(),
+        title: "Genres".to_string(),
+        updated: Utc::now(),
+        kind: Kind::Navigation,
+        page: Some(page),
+        links: hrefs.pages("/genres", Kind::Navigation, page, more),
+        navigation: genres
+            .iter()
+            .map(|(genre, books)| Navigation {
+                id: format!(
+                    "urn:bookstore:genre:{}",
+                    RawStr::new(genre).percent_encode()
+                ),
+                title: genre.clone(),
+                summary: format!("{} book(s).", books),
+                href: hrefs.to(&genre_path(genre)),
+                kind: Kind::Acquisition,
+            })
+            .collect(),
+        publications: vec![],
+    })
+}
+
+/// The books of a genre by title, 50 to a page. Genres are matched ignoring
+/// case, as the column's collation does.
+async fn genre(
+    db: &DatabaseConnection,
+    hrefs: &Hrefs,
+    genre: &str,
+    page: u64,
+) -> Result<Feed, ErrorResponse> {
+    let mut books = Book::find()
+        .filter(book::Column::DeletedAt.is_null())
+        .filter(book::Column::Genre.eq(genre))
+        .order_by_asc(book::Column::Title)
+        .order_by_asc(book::Column::Id)
+        .offset((page - 1) * PAGE_SIZE)
+        .limit(PAGE_SIZE + 1)
+        .find_also_related(Author)
+        .all(db)
+        .await?;
+
+    if books.is_empty() && page == 1 {
+        return Err(ErrorResponse::new(
+            Status::NotFound,
+            "No books found in the specified genre.".to_string(),
+        ));
+    }
+
+    let more = books.len() as u64 > PAGE_SIZE;
+    books.truncate(PAGE_SIZE as usize);
+
+    let path = genre_path(genre);
+    let mut links = hrefs.pages(&path, Kind::Acquisition, page, more);
+    links.retain(|l| l.rel != "up");
+    links.push(Link::new("up", &hrefs.to("/genres"), Kind::Navigation));
+
+    Ok(Feed {
+        id: format!(
+            "urn:bookstore:genre:{}",
+            RawStr::new(genre).percent_encode()
+        ),
+        title: books
+            .first()
+            .and_then(|(b, _)| b.genre.clone())
+            .unwrap_or_else(|| genre.to_string()),
+        updated: books
+            .iter()
+            .map(|(b, _)| b.updated_at)
+            .max()
+            .unwrap_or_else(Utc::now),
+        kind: Kind::Acquisition,
+        page: Some(page),
+        links,
+        navigation: vec![],
+        publications: books
+            .iter()
+            .filter_map(|(b, a)| a.as_ref().map(|a| publication(hrefs, b, a)))
+            .collect(),
+    })
+}
+
+async fn book(
+    db: &DatabaseConnection,
+    hrefs: &Hrefs,
+    id: i32,
+) -> Result<Publication, ErrorResponse> {
+    match Book::find_by_id(id)
+        .filter(book::Column::DeletedAt.is_null())
+        .find_also_related(Author)
+        .one(db)
+        .await?
+    {
+        Some((b, Some(a))) => Ok(publication(hrefs, &b, &a)),
+        _ => Err(ErrorResponse::new(
+            Status::NotFound,
+            "No book found with the specified ID.".to_string(),
+        )),
+    }
+}
+
+/// The start of the OPDS 1.2 catalogue.
+#[utoipa::path(
+    context_path = "/v1/opds",
+    tag = "opds",
+    responses(
+        (status = 200, description = "A navigation feed.", content((String = "application/atom+xml"))),
+        (status = 401, response = Problem)
+    ),
+    security(("token" = []), ("basic" = []), ("api_key" = []))
+)]
+#[get("/")]
+pub async fn atom_root(reader: ReaderUser) -> Catalog {
+    let hrefs = Hrefs::new(Flavor::Atom, &reader);
+
+    Catalog::new(
+        Flavor::Atom,
+        Kind::Navigation,
+        Flavor::Atom.feed(&root(&hrefs)),
+    )
+}
+
+/// The newest books, 50 to a page.
+#[utoipa::path(
+    context_path = "/v1/opds",
+    tag = "opds",
+    params(("page" = Option<u64>, Query, description = "From 1, the default.")),
+    responses(
+        (status = 200, description = "An acquisition feed.", content((String = "application/atom+xml"))),
+        (status = 401, response = Problem),
+        (status = 422, response = Problem)
+    ),
+    security(("token" = []), ("basic" = []), ("api_key" = []))
+)]
+#[get("/new?<page>")]
+pub async fn atom_newest(
+    db: &State<DatabaseConnection>,
+    reader: ReaderUser,
+    page: Option<u64>,
+) -> Result<Catalog, ErrorResponse> {
+    let hrefs = Hrefs::new(Flavor::Atom, &reader);
+    let feed = newest(db, &hrefs, check_page(page)?).await?;
+
+    Ok(Catalog::new(
+        Flavor::Atom,
+        Kind::Acquisition,
+        Flavor::Atom.feed(&feed),
+    ))
+}
+
+/// Authors by last name, 50 to a page.
+#[utoipa::path(
+    context_path = "/v1/opds",
+    tag = "opds",
+    params(("page" = Option<u64>, Query, description = "From 1, the default.")),
+    responses(
+        (status = 200, description = "A navigation feed.", content((String = "application/atom+xml"))),
+        (status = 401, response = Problem),
+        (status = 422, response = Problem)
+    ),
+    security(("token" = []), ("basic" = []), ("api_key" = []))
+)]
+#[get("/authors?<page>")]
+pub async fn atom_authors(
+    db: &State<DatabaseConnection>,
+    reader: ReaderUser,
+    page: Option<u64>,
+) -> Result<Catalog, ErrorResponse> {
+    let hrefs = Hrefs::new(Flavor::Atom, &reader);
+    let feed = authors(db, &hrefs, check_page(page)?).await?;
+
+    Ok(Catalog::new(
+        Flavor::Atom,
+        Kind::Navigation,
+        Flavor::Atom.feed(&feed),
+    ))
+}
+
+/// An author's books.
+#[utoipa::path(
+    context_path = "/v1/opds",
+    tag = "opds",
+    responses(
+        (status = 200, description = "An acquisition feed.", content((String = "application/atom+xml"))),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem)
+    ),
+    security(("token" = []), ("basic" = []), ("api_key" = []))
+)]
+#[get("/authors/<id>")]
+pub async fn atom_author(
+    db: &State<DatabaseConnection>,
+    reader: ReaderUser,
+    id: i32,
+) -> Result<Catalog, ErrorResponse> {
+    let hrefs = Hrefs::new(Flavor::Atom, &reader);
+    let feed = author(db, &hrefs, id).await?;
+
+    Ok(Catalog::new(
+        Flavor::Atom,
+        Kind::Acquisition,
+        Flavor::Atom.feed(&feed),
+    ))
+}
+
+/// Genres by name, 50 to a page.
+#[utoipa::path(
+    context_path = "/v1/opds",
+    tag = "opds",
+    params(("page" = Option<u64>, Query, description = "From 1, the default.")),
+    responses(
+        (status = 200, description = "A navigation feed.", content((String = "application/atom+xml"))),
+        (status = 401, response = Problem),
+        (status = 422, response = Problem)
+    ),
+    security(("token" = []), ("basic" = []), ("api_key" = []))
+)]
+#[get("/genres?<page>")]
+pub async fn atom_genres(
+    db: &State<DatabaseConnection>,
+    reader: ReaderUser,
+    page: Option<u64>,
+) -> Result<Catalog, ErrorResponse> {
+    let hrefs = Hrefs::new(Flavor::Atom, &reader);
+    let feed = genres(db, &hrefs, check_page(page)?).await?;
+
+    Ok(Catalog::new(
+        Flavor::Atom,
+        Kind::Navigation,
+        Flavor::Atom.feed(&feed),
+    ))
+}
+
+/// The books of a genre by title, 50 to a page.
+#[utoipa::path(
+    context_path = "/v1/opds",
+    tag = "opds",
+    params(("page" = Option<u64>, Query, description = "From 1, the default.")),
+    responses(
+        (status = 200, description = "An acquisition feed.", content((String = "application/atom+xml"))),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 422, response = Problem)
+    ),
+    security(("token" = []), ("basic" = []), ("api_key" = []))
+)]
+#[get("/genres/<name>?<page>")]
+pub async fn atom_genre(
+    db: &State<DatabaseConnection>,
+    reader: ReaderUser,
+    name: &str,
+    page: Option<u64>,
+) -> Result<Catalog, ErrorResponse> {
+    let hrefs = Hrefs::new(Flavor::Atom, &reader);
+    let feed = genre(db, &hrefs, name, check_page(page)?).await?;
+
+    Ok(Catalog::new(
+        Flavor::Atom,
+        Kind::Acquisition,
+        Flavor::Atom.feed(&feed),
+    ))
+}
+
+/// A book's complete entry, the target of its acquisition link.
+#[utoipa::path(
+    context_path = "/v1/opds",
+    tag = "opds",
+    responses(
+        (status = 200, description = "An entry.", content((String = "application/atom+xml"))),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem)
+    ),
+    security(("token" = []), ("basic" = []), ("api_key" = []))
+)]
+#[get("/books/<id>")]
+pub async fn atom_book(
+    db: &State<DatabaseConnection>,
+    reader: ReaderUser,
+    id: i32,
+) -> Result<Catalog, ErrorResponse> {
+    let hrefs = Hrefs::new(Flavor::Atom, &reader);
+    let entry = book(db, &hrefs, id).await?;
+
+    Ok(Catalog::new(
+        Flavor::Atom,
+        Kind::Entry,
+        Flavor::Atom.entry(&entry),
+    ))
+}
+
+/// The start of the OPDS 2.0 catalogue.
+#[utoipa::path(
+    context_path = "/v1/opds2",
+    tag = "opds",
+    responses(
+        (status = 200, description = "A navigation feed.", content((String = "application/opds+json"))),
+        (status = 401, response = Problem)
+    ),
+    security(("token" = []), ("basic" = []), ("api_key" = []))
+)]
+#[get("/")]
+pub async fn json_root(reader: ReaderUser) -> Catalog {
+    let hrefs = Hrefs::new(Flavor::Json, &reader);
+
+    Catalog::new(
+        Flavor::Json,
+        Kind::Navigation,
+        Flavor::Json.feed(&root(&hrefs)),
+    )
+}
+
+/// The newest books, 50 to a page.
+#[utoipa::path(
+    context_path = "/v1/opds2",
+    tag = "opds",
+    params(("page" = Option<u64>, Query, description = "From 1, the default.")),
+    responses(
+        (status = 200, description = "A feed of publications.", content((String = "application/opds+json"))),
+        (status = 401, response = Problem),
+        (status = 422, response = Problem)
+    ),
+    security(("token" = []), ("basic" = []), ("api_key" = []))
+)]
+#[get("/new?<page>")]
+pub async fn json_newest(
+    db: &State<DatabaseConnection>,
+    reader: ReaderUser,
+    page: Option<u64>,
+) -> Result<Catalog, ErrorResponse> {
+    let hrefs = Hrefs::new(Flavor::Json, &reader);
+    let feed = newest(db, &hrefs, check_page(page)?).await?;
+
+    Ok(Catalog::new(
+        Flavor::Json,
+        Kind::Acquisition,
+        Flavor::Json.feed(&feed),
+    ))
+}
+
+/// Authors by last name, 50 to a page.
+#[utoipa::path(
+    context_path = "/v1/opds2",
+    tag = "opds",
+    params(("page" = Option<u64>, Query, description = "From 1, the default.")),
+    responses(
+        (status = 200, description = "A navigation feed.", content((String = "application/opds+json"))),
+        (status = 401, response = Problem),
+        (status = 422, response = Problem)
+    ),
+    security(("token" = []), ("basic" = []), ("api_key" = []))
+)]
+#[get("/authors?<page>")]
+pub async fn json_authors(
+    db: &State<DatabaseConnection>,
+    reader: ReaderUser,
+    page: Option<u64>,
+) -> Result<Catalog, ErrorResponse> {
+    let hrefs = Hrefs::new(Flavor::Json, &reader);
+    let feed = authors(db, &hrefs, check_page(page)?).await?;
+
+    Ok(Catalog::new(
+        Flavor::Json,
+        Kind::Navigation,
+        Flavor::Json.feed(&feed),
+    ))
+}
+
+/// An author's books.
+#[utoipa::path(
+    context_path = "/v1/opds2",
+    tag = "opds",
+    responses(
+        (status = 200, description = "A feed of publications.", content((String = "application/opds+json"))),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem)
+    ),
+    security(("token" = []), ("basic" = []), ("api_key" = []))
+)]
+#[get("/authors/<id>")]
+pub async fn json_author(
+    db: &State<DatabaseConnection>,
+    reader: ReaderUser,
+    id: i32,
+) -> Result<Catalog, ErrorResponse> {
+    let hrefs = Hrefs::new(Flavor::Json, &reader);
+    let feed = author(db, &hrefs, id).await?;
+
+    Ok(Catalog::new(
+        Flavor::Json,
+        Kind::Acquisition,
+        Flavor::Json.feed(&feed),
+    ))
+}
+
+/// Genres by name, 50 to a page.
+#[utoipa::path(
+    context_path = "/v1/opds2",
+    tag = "opds",
+    params(("page" = Option<u64>, Query, description = "From 1, the default.")),
+    responses(
+        (status = 200, description = "A navigation feed.", content((String = "application/opds+json"))),
+        (status = 401, response = Problem),
+        (status = 422, response = Problem)
+    ),
+    security(("token" = []), ("basic" = []), ("api_key" = []))
+)]
+#[get("/genres?<page>")]
+pub async fn json_genres(
+    db: &State<DatabaseConnection>,
+    reader: ReaderUser,
+    page: Option<u64>,
+) -> Result<Catalog, ErrorResponse> {
+    let hrefs = Hrefs::new(Flavor::Json, &reader);
+    let feed = genres(db, &hrefs, check_page(page)?).await?;
+
+    Ok(Catalog::new(
+        Flavor::Json,
+        Kind::Navigation,
+        Flavor::Json.feed(&feed),
+    ))
+}
+
+/// The books of a genre by title, 50 to a page.
+#[utoipa::path(
+    context_path = "/v1/opds2",
+    tag = "opds",
+    params(("page" = Option<u64>, Query, description = "From 1, the default.")),
+    responses(
+        (status = 200, description = "A feed of publications.", content((String = "application/opds+json"))),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 422, response = Problem)
+    ),
+    security(("token" = []), ("basic" = []), ("api_key" = []))
+)]
+#[get("/genres/<name>?<page>")]
+pub async fn json_genre(
+    db: &State<DatabaseConnection>,
+    reader: ReaderUser,
+    name: &str,
+    page: Option<u64>,
+) -> Result<Catalog, ErrorResponse> {
+    let hrefs = Hrefs::new(Flavor::Json, &reader);
+    let feed = genre(db, &hrefs, name, check_page(page)?).await?;
+
+    Ok(Catalog::new(
+        Flavor::Json,
+        Kind::Acquisition,
+        Flavor::Json.feed(&feed),
+    ))
+}
+
+/// A book's publication, the target of its acquisition link.
+#[utoipa::path(
+    context_path = "/v1/opds2",
+    tag = "opds",
+    responses(
+        (status = 200, description = "A publication.", content((String = "application/opds-publication+json"))),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem)
+    ),
+    security(("token" = []), ("basic" = []), ("api_key" = []))
+)]
+#[get("/books/<id>")]
+pub async fn json_book(
+    db: &State<DatabaseConnection>,
+    reader: ReaderUser,
+    id: i32,
+) -> Result<Catalog, ErrorResponse> {
+    let hrefs = Hrefs::new(Flavor::Json, &reader);
+    let entry = book(db, &hrefs, id).await?;
+
+    Ok(Catalog::new(
+        Flavor::Json,
+        Kind::Entry,
+        Flavor::Json.entry(&entry),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn carries_the_api_key_percent_encoded() {
+        let hrefs = Hrefs {
+            base: ATOM_BASE,
+            api_key: Some("a&b=c".to_string()),
+        };
+
+        assert_eq!(hrefs.to("/"), "/v1/opds/?api_key=a%26b%3Dc");
+        assert_eq!(
+            hrefs.to("/books?page=2"),
+            "/v1/opds/books?page=2&api_key=a%26b%3Dc"
+        );
+    }
+
+    #[test]
+    fn links_genres_percent_encoded() {
+        assert_eq!(genre_path("Science fiction"), "/genres/Science%20fiction");
+        assert_eq!(genre_path("Crime/Thriller"), "/genres/Crime%2FThriller");
+    }
+}
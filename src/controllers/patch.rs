@@ -0,0 +1,84 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use json_patch::{Patch, PatchErrorKind};
+use rocket::{
+    data::{self, Data, FromData, Outcome},
+    http::Status,
+    request::Request,
+    serde::json::Json,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use super::{error::GuardFailure, ErrorResponse};
+
+/// The body of a PATCH request: an RFC 7396 JSON Merge Patch, or an RFC 6902
+/// JSON Patch when sent as `application/json-patch+json`.
+pub enum ReqPatch {
+    Merge(Value),
+    Json(Patch),
+}
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for ReqPatch {
+    type Error = String;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let is_json_patch = req
+            .content_type()
+            .map(|ct| ct.top() == "application" && ct.sub() == "json-patch+json")
+            .unwrap_or(false);
+
+        let value = match Json::<Value>::from_data(req, data).await {
+            Outcome::Success(v) => v.into_inner(),
+            Outcome::Error((status, e)) => {
+                GuardFailure::record(req, e.to_string());
+                return Outcome::Error((status, e.to_string()));
+            }
+            Outcome::Forward(f) => return Outcome::Forward(f),
+        };
+
+        if !is_json_patch {
+            return Outcome::Success(ReqPatch::Merge(value));
+        }
+
+        match serde_json::from_value(value) {
+            Ok(p) => Outcome::Success(ReqPatch::Json(p)),
+            Err(e) => {
+                GuardFailure::record(req, e.to_string());
+                Outcome::Error((Status::UnprocessableEntity, e.to_string()))
+            }
+        }
+    }
+}
+
+impl ReqPatch {
+    /// Applies the patch to the JSON form of `current` and reads the result back.
+    pub fn apply<T: Serialize + DeserializeOwned>(self, current: &T) -> Result<T, ErrorResponse> {
+        let mut doc = serde_json::to_value(current).map_err(ErrorResponse::internal)?;
+
+        match self {
+            ReqPatch::Merge(patch) => json_patch::merge(&mut doc, &patch),
+            ReqPatch::Json(patch) => json_patch::patch(&mut doc, &patch).map_err(|e| {
+                let status = match e.kind {
+                    PatchErrorKind::TestFailed => Status::Conflict,
+                    _ => Status::UnprocessableEntity,
+                };
+
+                ErrorResponse::new(status, format!("The patch could not be applied: {}.", e))
+            })?,
+        }
+
+        serde_json::from_value(doc).map_err(|e| {
+            ErrorResponse::new(
+                Status::UnprocessableEntity,
+                format!("The patched record is invalid: {}.", e),
+            )
+        })
+    }
+}
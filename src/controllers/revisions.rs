@@ -0,0 +1,362 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use rocket::{
+    http::Status,
+    serde::{json::Json, Serialize},
+    State,
+};
+use sea_orm::{prelude::DateTimeUtc, *};
+use serde_json::Value;
+use std::time::SystemTime;
+use utoipa::ToSchema;
+
+use super::{
+    authors::{find_author, replace_aliases, ReqAlias, ReqAuthor, ResAuthor},
+    books::{ensure_author_active, find_book, ReqBook, ResBook},
+    conditional::{guarded, Preconditions, Tagged, Versioned},
+    error::Problem,
+    ErrorResponse, Response, SuccessResponse,
+};
+use crate::auth::AuthenticatedUser;
+use crate::entities::{
+    author, book,
+    prelude::*,
+    revision,
+    sea_orm_active_enums::{RevisionAction, RevisionEntity},
+};
+use crate::idempotency::Idempotency;
+use crate::revisions::{self, AuthorWithAliases};
+use crate::validation::Validate;
+use crate::AppConfig;
+
+#[derive(Serialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct ResRevision {
+    id: i32,
+    user_id: i32,
+    action: String,
+    #[schema(value_type = Object)]
+    changes: Value,
+    #[schema(value_type = String, format = DateTime)]
+    created_at: DateTimeUtc,
+}
+
+impl From<&revision::Model> for ResRevision {
+    fn from(r: &revision::Model) -> Self {
+        Self {
+            id: r.id,
+            user_id: r.user_id,
+            action: r.action.to_value(),
+            changes: r.changes.to_owned(),
+            created_at: r.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(crate = "rocket::serde")]
+pub struct ResRevisionList {
+    total: usize,
+    revisions: Vec<ResRevision>,
+}
+
+async fn history(
+    db: &DatabaseConnection,
+    entity: RevisionEntity,
+    id: i32,
+) -> Response<Json<ResRevisionList>> {
+    let revisions = Revision::find()
+        .filter(revision::Column::Entity.eq(entity))
+        .filter(revision::Column::EntityId.eq(id))
+        .order_by_desc(revision::Column::Id)
+        .all(db)
+        .await?
+        .iter()
+        .map(ResRevision::from)
+        .collect::<Vec<_>>();
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Json(ResRevisionList {
+            total: revisions.len(),
+            revisions,
+        }),
+    )))
+}
+
+async fn find_revision(
+    db: &DatabaseConnection,
+    entity: RevisionEntity,
+    id: i32,
+    revision_id: i32,
+) -> Result<revision::Model, ErrorResponse> {
+    let revision = Revision::find_by_id(revision_id)
+        .filter(revision::Column::Entity.eq(entity))
+        .filter(revision::Column::EntityId.eq(id))
+        .one(db)
+        .await?;
+
+    match revision {
+        Some(r) if r.action == RevisionAction::Create => Err(ErrorResponse::new(
+            Status::UnprocessableEntity,
+            "A create cannot be reverted. Delete the record instead.".to_string(),
+        )),
+        Some(r) => Ok(r),
+        None => Err(ErrorResponse::new(
+            Status::NotFound,
+            "No revision found with the specified ID.".to_string(),
+        )),
+    }
+}
+
+/// Lists a book's revisions.
+#[utoipa::path(
+    context_path = "/v1/books",
+    tag = "books",
+    responses(
+        (status = 200, body = ResRevisionList),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[get("/<id>/history")]
+pub async fn book_history(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResRevisionList>> {
+    let db = db as &DatabaseConnection;
+
+    find_book(db, id, true).await?;
+
+    history(db, RevisionEntity::Book, id).await
+}
+
+/// Lists an author's revisions.
+#[utoipa::path(
+    context_path = "/v1/authors",
+    tag = "authors",
+    responses(
+        (status = 200, body = ResRevisionList),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[get("/<id>/history")]
+pub async fn author_history(
+    db: &State<DatabaseConnection>,
+    _user: AuthenticatedUser,
+    id: i32,
+) -> Response<Json<ResRevisionList>> {
+    let db = db as &DatabaseConnection;
+
+    find_author(db, id, true).await?;
+
+    history(db, RevisionEntity::Author, id).await
+}
+
+/// Sets every field changed by the revision back to the value it had before it.
+///
+/// Takes `If-Match` and is validated as a `PUT` is.
+#[utoipa::path(
+    context_path = "/v1/books",
+    tag = "books",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response replayed.")
+    ),
+    responses(
+        (status = 200, body = ResBook, headers(("ETag" = String, description = "The version of the record."))),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 409, response = Problem),
+        (status = 412, response = Problem),
+        (status = 422, response = Problem),
+        (status = 428, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[post("/<id>/revert/<revision_id>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn revert_book(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    preconditions: Preconditions,
+    _idempotency: Idempotency,
+    id: i32,
+    revision_id: i32,
+) -> Response<Tagged<Json<ResBook>>> {
+    let db = db as &DatabaseConnection;
+
+    let before = find_book(db, id, true).await?;
+    preconditions.check_if_match(&before.etag(), config.require_if_match)?;
+
+    let revision = find_revision(db, RevisionEntity::Book, id, revision_id).await?;
+    let reverted = revisions::reverted(&before, &revision);
+
+    let mut book: book::ActiveModel = before.clone().into();
+    book.set_from_json(reverted.clone())?;
+
+    let after = serde_json::from_value::<book::Model>(reverted).map_err(ErrorResponse::internal)?;
+
+    if after.deleted_at.is_none() {
+        ensure_author_active(db, after.author_id).await?;
+        ReqBook::from(&after).validate(db).await?;
+    } else {
+        ReqBook::from(&after).validate_rules()?;
+    }
+
+    book.updated_at = Set(DateTimeUtc::from(SystemTime::now()));
+    book.version = Set(before.version + 1);
+
+    let txn = db.begin().await?;
+
+    // Only applies if nobody else updated the record since it was read.
+    let book = Book::update(book)
+        .filter(book::Column::Version.eq(before.version))
+        .exec(&txn)
+        .await
+        .map_err(guarded)?;
+    revisions::record(
+        &txn,
+        user.id,
+        RevisionAction::Revert,
+        Some(&before),
+        Some(&book),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Tagged::new(Json(ResBook::from(&book)), &book),
+    )))
+}
+
+/// Sets every field changed by the revision back to the value it had before it.
+///
+/// Takes `If-Match` and is validated as a `PUT` is.
+#[utoipa::path(
+    context_path = "/v1/authors",
+    tag = "authors",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key get the first response replayed.")
+    ),
+    responses(
+        (status = 200, body = ResAuthor, headers(("ETag" = String, description = "The version of the record."))),
+        (status = 401, response = Problem),
+        (status = 404, response = Problem),
+        (status = 409, response = Problem),
+        (status = 412, response = Problem),
+        (status = 422, response = Problem),
+        (status = 428, response = Problem)
+    ),
+    security(("token" = []))
+)]
+#[post("/<id>/revert/<revision_id>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn revert_author(
+    db: &State<DatabaseConnection>,
+    config: &State<AppConfig>,
+    user: AuthenticatedUser,
+    preconditions: Preconditions,
+    _idempotency: Idempotency,
+    id: i32,
+    revision_id: i32,
+) -> Response<Tagged<Json<ResAuthor>>> {
+    let db = db as &DatabaseConnection;
+
+    let before = find_author(db, id, true).await?;
+    preconditions.check_if_match(&before.etag(), config.require_if_match)?;
+
+    if let Some(survivor) = before.merged_into_id {
+        return Err(ErrorResponse::new(
+            Status::Conflict,
+            format!(
+                "The author was merged into the author {} and can't be reverted.",
+                survivor
+            ),
+        ));
+    }
+
+    let revision = find_revision(db, RevisionEntity::Author, id, revision_id).await?;
+    let reverted = revisions::reverted(&before, &revision);
+
+    let mut author: author::ActiveModel = before.clone().into();
+    author.set_from_json(reverted.clone())?;
+
+    // Aliases are only replaced when the revision changed them. Revisions leave
+    // out an empty list, so a `null` means there were none.
+    let aliases = reverted
+        .get("aliases")
+        .map(|a| serde_json::from_value::<Option<Vec<ReqAlias>>>(a.to_owned()))
+        .transpose()
+        .map_err(ErrorResponse::internal)?
+        .map(Option::unwrap_or_default);
+
+    let after =
+        serde_json::from_value::<author::Model>(reverted).map_err(ErrorResponse::internal)?;
+    let req_author = ReqAuthor {
+        aliases,
+        ..ReqAuthor::from(&after)
+    };
+    req_author.validate(db).await?;
+
+    if before.deleted_at.is_none()
+        && after.deleted_at.is_some()
+        && before
+            .find_related(Book)
+            .filter(book::Column::DeletedAt.is_null())
+            .count(db)
+            .await?
+            > 0
+    {
+        return Err(ErrorResponse::new(
+            Status::Conflict,
+            "Reverting would delete an author who still has books. Use DELETE /authors/<id> instead."
+                .to_string(),
+        ));
+    }
+
+    author.updated_at = Set(DateTimeUtc::from(SystemTime::now()));
+    author.version = Set(before.version + 1);
+
+    let txn = db.begin().await?;
+
+    let tracked = AuthorWithAliases::load(&txn, &before).await?;
+
+    // Only applies if nobody else updated the record since it was read.
+    let author = Author::update(author)
+        .filter(author::Column::Version.eq(before.version))
+        .exec(&txn)
+        .await
+        .map_err(guarded)?;
+
+    if let Some(aliases) = &req_author.aliases {
+        replace_aliases(&txn, author.id, aliases).await?;
+    }
+
+    revisions::record(
+        &txn,
+        user.id,
+        RevisionAction::Revert,
+        Some(&tracked),
+        Some(&AuthorWithAliases::load(&txn, &author).await?),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(SuccessResponse((
+        Status::Ok,
+        Tagged::new(Json(ResAuthor::from(&author)), &author),
+    )))
+}
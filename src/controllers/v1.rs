@@ -0,0 +1,110 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use rocket::Route;
+
+use super::{
+    auth, authors, books, citations, covers, enrichment, exports, imports, merges, opds, revisions,
+};
+
+/// Where version 1 of the API is mounted.
+pub const PREFIX: &str = "/v1";
+
+/// The handlers making up v1, by the base they are mounted at below [`PREFIX`].
+///
+/// A v2 gets its own module listing its own routes; handlers and DTOs that did
+/// not change between versions are shared rather than copied.
+pub fn routes() -> Vec<(&'static str, Vec<Route>)> {
+    vec![
+        ("/auth", routes![auth::sign_in, auth::sign_up, auth::me]),
+        (
+            "/api-key",
+            routes![auth::create_api_key, auth::revoke_api_key],
+        ),
+        (
+            "/authors",
+            routes![
+                authors::index,
+                authors::create,
+                authors::bulk,
+                authors::show,
+                authors::update,
+                authors::patch,
+                authors::delete,
+                authors::restore,
+                authors::get_books,
+                revisions::author_history,
+                revisions::revert_author,
+                merges::duplicates,
+                merges::merge,
+            ],
+        ),
+        (
+            "/books",
+            routes![
+                books::index,
+                books::create,
+                books::bulk,
+                books::show,
+                books::update,
+                books::patch,
+                books::delete,
+                books::restore,
+                revisions::book_history,
+                revisions::revert_book,
+                covers::upload,
+                covers::delete,
+                covers::original,
+                covers::thumbnail,
+                citations::cite,
+                citations::cite_many,
+                enrichment::enrich,
+            ],
+        ),
+        (
+            "/export",
+            routes![exports::export_books, exports::export_authors],
+        ),
+        (
+            "/imports",
+            routes![imports::create, imports::show, imports::errors],
+        ),
+        (
+            opds::ATOM_BASE,
+            routes![
+                opds::atom_root,
+                opds::atom_newest,
+                opds::atom_authors,
+                opds::atom_author,
+                opds::atom_genres,
+                opds::atom_genre,
+                opds::atom_book,
+            ],
+        ),
+        (
+            opds::JSON_BASE,
+            routes![
+                opds::json_root,
+                opds::json_newest,
+                opds::json_authors,
+                opds::json_author,
+                opds::json_genres,
+                opds::json_genre,
+                opds::json_book,
+            ],
+        ),
+    ]
+}
+
+/// Bases that were served at the root before the API was versioned, and so are
+/// also mounted there. These aliases are deprecated; later bases are `/v1` only.
+pub const UNVERSIONED_BASES: [&str; 3] = ["/auth", "/authors", "/books"];
+
+/// Whether `route` is a v1 route also mounted at the root.
+pub fn is_unversioned_alias(route: &Route) -> bool {
+    UNVERSIONED_BASES.contains(&route.uri.base())
+}
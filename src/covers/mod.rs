@@ -0,0 +1,56 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use image::{codecs::jpeg::JpegEncoder, ImageError, ImageFormat};
+
+/// Thumbnail names and their maximum width in pixels. Thumbnails are never
+/// upscaled, so a small original is stored as is (re-encoded as JPEG).
+pub const THUMBNAIL_SIZES: [(&str, u32); 3] = [("small", 150), ("medium", 300), ("large", 600)];
+
+const THUMBNAIL_QUALITY: u8 = 85;
+
+/// The image formats accepted for upload.
+pub const ALLOWED_FORMATS: [ImageFormat; 3] =
+    [ImageFormat::Jpeg, ImageFormat::Png, ImageFormat::WebP];
+
+pub fn original_key(book_id: i32) -> String {
+    format!("covers/{}/original", book_id)
+}
+
+pub fn thumbnail_key(book_id: i32, size: &str) -> String {
+    format!("covers/{}/{}", book_id, size)
+}
+
+/// Detects the format of an image from its contents, ignoring anything
+/// not in [`ALLOWED_FORMATS`].
+pub fn detect_format(bytes: &[u8]) -> Option<ImageFormat> {
+    image::guess_format(bytes)
+        .ok()
+        .filter(|f| ALLOWED_FORMATS.contains(f))
+}
+
+/// Decodes `bytes` and renders each of [`THUMBNAIL_SIZES`] as a JPEG.
+pub fn thumbnails(bytes: &[u8]) -> Result<Vec<(&'static str, Vec<u8>)>, ImageError> {
+    let img = image::load_from_memory(bytes)?;
+
+    THUMBNAIL_SIZES
+        .iter()
+        .map(|&(name, width)| {
+            let thumb = if img.width() > width {
+                img.thumbnail(width, u32::MAX)
+            } else {
+                img.clone()
+            };
+
+            let mut out = vec![];
+            JpegEncoder::new_with_quality(&mut out, THUMBNAIL_QUALITY)
+                .encode_image(&thumb.to_rgb8())?;
+
+            Ok((name, out))
+        })
+        .collect()
+}
@@ -1,8 +1,10 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.1
 
+use super::sea_orm_active_enums::PublishedPrecision;
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "author")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -13,12 +15,33 @@ pub struct Model {
     pub bio: String,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
+    pub deleted_at: Option<DateTimeUtc>,
+    pub version: i32,
+    pub merged_into_id: Option<i32>,
+    pub sort_name: String,
+    pub born_on: Option<Date>,
+    pub born_precision: Option<PublishedPrecision>,
+    pub died_on: Option<Date>,
+    pub died_precision: Option<PublishedPrecision>,
+    pub nationality: Option<String>,
+    pub website: Option<String>,
+    pub photo: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::author_alias::Entity")]
+    AuthorAlias,
     #[sea_orm(has_many = "super::book::Entity")]
     Book,
+    #[sea_orm(
+        belongs_to = "Entity",
+        from = "Column::MergedIntoId",
+        to = "Column::Id",
+        on_update = "Restrict",
+        on_delete = "Restrict"
+    )]
+    SelfRef,
     #[sea_orm(
         belongs_to = "super::user::Entity",
         from = "Column::UserId",
@@ -29,6 +52,12 @@ pub enum Relation {
     User,
 }
 
+impl Related<super::author_alias::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AuthorAlias.def()
+    }
+}
+
 impl Related<super::book::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Book.def()
@@ -1,8 +1,10 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.1
 
+use super::sea_orm_active_enums::PublishedPrecision;
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "book")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -10,10 +12,19 @@ pub struct Model {
     pub user_id: i32,
     pub author_id: i32,
     pub title: String,
-    pub year: String,
+    pub published_on: Option<Date>,
+    pub published_precision: Option<PublishedPrecision>,
     pub cover: String,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
+    pub deleted_at: Option<DateTimeUtc>,
+    pub version: i32,
+    #[sea_orm(unique)]
+    pub isbn: Option<String>,
+    pub publisher: Option<String>,
+    pub edition: Option<String>,
+    pub pages: Option<i32>,
+    pub genre: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
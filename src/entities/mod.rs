@@ -3,5 +3,13 @@
 pub mod prelude;
 
 pub mod author;
+pub mod author_alias;
 pub mod book;
+pub mod idempotency_key;
+pub mod import_job;
+pub mod open_library_author;
+pub mod open_library_edition;
+pub mod open_library_work;
+pub mod revision;
+pub mod sea_orm_active_enums;
 pub mod user;
@@ -1,5 +1,12 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.1
 
 pub use super::author::Entity as Author;
+pub use super::author_alias::Entity as AuthorAlias;
 pub use super::book::Entity as Book;
+pub use super::idempotency_key::Entity as IdempotencyKey;
+pub use super::import_job::Entity as ImportJob;
+pub use super::open_library_author::Entity as OpenLibraryAuthor;
+pub use super::open_library_edition::Entity as OpenLibraryEdition;
+pub use super::open_library_work::Entity as OpenLibraryWork;
+pub use super::revision::Entity as Revision;
 pub use super::user::Entity as User;
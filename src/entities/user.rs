@@ -1,8 +1,9 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.1
 
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "user")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -14,6 +15,9 @@ pub struct Model {
     pub lastname: Option<String>,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
+    pub role: String,
+    #[sea_orm(unique)]
+    pub api_key: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -22,6 +26,8 @@ pub enum Relation {
     Author,
     #[sea_orm(has_many = "super::book::Entity")]
     Book,
+    #[sea_orm(has_many = "super::revision::Entity")]
+    Revision,
 }
 
 impl Related<super::author::Entity> for Entity {
@@ -36,4 +42,10 @@ impl Related<super::book::Entity> for Entity {
     }
 }
 
+impl Related<super::revision::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Revision.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,435 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use chrono::Utc;
+use rocket::{
+    futures::Stream,
+    http::{Accept, ContentType, Header, MediaType},
+    request::Request,
+    response::{self, stream::ByteStream, Responder},
+    tokio::{fs::File, io::AsyncReadExt, task},
+};
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+use sea_orm::{Condition, DatabaseConnection, DbErr};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::marc;
+use crate::onix;
+
+/// Rows fetched from the database at a time, and so held in memory at once.
+const PAGE_SIZE: u64 = 500;
+
+/// Bytes of a finished spreadsheet sent at a time.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+    Xlsx,
+    Onix,
+    Marcxml,
+}
+
+impl ExportFormat {
+    /// The formats anything can be exported as.
+    pub const TABULAR: &'static [ExportFormat] =
+        &[ExportFormat::Csv, ExportFormat::Jsonl, ExportFormat::Xlsx];
+
+    /// The one of `allowed` called `name`.
+    pub fn from_name(name: &str, allowed: &[Self]) -> Option<Self> {
+        allowed.iter().copied().find(|f| f.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Onix => "onix",
+            ExportFormat::Marcxml => "marcxml",
+        }
+    }
+
+    /// Picks the one of `allowed` the client prefers from its `Accept` header,
+    /// CSV if it accepts anything. `None` if it accepts none of them.
+    pub fn negotiate(accept: Option<&Accept>, allowed: &[Self]) -> Option<Self> {
+        let accept = match accept {
+            Some(accept) => accept,
+            None => return Some(ExportFormat::Csv),
+        };
+
+        let mut accepted = accept
+            .iter()
+            .filter(|m| m.weight_or(1.0) > 0.0)
+            .collect::<Vec<_>>();
+
+        // Stable, so equally weighted types keep the client's order.
+        accepted.sort_by(|a, b| b.weight_or(1.0).total_cmp(&a.weight_or(1.0)));
+
+        accepted.into_iter().find_map(|m| {
+            let m = m.media_type();
+
+            if m.top() == "*" {
+                return Some(ExportFormat::Csv);
+            }
+
+            allowed.iter().copied().find(|f| {
+                let own = f.media_type();
+                m.top() == own.top() && (m.sub() == "*" || m.sub() == own.sub())
+            })
+        })
+    }
+
+    pub fn media_type(&self) -> MediaType {
+        match self {
+            ExportFormat::Csv => MediaType::CSV,
+            ExportFormat::Jsonl => MediaType::new("application", "x-ndjson"),
+            ExportFormat::Xlsx => MediaType::new(
+                "application",
+                "vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            ),
+            ExportFormat::Onix => MediaType::new("application", "xml"),
+            ExportFormat::Marcxml => MediaType::new("application", "marcxml+xml"),
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Onix | ExportFormat::Marcxml => "xml",
+        }
+    }
+}
+
+/// A record as exported: one row of CSV or of the spreadsheet, one line of
+/// JSON Lines.
+#[rocket::async_trait]
+pub trait Exportable: Serialize + Sized + Send + 'static {
+    /// Names the file and the worksheet.
+    const NAME: &'static str;
+
+    /// The serialized fields, in column order.
+    const COLUMNS: &'static [&'static str];
+
+    /// The formats the records can be exported as.
+    const FORMATS: &'static [ExportFormat] = ExportFormat::TABULAR;
+
+    /// Up to `limit` records matching `filter` with an ID above `after`, by ID.
+    async fn page(
+        db: &DatabaseConnection,
+        filter: Condition,
+        after: i32,
+        limit: u64,
+    ) -> Result<Vec<Self>, DbErr>;
+
+    fn id(&self) -> i32;
+
+    /// The record as an ONIX product, for those exported as ONIX. Links to
+    /// this server are made absolute with `base_url`.
+    fn onix(&self, _base_url: &str) -> Option<onix::Product> {
+        None
+    }
+
+    /// The record as a MARC record, for those exported as MARCXML. Links to
+    /// this server are made absolute with `base_url`.
+    fn marc(&self, _base_url: &str) -> Option<marc::Record> {
+        None
+    }
+}
+
+/// `href` as an absolute URL, resolving a path on this server against `base_url`.
+pub fn absolute_url(base_url: &str, href: &str) -> String {
+    if href.starts_with("http://") || href.starts_with("https://") {
+        return href.to_string();
+    }
+
+    format!(
+        "{}/{}",
+        base_url.trim_end_matches('/'),
+        href.trim_start_matches('/')
+    )
+}
+
+/// An export download. Its body is produced as it is sent.
+pub struct Export<S> {
+    format: ExportFormat,
+    name: &'static str,
+    body: ByteStream<S>,
+}
+
+impl<'r, S: Stream<Item = Vec<u8>> + Send + 'r> Responder<'r, 'r> for Export<S> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'r> {
+        rocket::Response::build_from(self.body.respond_to(req)?)
+            .header(ContentType(self.format.media_type()))
+            .header(Header::new(
+                "Content-Disposition",
+                format!(
+                    "attachment; filename=\"{}.{}\"",
+                    self.name,
+                    self.format.extension()
+                ),
+            ))
+            .ok()
+    }
+}
+
+/// Exports every `T` matching `filter`, a page at a time.
+///
+/// CSV, JSON Lines, ONIX and MARCXML are sent page by page. A spreadsheet is a zip archive,
+/// so it is written to a temporary file in constant memory and sent once
+/// complete. The status is sent before the first row is fetched, so an error
+/// after that can only be logged and cut the download short.
+pub fn export<T: Exportable>(
+    db: DatabaseConnection,
+    format: ExportFormat,
+    filter: Condition,
+    base_url: String,
+) -> Export<impl Stream<Item = Vec<u8>>> {
+    let body = ByteStream! {
+        let mut pages = Pages::<T>::new(db, filter);
+
+        match format {
+            ExportFormat::Csv | ExportFormat::Jsonl => {
+                if format == ExportFormat::Csv {
+                    yield csv_line(T::COLUMNS.iter().map(|c| c.to_string()));
+                }
+
+                while let Some(page) = pages.next().await {
+                    let mut chunk = vec![];
+
+                    for record in page {
+                        if format == ExportFormat::Csv {
+                            let record = serde_json::to_value(record).unwrap_or_default();
+                            chunk.extend(csv_line(T::COLUMNS.iter().map(|c| cell(&record[c]))));
+                        } else if serde_json::to_writer(&mut chunk, &record).is_ok() {
+                            chunk.push(b'\n');
+                        }
+                    }
+
+                    yield chunk;
+                }
+            }
+            ExportFormat::Onix => {
+                yield onix::header("BookStore", Utc::now()).into_bytes();
+
+                while let Some(page) = pages.next().await {
+                    yield page
+                        .iter()
+                        .filter_map(|r| r.onix(&base_url))
+                        .map(|p| p.to_xml())
+                        .collect::<String>()
+                        .into_bytes();
+                }
+
+                yield onix::footer().into_bytes();
+            }
+            ExportFormat::Marcxml => {
+                yield marc::header().into_bytes();
+
+                while let Some(page) = pages.next().await {
+                    yield page
+                        .iter()
+                        .filter_map(|r| r.marc(&base_url))
+                        .map(|r| r.to_xml())
+                        .collect::<String>()
+                        .into_bytes();
+                }
+
+                yield marc::footer().into_bytes();
+            }
+            ExportFormat::Xlsx => {
+                let file = match spreadsheet::<T>(&mut pages).await {
+                    Ok(file) => file,
+                    Err(e) => {
+                        error!("Exporting {} as a spreadsheet failed: {}", T::NAME, e);
+                        return;
+                    }
+                };
+
+                let mut reader = match file.reopen() {
+                    Ok(f) => File::from_std(f),
+                    Err(e) => {
+                        error!("Reading the {} spreadsheet failed: {}", T::NAME, e);
+                        return;
+                    }
+                };
+
+                let mut buffer = vec![0; CHUNK_SIZE];
+
+                loop {
+                    match reader.read(&mut buffer).await {
+                        Ok(0) => break,
+                        Ok(n) => yield buffer[..n].to_vec(),
+                        Err(e) => {
+                            error!("Reading the {} spreadsheet failed: {}", T::NAME, e);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    Export {
+        format,
+        name: T::NAME,
+        body,
+    }
+}
+
+/// Walks the records matching a filter a page at a time, ending early on a
+/// database error, which it logs.
+struct Pages<T> {
+    db: DatabaseConnection,
+    filter: Condition,
+    after: i32,
+    done: bool,
+    _record: std::marker::PhantomData<T>,
+}
+
+impl<T: Exportable> Pages<T> {
+    fn new(db: DatabaseConnection, filter: Condition) -> Self {
+        Self {
+            db,
+            filter,
+            after: 0,
+            done: false,
+            _record: std::marker::PhantomData,
+        }
+    }
+
+    async fn next(&mut self) -> Option<Vec<T>> {
+        if self.done {
+            return None;
+        }
+
+        match T::page(&self.db, self.filter.clone(), self.after, PAGE_SIZE).await {
+            Ok(page) => {
+                self.done = (page.len() as u64) < PAGE_SIZE;
+                self.after = page.last().map_or(self.after, |r| r.id());
+
+                if page.is_empty() {
+                    None
+                } else {
+                    Some(page)
+                }
+            }
+            Err(e) => {
+                error!("Exporting {} failed: {}", T::NAME, e);
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
+/// Formats one line of CSV.
+fn csv_line(fields: impl Iterator<Item = String>) -> Vec<u8> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+
+    // Writing to memory only fails on I/O errors, which it can't have.
+    let _ = writer.write_record(fields.collect::<Vec<_>>());
+    writer.into_inner().unwrap_or_default()
+}
+
+/// A field as CSV text: nulls are empty, strings unquoted.
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.to_owned(),
+        other => other.to_string(),
+    }
+}
+
+/// Writes every record into a spreadsheet in a temporary file, which is
+/// deleted once the returned handle is dropped.
+async fn spreadsheet<T: Exportable>(
+    pages: &mut Pages<T>,
+) -> Result<tempfile::NamedTempFile, XlsxError> {
+    let mut workbook = Workbook::new();
+    let mut sheet = workbook.new_worksheet_with_constant_memory();
+    sheet.set_name(T::NAME)?;
+
+    let bold = Format::new().set_bold();
+    for (col, name) in T::COLUMNS.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *name, &bold)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+
+    let mut row = 0;
+    while let Some(page) = pages.next().await {
+        for record in page {
+            row += 1;
+            write_row(
+                &mut sheet,
+                row,
+                T::COLUMNS,
+                &serde_json::to_value(record).unwrap_or_default(),
+            )?;
+        }
+    }
+
+    workbook.push_worksheet(sheet);
+
+    task::spawn_blocking(move || {
+        let file = tempfile::NamedTempFile::new()?;
+        workbook.save_to_writer(file.as_file())?;
+        Ok(file)
+    })
+    .await
+    .map_err(|e| XlsxError::CustomError(e.to_string()))?
+}
+
+fn write_row(
+    sheet: &mut Worksheet,
+    row: u32,
+    columns: &[&str],
+    record: &Value,
+) -> Result<(), XlsxError> {
+    for (col, name) in columns.iter().enumerate() {
+        let col = col as u16;
+
+        match &record[name] {
+            Value::Null => {}
+            Value::Bool(b) => {
+                sheet.write_boolean(row, col, *b)?;
+            }
+            Value::Number(n) => {
+                sheet.write_number(row, col, n.as_f64().unwrap_or_default())?;
+            }
+            Value::String(s) => {
+                sheet.write_string(row, col, s)?;
+            }
+            other => {
+                sheet.write_string(row, col, other.to_string())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn makes_paths_on_this_server_absolute() {
+        assert_eq!(
+            absolute_url("https://books.example.com/", "/v1/books/7/cover"),
+            "https://books.example.com/v1/books/7/cover"
+        );
+        assert_eq!(
+            absolute_url("https://books.example.com", "https://cdn.example.com/7.jpg"),
+            "https://cdn.example.com/7.jpg"
+        );
+    }
+}
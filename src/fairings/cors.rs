@@ -29,6 +29,10 @@ impl Fairing for Cors {
             "GET, POST, PUT, PATCH, DELETE, OPTIONS",
         ));
         response.set_header(Header::new("Access-Control-Allow-Headers", "*"));
+        response.set_header(Header::new(
+            "Access-Control-Expose-Headers",
+            "ETag, X-Request-Id, Deprecation, Sunset, Link, Idempotent-Replayed",
+        ));
         response.set_header(Header::new("Access-Control-Allow-Credentials", "true"));
     }
 }
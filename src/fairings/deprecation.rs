@@ -0,0 +1,50 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    http::Header,
+    Request, Response,
+};
+
+use crate::controllers::v1;
+
+/// When the unversioned routes were deprecated (2026-10-19), as an RFC 9745 date.
+const DEPRECATED_AT: &str = "@1792368000";
+
+/// When the unversioned routes will be removed, as an RFC 8594 HTTP-date.
+const SUNSET: &str = "Mon, 19 Apr 2027 00:00:00 GMT";
+
+/// Marks responses to unversioned routes as deprecated and points to the `/v1` equivalent.
+pub struct Deprecation;
+
+#[rocket::async_trait]
+impl Fairing for Deprecation {
+    fn info(&self) -> Info {
+        Info {
+            name: "Mark unversioned routes as deprecated",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        if !req.route().is_some_and(v1::is_unversioned_alias) {
+            return;
+        }
+
+        res.set_header(Header::new("Deprecation", DEPRECATED_AT));
+        res.set_header(Header::new("Sunset", SUNSET));
+        res.set_header(Header::new(
+            "Link",
+            format!(
+                "<{}{}>; rel=\"successor-version\"",
+                v1::PREFIX,
+                req.uri().path()
+            ),
+        ));
+    }
+}
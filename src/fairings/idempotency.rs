@@ -0,0 +1,60 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use chrono::Duration;
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    tokio::{self, time},
+    Orbit, Request, Response, Rocket,
+};
+use sea_orm::DatabaseConnection;
+
+use crate::idempotency;
+
+const PURGE_INTERVAL: time::Duration = time::Duration::from_secs(60 * 60);
+
+/// Stores the responses of requests sent with an `Idempotency-Key` and replays
+/// them on retries, and periodically forgets keys older than `ttl_hours`.
+///
+/// Attach right after [`super::request_id::RequestId`], so the stored headers
+/// are those of the handler and not of the other fairings.
+pub struct Idempotency {
+    pub ttl_hours: i64,
+}
+
+#[rocket::async_trait]
+impl Fairing for Idempotency {
+    fn info(&self) -> Info {
+        Info {
+            name: "Idempotency keys",
+            kind: Kind::Response | Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let db = rocket.state::<DatabaseConnection>().unwrap().clone();
+        let ttl = Duration::hours(self.ttl_hours);
+
+        tokio::spawn(async move {
+            let mut interval = time::interval(PURGE_INTERVAL);
+
+            loop {
+                interval.tick().await;
+
+                match idempotency::purge(&db, ttl).await {
+                    Ok(0) => {}
+                    Ok(n) => info!("Forgot {} expired idempotency key(s).", n),
+                    Err(e) => error!("Purging expired idempotency keys failed: {}", e),
+                }
+            }
+        });
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        idempotency::finish(req, res).await;
+    }
+}
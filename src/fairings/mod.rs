@@ -6,3 +6,7 @@
  * @link   https://github.com/AfaanBilal/bookstore
  */
 pub mod cors;
+pub mod deprecation;
+pub mod idempotency;
+pub mod purge;
+pub mod request_id;
@@ -0,0 +1,128 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use chrono::{Duration, Utc};
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    tokio::{self, time},
+    Orbit, Rocket,
+};
+use sea_orm::{sea_query::Query, *};
+use std::sync::Arc;
+
+use crate::covers::{self, THUMBNAIL_SIZES};
+use crate::entities::{author, book, prelude::*};
+use crate::storage::Storage;
+
+const PURGE_INTERVAL: time::Duration = time::Duration::from_secs(60 * 60);
+
+/// Periodically hard-deletes books and authors that were soft-deleted
+/// more than `retention_days` ago.
+pub struct Purge {
+    pub retention_days: i64,
+}
+
+#[rocket::async_trait]
+impl Fairing for Purge {
+    fn info(&self) -> Info {
+        Info {
+            name: "Purge soft-deleted records",
+            kind: Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let db = rocket.state::<DatabaseConnection>().unwrap().clone();
+        let storage = rocket.state::<Arc<dyn Storage>>().unwrap().clone();
+        let retention = Duration::days(self.retention_days);
+
+        tokio::spawn(async move {
+            let mut interval = time::interval(PURGE_INTERVAL);
+
+            loop {
+                interval.tick().await;
+
+                match purge(&db, storage.as_ref(), retention).await {
+                    Ok((0, 0)) => {}
+                    Ok((books, authors)) => info!(
+                        "Purged {} book(s) and {} author(s) deleted more than {} day(s) ago.",
+                        books,
+                        authors,
+                        retention.num_days()
+                    ),
+                    Err(e) => error!("Purging soft-deleted records failed: {}", e),
+                }
+            }
+        });
+    }
+}
+
+/// Returns the number of books and authors purged. Authors that still have
+/// books (deleted or not) are kept until those books are gone, and merged
+/// authors are kept for good.
+async fn purge(
+    db: &DatabaseConnection,
+    storage: &dyn Storage,
+    retention: Duration,
+) -> Result<(u64, u64), DbErr> {
+    let cutoff = Utc::now() - retention;
+
+    let books = Book::find()
+        .filter(book::Column::DeletedAt.lt(cutoff))
+        .all(db)
+        .await?;
+
+    for b in &books {
+        let keys = std::iter::once(covers::original_key(b.id)).chain(
+            THUMBNAIL_SIZES
+                .iter()
+                .map(|&(size, _)| covers::thumbnail_key(b.id, size)),
+        );
+
+        for key in keys {
+            if let Err(e) = storage.delete(&key).await {
+                warn!(
+                    "Could not delete {} while purging book {}: {}",
+                    key, b.id, e
+                );
+            }
+        }
+    }
+
+    let purged_books = Book::delete_many()
+        .filter(book::Column::Id.is_in(books.iter().map(|b| b.id)))
+        .exec(db)
+        .await?
+        .rows_affected;
+
+    // Merged authors are kept so their IDs keep redirecting, and with them
+    // the authors they were merged into.
+    let survivors = Author::find()
+        .filter(author::Column::MergedIntoId.is_not_null())
+        .all(db)
+        .await?
+        .into_iter()
+        .filter_map(|a| a.merged_into_id);
+
+    let purged_authors = Author::delete_many()
+        .filter(author::Column::DeletedAt.lt(cutoff))
+        .filter(author::Column::MergedIntoId.is_null())
+        .filter(author::Column::Id.is_not_in(survivors))
+        .filter(
+            author::Column::Id.not_in_subquery(
+                Query::select()
+                    .column(book::Column::AuthorId)
+                    .from(Book)
+                    .to_owned(),
+            ),
+        )
+        .exec(db)
+        .await?
+        .rows_affected;
+
+    Ok((purged_books, purged_authors))
+}
@@ -0,0 +1,62 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    http::Header,
+    Data, Request, Response,
+};
+use uuid::Uuid;
+
+/// Tags every request with an ID, echoed in the `X-Request-Id` response header
+/// and in error bodies so that client reports can be matched with server logs.
+pub struct RequestId;
+
+/// The ID of the current request.
+pub struct CurrentRequestId(String);
+
+impl CurrentRequestId {
+    pub fn of<'r>(req: &'r Request<'_>) -> &'r str {
+        &req.local_cache(|| CurrentRequestId(Uuid::new_v4().to_string()))
+            .0
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for RequestId {
+    fn info(&self) -> Info {
+        Info {
+            name: "Assign request IDs",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _: &mut Data<'_>) {
+        // Honour an ID set by a proxy in front of us, if it looks sane.
+        let id = req
+            .headers()
+            .get_one("X-Request-Id")
+            .filter(|id| {
+                !id.is_empty()
+                    && id.len() <= 128
+                    && id
+                        .bytes()
+                        .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+            })
+            .map(str::to_string)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        req.local_cache(|| CurrentRequestId(id));
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        res.set_header(Header::new(
+            "X-Request-Id",
+            CurrentRequestId::of(req).to_string(),
+        ));
+    }
+}
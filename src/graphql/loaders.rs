@@ -0,0 +1,73 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use async_graphql::dataloader::Loader;
+use sea_orm::*;
+use std::{collections::HashMap, sync::Arc};
+
+use crate::controllers::embed;
+use crate::entities::{author, author_alias, book, prelude::*, user};
+
+/// Authors by ID, deleted or not.
+pub struct AuthorLoader(pub DatabaseConnection);
+
+impl Loader<i32> for AuthorLoader {
+    type Value = author::Model;
+    type Error = Arc<DbErr>;
+
+    async fn load(&self, ids: &[i32]) -> Result<HashMap<i32, Self::Value>, Self::Error> {
+        Ok(Author::find()
+            .filter(author::Column::Id.is_in(ids.to_vec()))
+            .all(&self.0)
+            .await?
+            .into_iter()
+            .map(|a| (a.id, a))
+            .collect())
+    }
+}
+
+/// The aliases of each author, oldest first.
+pub struct AliasesByAuthorLoader(pub DatabaseConnection);
+
+impl Loader<i32> for AliasesByAuthorLoader {
+    type Value = Vec<author_alias::Model>;
+    type Error = Arc<DbErr>;
+
+    async fn load(&self, author_ids: &[i32]) -> Result<HashMap<i32, Self::Value>, Self::Error> {
+        Ok(embed::aliases_by_author(&self.0, author_ids.iter().copied()).await?)
+    }
+}
+
+/// The live books of each author, most recently updated first.
+pub struct BooksByAuthorLoader(pub DatabaseConnection);
+
+impl Loader<i32> for BooksByAuthorLoader {
+    type Value = Vec<book::Model>;
+    type Error = Arc<DbErr>;
+
+    async fn load(&self, author_ids: &[i32]) -> Result<HashMap<i32, Self::Value>, Self::Error> {
+        Ok(embed::books_by_author(&self.0, author_ids.iter().copied()).await?)
+    }
+}
+
+/// Users by ID.
+pub struct UserLoader(pub DatabaseConnection);
+
+impl Loader<i32> for UserLoader {
+    type Value = user::Model;
+    type Error = Arc<DbErr>;
+
+    async fn load(&self, ids: &[i32]) -> Result<HashMap<i32, Self::Value>, Self::Error> {
+        Ok(User::find()
+            .filter(user::Column::Id.is_in(ids.to_vec()))
+            .all(&self.0)
+            .await?
+            .into_iter()
+            .map(|u| (u.id, u))
+            .collect())
+    }
+}
@@ -0,0 +1,385 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use async_graphql::{
+    dataloader::DataLoader, Context, EmptySubscription, Enum, Object, Request, Result, Schema,
+};
+use rocket::serde::json::Json;
+use sea_orm::*;
+
+use crate::auth::AuthenticatedUser;
+use crate::controllers::{
+    self, authors::ReqAuthor, books::ReqBook, conditional::Preconditions, ErrorResponse,
+    SuccessResponse,
+};
+use crate::entities::{author, book, prelude::*};
+use crate::idempotency::{Idempotency, Idempotent};
+use crate::AppConfig;
+
+mod loaders;
+mod types;
+
+use loaders::{AliasesByAuthorLoader, AuthorLoader, BooksByAuthorLoader, UserLoader};
+
+/// Where the endpoint is mounted.
+pub const PATH: &str = "/graphql";
+
+/// How deeply queries may nest, e.g. `authors { books { author { ... } } }`.
+const MAX_DEPTH: usize = 10;
+
+pub type BookStoreSchema = Schema<Query, Mutation, EmptySubscription>;
+
+pub fn schema(db: DatabaseConnection, config: AppConfig) -> BookStoreSchema {
+    Schema::build(Query, Mutation, EmptySubscription)
+        .data(db)
+        .data(config)
+        .limit_depth(MAX_DEPTH)
+        .finish()
+}
+
+/// Attaches the signed-in user and fresh loaders, so nothing is cached across requests.
+pub fn prepare(request: Request, db: &DatabaseConnection, user: AuthenticatedUser) -> Request {
+    let spawner = |f| rocket::tokio::spawn(f);
+
+    request
+        .data(user)
+        .data(DataLoader::new(AliasesByAuthorLoader(db.clone()), spawner))
+        .data(DataLoader::new(AuthorLoader(db.clone()), spawner))
+        .data(DataLoader::new(BooksByAuthorLoader(db.clone()), spawner))
+        .data(DataLoader::new(UserLoader(db.clone()), spawner))
+}
+
+fn db<'c>(ctx: &Context<'c>) -> &'c DatabaseConnection {
+    ctx.data_unchecked::<DatabaseConnection>()
+}
+
+fn user<'c>(ctx: &Context<'c>) -> &'c AuthenticatedUser {
+    ctx.data_unchecked::<AuthenticatedUser>()
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// The signed-in user.
+    async fn me(&self, ctx: &Context<'_>) -> Result<Option<types::User>> {
+        Ok(User::find_by_id(user(ctx).id)
+            .one(db(ctx))
+            .await
+            .map_err(ErrorResponse::from)?
+            .map(types::User))
+    }
+
+    /// `q` works as on `GET /authors`.
+    async fn authors(
+        &self,
+        ctx: &Context<'_>,
+        q: Option<String>,
+        include_deleted: Option<bool>,
+    ) -> Result<Vec<types::Author>> {
+        let filter = controllers::authors::list_filter(user(ctx), q.as_deref(), include_deleted)?;
+
+        Ok(Author::find()
+            .filter(filter)
+            .order_by_desc(author::Column::UpdatedAt)
+            .all(db(ctx))
+            .await
+            .map_err(ErrorResponse::from)?
+            .into_iter()
+            .map(types::Author)
+            .collect())
+    }
+
+    async fn author(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+        include_deleted: Option<bool>,
+    ) -> Result<Option<types::Author>> {
+        let include_deleted = controllers::include_deleted(user(ctx), include_deleted)?;
+
+        Ok(find_author(db(ctx), id)
+            .await?
+            .filter(|a| include_deleted || a.0.deleted_at.is_none()))
+    }
+
+    /// `publishedFrom`, `publishedTo` and `genre` work as on `GET /books`.
+    async fn books(
+        &self,
+        ctx: &Context<'_>,
+        published_from: Option<String>,
+        published_to: Option<String>,
+        genre: Option<String>,
+        include_deleted: Option<bool>,
+    ) -> Result<Vec<types::Book>> {
+        let filter = controllers::books::list_filter(
+            user(ctx),
+            published_from.as_deref(),
+            published_to.as_deref(),
+            genre.as_deref(),
+            include_deleted,
+        )?;
+
+        Ok(Book::find()
+            .filter(filter)
+            .order_by_desc(book::Column::UpdatedAt)
+            .all(db(ctx))
+            .await
+            .map_err(ErrorResponse::from)?
+            .into_iter()
+            .map(types::Book)
+            .collect())
+    }
+
+    async fn book(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+        include_deleted: Option<bool>,
+    ) -> Result<Option<types::Book>> {
+        let include_deleted = controllers::include_deleted(user(ctx), include_deleted)?;
+
+        Ok(find_book(db(ctx), id)
+            .await?
+            .filter(|b| include_deleted || b.0.deleted_at.is_none()))
+    }
+}
+
+/// What to do with an author's books when deleting the author.
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+enum DeleteStrategy {
+    /// Fail if the author has books.
+    Restrict,
+    /// Delete the books along with the author.
+    Cascade,
+}
+
+/// Mutations go through the REST handlers, so validation, preconditions and
+/// revision history apply the same way.
+pub struct Mutation;
+
+#[Object]
+impl Mutation {
+    async fn create_author(&self, ctx: &Context<'_>, input: ReqAuthor) -> Result<types::Author> {
+        let SuccessResponse((_, Json(res))) =
+            controllers::authors::create(db(ctx).into(), user(ctx).clone(), Idempotent(input))
+                .await?;
+
+        reload_author(ctx, res.id).await
+    }
+
+    /// Pass the author's `etag` as `ifMatch` to only update an unchanged author.
+    async fn update_author(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+        input: ReqAuthor,
+        if_match: Option<String>,
+    ) -> Result<types::Author> {
+        controllers::authors::update(
+            db(ctx).into(),
+            ctx.data_unchecked::<AppConfig>().into(),
+            user(ctx).clone(),
+            Preconditions::if_match(if_match),
+            id,
+            Json(input),
+        )
+        .await?;
+
+        reload_author(ctx, id).await
+    }
+
+    /// Works as `DELETE /authors/{id}`; `reassignTo` moves the books to another author.
+    /// Pass the author's `etag` as `ifMatch` to only delete an unchanged author.
+    async fn delete_author(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+        strategy: Option<DeleteStrategy>,
+        reassign_to: Option<i32>,
+        if_match: Option<String>,
+    ) -> Result<bool> {
+        let strategy = strategy.map(|s| match s {
+            DeleteStrategy::Restrict => "restrict",
+            DeleteStrategy::Cascade => "cascade",
+        });
+
+        controllers::authors::delete(
+            db(ctx).into(),
+            ctx.data_unchecked::<AppConfig>().into(),
+            user(ctx).clone(),
+            Preconditions::if_match(if_match),
+            id,
+            strategy,
+            reassign_to,
+        )
+        .await?;
+
+        Ok(true)
+    }
+
+    /// Pass the author's `etag` as `ifMatch` to only restore an unchanged author.
+    async fn restore_author(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+        if_match: Option<String>,
+    ) -> Result<types::Author> {
+        controllers::authors::restore(
+            db(ctx).into(),
+            ctx.data_unchecked::<AppConfig>().into(),
+            user(ctx).clone(),
+            Preconditions::if_match(if_match),
+            Idempotency,
+            id,
+        )
+        .await?;
+
+        reload_author(ctx, id).await
+    }
+
+    /// Pass the author's `etag` as `ifMatch` to only revert an unchanged author.
+    async fn revert_author(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+        revision_id: i32,
+        if_match: Option<String>,
+    ) -> Result<types::Author> {
+        controllers::revisions::revert_author(
+            db(ctx).into(),
+            ctx.data_unchecked::<AppConfig>().into(),
+            user(ctx).clone(),
+            Preconditions::if_match(if_match),
+            Idempotency,
+            id,
+            revision_id,
+        )
+        .await?;
+
+        reload_author(ctx, id).await
+    }
+
+    async fn create_book(&self, ctx: &Context<'_>, input: ReqBook) -> Result<types::Book> {
+        let SuccessResponse((_, Json(res))) =
+            controllers::books::create(db(ctx).into(), user(ctx).clone(), None, Idempotent(input))
+                .await?;
+
+        reload_book(ctx, res.id).await
+    }
+
+    /// Pass the book's `etag` as `ifMatch` to only update an unchanged book.
+    async fn update_book(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+        input: ReqBook,
+        if_match: Option<String>,
+    ) -> Result<types::Book> {
+        controllers::books::update(
+            db(ctx).into(),
+            ctx.data_unchecked::<AppConfig>().into(),
+            user(ctx).clone(),
+            Preconditions::if_match(if_match),
+            id,
+            Json(input),
+        )
+        .await?;
+
+        reload_book(ctx, id).await
+    }
+
+    /// Pass the book's `etag` as `ifMatch` to only delete an unchanged book.
+    async fn delete_book(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+        if_match: Option<String>,
+    ) -> Result<bool> {
+        controllers::books::delete(
+            db(ctx).into(),
+            ctx.data_unchecked::<AppConfig>().into(),
+            user(ctx).clone(),
+            Preconditions::if_match(if_match),
+            id,
+        )
+        .await?;
+
+        Ok(true)
+    }
+
+    /// Pass the book's `etag` as `ifMatch` to only restore an unchanged book.
+    async fn restore_book(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+        if_match: Option<String>,
+    ) -> Result<types::Book> {
+        controllers::books::restore(
+            db(ctx).into(),
+            ctx.data_unchecked::<AppConfig>().into(),
+            user(ctx).clone(),
+            Preconditions::if_match(if_match),
+            Idempotency,
+            id,
+        )
+        .await?;
+
+        reload_book(ctx, id).await
+    }
+
+    /// Pass the book's `etag` as `ifMatch` to only revert an unchanged book.
+    async fn revert_book(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+        revision_id: i32,
+        if_match: Option<String>,
+    ) -> Result<types::Book> {
+        controllers::revisions::revert_book(
+            db(ctx).into(),
+            ctx.data_unchecked::<AppConfig>().into(),
+            user(ctx).clone(),
+            Preconditions::if_match(if_match),
+            Idempotency,
+            id,
+            revision_id,
+        )
+        .await?;
+
+        reload_book(ctx, id).await
+    }
+}
+
+async fn find_author(db: &DatabaseConnection, id: i32) -> Result<Option<types::Author>> {
+    Ok(Author::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(ErrorResponse::from)?
+        .map(types::Author))
+}
+
+async fn find_book(db: &DatabaseConnection, id: i32) -> Result<Option<types::Book>> {
+    Ok(Book::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(ErrorResponse::from)?
+        .map(types::Book))
+}
+
+/// Reads back a record a mutation just wrote, for its relations to resolve.
+async fn reload_author(ctx: &Context<'_>, id: i32) -> Result<types::Author> {
+    find_author(db(ctx), id)
+        .await?
+        .ok_or_else(|| ErrorResponse::internal(format!("author {} vanished", id)).into())
+}
+
+async fn reload_book(ctx: &Context<'_>, id: i32) -> Result<types::Book> {
+    find_book(db(ctx), id)
+        .await?
+        .ok_or_else(|| ErrorResponse::internal(format!("book {} vanished", id)).into())
+}
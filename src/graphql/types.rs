@@ -0,0 +1,219 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use async_graphql::{dataloader::DataLoader, Context, Object, Result};
+use sea_orm::{prelude::DateTimeUtc, ActiveEnum};
+
+use super::loaders::{AliasesByAuthorLoader, AuthorLoader, BooksByAuthorLoader, UserLoader};
+use crate::auth::AuthenticatedUser;
+use crate::controllers::{conditional::Versioned, ErrorResponse};
+use crate::entities::{author, author_alias, book, user};
+use crate::publication::PublicationDate;
+
+pub struct User(pub user::Model);
+
+#[Object]
+impl User {
+    async fn id(&self) -> i32 {
+        self.0.id
+    }
+
+    async fn firstname(&self) -> Option<&str> {
+        self.0.firstname.as_deref()
+    }
+
+    async fn lastname(&self) -> Option<&str> {
+        self.0.lastname.as_deref()
+    }
+
+    /// Only visible to the user themselves.
+    async fn email(&self, ctx: &Context<'_>) -> Option<&str> {
+        let viewer = ctx.data_unchecked::<AuthenticatedUser>();
+
+        (viewer.id == self.0.id).then_some(self.0.email.as_str())
+    }
+}
+
+pub struct Author(pub author::Model);
+
+#[Object]
+impl Author {
+    async fn id(&self) -> i32 {
+        self.0.id
+    }
+
+    /// Blank for authors known by a single name.
+    async fn firstname(&self) -> &str {
+        &self.0.firstname
+    }
+
+    async fn lastname(&self) -> &str {
+        &self.0.lastname
+    }
+
+    /// How the author files in lists sorted by name, e.g. `Le Guin, Ursula K.`.
+    async fn sort_name(&self) -> &str {
+        &self.0.sort_name
+    }
+
+    async fn bio(&self) -> &str {
+        &self.0.bio
+    }
+
+    /// Formatted as `YYYY`, `YYYY-MM` or `YYYY-MM-DD`.
+    async fn born(&self) -> Option<String> {
+        PublicationDate::from_columns(self.0.born_on, self.0.born_precision.as_ref())
+            .map(|d| d.to_string())
+    }
+
+    /// Formatted as `YYYY`, `YYYY-MM` or `YYYY-MM-DD`.
+    async fn died(&self) -> Option<String> {
+        PublicationDate::from_columns(self.0.died_on, self.0.died_precision.as_ref())
+            .map(|d| d.to_string())
+    }
+
+    async fn nationality(&self) -> Option<&str> {
+        self.0.nationality.as_deref()
+    }
+
+    async fn website(&self) -> Option<&str> {
+        self.0.website.as_deref()
+    }
+
+    async fn photo(&self) -> Option<&str> {
+        self.0.photo.as_deref()
+    }
+
+    async fn deleted_at(&self) -> Option<DateTimeUtc> {
+        self.0.deleted_at
+    }
+
+    /// Pass as `ifMatch` to `updateAuthor` to detect concurrent edits.
+    async fn etag(&self) -> String {
+        self.0.etag()
+    }
+
+    async fn books(&self, ctx: &Context<'_>) -> Result<Vec<Book>> {
+        Ok(ctx
+            .data_unchecked::<DataLoader<BooksByAuthorLoader>>()
+            .load_one(self.0.id)
+            .await
+            .map_err(ErrorResponse::internal)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(Book)
+            .collect())
+    }
+
+    /// The other names the author is known by, pen names included.
+    async fn aliases(&self, ctx: &Context<'_>) -> Result<Vec<Alias>> {
+        Ok(ctx
+            .data_unchecked::<DataLoader<AliasesByAuthorLoader>>()
+            .load_one(self.0.id)
+            .await
+            .map_err(ErrorResponse::internal)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(Alias)
+            .collect())
+    }
+
+    /// The user who added the author.
+    async fn user(&self, ctx: &Context<'_>) -> Result<Option<User>> {
+        load_user(ctx, self.0.user_id).await
+    }
+}
+
+pub struct Alias(pub author_alias::Model);
+
+#[Object]
+impl Alias {
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    /// `alias` or `pen_name`.
+    async fn kind(&self) -> String {
+        self.0.kind.to_value()
+    }
+}
+
+pub struct Book(pub book::Model);
+
+#[Object]
+impl Book {
+    async fn id(&self) -> i32 {
+        self.0.id
+    }
+
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    /// Formatted as `YYYY`, `YYYY-MM` or `YYYY-MM-DD`.
+    async fn published(&self) -> Option<String> {
+        PublicationDate::from_columns(self.0.published_on, self.0.published_precision.as_ref())
+            .map(|d| d.to_string())
+    }
+
+    async fn cover(&self) -> &str {
+        &self.0.cover
+    }
+
+    /// As ISBN-13 digits.
+    async fn isbn(&self) -> Option<&str> {
+        self.0.isbn.as_deref()
+    }
+
+    async fn publisher(&self) -> Option<&str> {
+        self.0.publisher.as_deref()
+    }
+
+    async fn edition(&self) -> Option<&str> {
+        self.0.edition.as_deref()
+    }
+
+    async fn pages(&self) -> Option<i32> {
+        self.0.pages
+    }
+
+    async fn genre(&self) -> Option<&str> {
+        self.0.genre.as_deref()
+    }
+
+    async fn deleted_at(&self) -> Option<DateTimeUtc> {
+        self.0.deleted_at
+    }
+
+    /// Pass as `ifMatch` to `updateBook` to detect concurrent edits.
+    async fn etag(&self) -> String {
+        self.0.etag()
+    }
+
+    async fn author(&self, ctx: &Context<'_>) -> Result<Option<Author>> {
+        Ok(ctx
+            .data_unchecked::<DataLoader<AuthorLoader>>()
+            .load_one(self.0.author_id)
+            .await
+            .map_err(ErrorResponse::internal)?
+            .map(Author))
+    }
+
+    /// The user who added the book.
+    async fn user(&self, ctx: &Context<'_>) -> Result<Option<User>> {
+        load_user(ctx, self.0.user_id).await
+    }
+}
+
+async fn load_user(ctx: &Context<'_>, id: i32) -> Result<Option<User>> {
+    Ok(ctx
+        .data_unchecked::<DataLoader<UserLoader>>()
+        .load_one(id)
+        .await
+        .map_err(ErrorResponse::internal)?
+        .map(User))
+}
@@ -0,0 +1,364 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use chrono::{Duration, Utc};
+use rocket::{
+    data::{self, ByteUnit, Data, FromData, Limits, Outcome},
+    http::{Header, Status},
+    request::{self, FromRequest, Request},
+    Response,
+};
+use sea_orm::*;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use std::{io::Cursor, ops::Deref};
+
+use crate::auth::AuthenticatedUser;
+use crate::controllers::error::{is_duplicate_entry, GuardFailure};
+use crate::entities::{idempotency_key, prelude::*};
+use crate::AppConfig;
+
+pub const HEADER: &str = "Idempotency-Key";
+
+/// Set on responses replayed from an earlier request with the same key.
+pub const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+const MAX_KEY_LENGTH: usize = 255;
+
+/// Set by fairings on every response, so not stored.
+const UNSTORED_HEADERS: [&str; 1] = ["X-Request-Id"];
+
+/// A JSON body whose request honours the `Idempotency-Key` header.
+///
+/// The first request with a key runs as usual and its response is stored;
+/// retries with the same key and request get the stored response instead.
+pub struct Idempotent<T>(pub T);
+
+impl<T> Deref for Idempotent<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> Idempotent<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, T: DeserializeOwned + Send> FromData<'r> for Idempotent<T> {
+    type Error = String;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let limit = req.limits().get("json").unwrap_or(Limits::JSON);
+
+        let body = match read(req, data, limit).await {
+            Ok(body) => body,
+            Err(e) => return Outcome::Error(e),
+        };
+
+        // Malformed bodies are rejected before they can claim the key.
+        let value = match serde_json::from_slice(&body) {
+            Ok(v) => v,
+            Err(e) if e.is_data() => return fail(req, Status::UnprocessableEntity, e.to_string()),
+            Err(e) => return fail(req, Status::BadRequest, e.to_string()),
+        };
+
+        match claim(req, &body).await {
+            Ok(()) => Outcome::Success(Idempotent(value)),
+            Err(e) => Outcome::Error(e),
+        }
+    }
+}
+
+/// A raw body, such as an uploaded file, whose request honours the
+/// `Idempotency-Key` header like [`Idempotent`].
+pub struct IdempotentBytes(pub Vec<u8>);
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for IdempotentBytes {
+    type Error = String;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let limit = req.limits().get("bytes").unwrap_or(Limits::BYTES);
+
+        let body = match read(req, data, limit).await {
+            Ok(body) => body,
+            Err(e) => return Outcome::Error(e),
+        };
+
+        match claim(req, &body).await {
+            Ok(()) => Outcome::Success(IdempotentBytes(body)),
+            Err(e) => Outcome::Error(e),
+        }
+    }
+}
+
+/// Honours the `Idempotency-Key` header on a POST without a body.
+#[derive(Default)]
+pub struct Idempotency;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Idempotency {
+    type Error = String;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        match claim(req, &[]).await {
+            Ok(()) => request::Outcome::Success(Idempotency),
+            Err(e) => request::Outcome::Error(e),
+        }
+    }
+}
+
+async fn read(
+    req: &Request<'_>,
+    data: Data<'_>,
+    limit: ByteUnit,
+) -> Result<Vec<u8>, (Status, String)> {
+    let rejected = |status: Status, reason: String| {
+        GuardFailure::record(req, reason.clone());
+        Err((status, reason))
+    };
+
+    match data.open(limit).into_bytes().await {
+        Ok(body) if body.is_complete() => Ok(body.into_inner()),
+        Ok(_) => rejected(
+            Status::PayloadTooLarge,
+            format!("The body is larger than {}.", limit),
+        ),
+        Err(e) => rejected(Status::BadRequest, e.to_string()),
+    }
+}
+
+fn fail<'r, T>(
+    req: &Request<'_>,
+    status: Status,
+    reason: impl Into<String>,
+) -> data::Outcome<'r, T, String> {
+    let reason = reason.into();
+
+    GuardFailure::record(req, reason.clone());
+    Outcome::Error((status, reason))
+}
+
+/// What became of the request's key, for [`finish`] to act on.
+enum KeyState {
+    /// No key was sent.
+    None,
+    /// The request runs under this row; its response is to be stored.
+    Claimed(i32),
+    /// The request is a retry; the handler did not run.
+    Replay(idempotency_key::Model),
+}
+
+/// Claims the request's key, or arranges for the stored response to be replayed.
+async fn claim(req: &Request<'_>, body: &[u8]) -> Result<(), (Status, String)> {
+    let key = match req.headers().get_one(HEADER) {
+        Some(key) => key,
+        None => return Ok(()),
+    };
+
+    let rejected = |status: Status, reason: &str| {
+        GuardFailure::record(req, reason);
+        Err((status, reason.to_string()))
+    };
+
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH || !key.bytes().all(|b| b.is_ascii_graphic()) {
+        return rejected(
+            Status::BadRequest,
+            "The Idempotency-Key must be 1 to 255 visible ASCII characters.",
+        );
+    }
+
+    let db = req.rocket().state::<DatabaseConnection>().unwrap();
+    let config = req.rocket().state::<AppConfig>().unwrap();
+
+    // Keys are scoped to the user, so nobody can replay another user's response.
+    let user = if req.headers().contains("token") {
+        req.guard::<AuthenticatedUser>().await.succeeded()
+    } else {
+        None
+    };
+
+    let (user_id, key) = match user {
+        Some(user) => (user.id, key.to_string()),
+        None => (0, anonymous_key(req, key)),
+    };
+
+    let mut hasher = Sha256::new();
+    hasher.update(req.method().as_str());
+    hasher.update(b" ");
+    hasher.update(req.uri().to_string());
+    hasher.update(b"\n");
+    hasher.update(body);
+    let fingerprint = hex::encode(hasher.finalize());
+
+    let internal = |e: DbErr| {
+        error!("Claiming an Idempotency-Key failed: {}", e);
+        (Status::InternalServerError, e.to_string())
+    };
+
+    let existing = IdempotencyKey::find()
+        .filter(idempotency_key::Column::UserId.eq(user_id))
+        .filter(idempotency_key::Column::Key.eq(key.as_str()))
+        .one(db)
+        .await
+        .map_err(internal)?;
+
+    if let Some(existing) = existing {
+        if existing.created_at >= Utc::now() - Duration::hours(config.idempotency_ttl_hours) {
+            if existing.fingerprint != fingerprint {
+                return rejected(
+                    Status::UnprocessableEntity,
+                    "The Idempotency-Key was already used for a different request.",
+                );
+            }
+
+            if existing.response_status.is_none() {
+                return rejected(
+                    Status::Conflict,
+                    "A request with this Idempotency-Key is still in progress.",
+                );
+            }
+
+            req.local_cache(|| KeyState::Replay(existing));
+
+            // Failing keeps the handler from running; `finish` then swaps the
+            // catcher's response for the stored one.
+            return Err((Status::Conflict, "Replayed".to_string()));
+        }
+
+        existing.delete(db).await.map_err(internal)?;
+    }
+
+    let row = idempotency_key::ActiveModel {
+        user_id: Set(user_id),
+        key: Set(key),
+        fingerprint: Set(fingerprint),
+        ..Default::default()
+    };
+
+    let row = match row.insert(db).await {
+        Ok(row) => row,
+        // Another request with the same key got there first.
+        Err(e) if is_duplicate_entry(&e) => {
+            return rejected(
+                Status::Conflict,
+                "A request with this Idempotency-Key is still in progress.",
+            )
+        }
+        Err(e) => return Err(internal(e)),
+    };
+
+    req.local_cache(|| KeyState::Claimed(row.id));
+
+    Ok(())
+}
+
+/// Scopes the key of an unauthenticated request, such as a sign-up, to the
+/// client's IP address, so that anonymous clients sending the same key don't
+/// get each other's responses. Hashed to fit the column.
+fn anonymous_key(req: &Request<'_>, key: &str) -> String {
+    let ip = req
+        .client_ip()
+        .map_or_else(String::new, |ip| ip.to_string());
+
+    let mut hasher = Sha256::new();
+    hasher.update(ip);
+    hasher.update(b" ");
+    hasher.update(key);
+
+    format!("anonymous:{}", hex::encode(hasher.finalize()))
+}
+
+/// Stores the response of a request that claimed a key, or replaces the
+/// response of a retry with the stored one. Server errors are not stored, so
+/// the request can be retried with the same key.
+pub async fn finish<'r>(req: &'r Request<'_>, res: &mut Response<'r>) {
+    match req.local_cache(|| KeyState::None) {
+        KeyState::None => {}
+        KeyState::Claimed(id) => {
+            let db = req.rocket().state::<DatabaseConnection>().unwrap();
+
+            if let Err(e) = store(db, *id, res).await {
+                error!("Storing the response to an Idempotency-Key failed: {}", e);
+            }
+        }
+        KeyState::Replay(stored) => replay(stored, res),
+    }
+}
+
+async fn store(db: &DatabaseConnection, id: i32, res: &mut Response<'_>) -> Result<(), DbErr> {
+    if res.status().class().is_server_error() {
+        IdempotencyKey::delete_by_id(id).exec(db).await?;
+        return Ok(());
+    }
+
+    let body = res.body_mut().to_bytes().await.unwrap_or_default();
+
+    let headers = res
+        .headers()
+        .iter()
+        .filter(|h| {
+            !UNSTORED_HEADERS
+                .iter()
+                .any(|u| h.name().as_str().eq_ignore_ascii_case(u))
+        })
+        .map(|h| (h.name().to_string(), h.value().to_string()))
+        .collect::<Vec<_>>();
+
+    IdempotencyKey::update(idempotency_key::ActiveModel {
+        id: Unchanged(id),
+        response_status: Set(Some(res.status().code as i16)),
+        response_headers: Set(Some(serde_json::json!(headers))),
+        response_body: Set(Some(body.clone())),
+        ..Default::default()
+    })
+    .exec(db)
+    .await?;
+
+    res.set_sized_body(body.len(), Cursor::new(body));
+
+    Ok(())
+}
+
+fn replay(stored: &idempotency_key::Model, res: &mut Response<'_>) {
+    let status = stored
+        .response_status
+        .and_then(|s| Status::from_code(s as u16))
+        .unwrap_or(Status::Ok);
+
+    let headers: Vec<(String, String)> = stored
+        .response_headers
+        .clone()
+        .and_then(|h| serde_json::from_value(h).ok())
+        .unwrap_or_default();
+
+    res.set_status(status);
+    res.remove_header("Content-Type");
+
+    for (name, value) in headers {
+        res.set_header(Header::new(name, value));
+    }
+
+    res.set_header(Header::new(REPLAYED_HEADER, "true"));
+
+    let body = stored.response_body.clone().unwrap_or_default();
+    res.set_sized_body(body.len(), Cursor::new(body));
+}
+
+/// Deletes keys older than `ttl`, returning how many there were.
+pub async fn purge(db: &DatabaseConnection, ttl: Duration) -> Result<u64, DbErr> {
+    Ok(IdempotencyKey::delete_many()
+        .filter(idempotency_key::Column::CreatedAt.lt(Utc::now() - ttl))
+        .exec(db)
+        .await?
+        .rows_affected)
+}
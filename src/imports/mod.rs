@@ -0,0 +1,587 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use chrono::Utc;
+use rocket::tokio;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use crate::auth::AuthenticatedUser;
+use crate::controllers::{
+    authors, books,
+    conditional::{Preconditions, Versioned},
+};
+use crate::entities::{
+    author, author_alias, book, import_job,
+    prelude::*,
+    sea_orm_active_enums::{ImportFormat, ImportStatus},
+};
+use crate::isbn::Isbn;
+use crate::marc;
+use crate::onix;
+use crate::AppConfig;
+
+/// How often a running import reports its progress, in rows.
+const PROGRESS_EVERY: usize = 100;
+
+/// A book as read from one line of an import file. The author is given either
+/// as `author` ("First Last", split at the last space) or as
+/// `author_firstname` and `author_lastname`. A row with the ISBN of a book
+/// already in the store updates that book.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct ImportRow {
+    title: String,
+    author: Option<String>,
+    author_firstname: Option<String>,
+    author_lastname: Option<String>,
+    published: Option<String>,
+    cover: Option<String>,
+    isbn: Option<String>,
+    publisher: Option<String>,
+    edition: Option<String>,
+    pages: Option<i32>,
+    genre: Option<String>,
+}
+
+impl ImportRow {
+    fn author_name(&self) -> (String, String) {
+        let first = self.author_firstname.as_deref().unwrap_or("").trim();
+        let last = self.author_lastname.as_deref().unwrap_or("").trim();
+
+        if !first.is_empty() || !last.is_empty() {
+            return (first.to_string(), last.to_string());
+        }
+
+        match self.author.as_deref().unwrap_or("").trim().rsplit_once(' ') {
+            Some((first, last)) => (first.trim().to_string(), last.to_string()),
+            None => (String::new(), self.author.clone().unwrap_or_default()),
+        }
+    }
+}
+
+/// A row as parsed from the file, keeping its line number for the report.
+pub struct ParsedRow {
+    pub line: u64,
+    pub row: Result<ImportRow, String>,
+}
+
+/// A problem with one row, as listed in the error report.
+#[derive(Serialize, Deserialize, ToSchema, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct RowError {
+    pub line: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    pub message: String,
+}
+
+#[derive(Default)]
+pub struct Summary {
+    pub processed: usize,
+    pub failed: usize,
+    pub books_created: usize,
+    pub books_updated: usize,
+    pub authors_created: usize,
+    pub authors_matched: usize,
+    pub errors: Vec<RowError>,
+}
+
+/// Told how an import is going every [`PROGRESS_EVERY`] rows and at the end.
+#[rocket::async_trait]
+pub trait Progress: Sync {
+    async fn report(&self, summary: &Summary);
+}
+
+impl ImportFormat {
+    /// Guesses the format from a media type such as `text/csv`.
+    pub fn from_media_type(top: &str, sub: &str) -> Option<Self> {
+        match (top, sub) {
+            ("text", "csv") => Some(ImportFormat::Csv),
+            ("application", "jsonl" | "x-ndjson" | "x-jsonlines") => Some(ImportFormat::Jsonl),
+            ("application" | "text", "xml") | ("application", "onix+xml") => {
+                Some(ImportFormat::Onix)
+            }
+            ("application", "marc" | "marcxml+xml") => Some(ImportFormat::Marc),
+            _ => None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(ImportFormat::Csv),
+            "jsonl" => Some(ImportFormat::Jsonl),
+            "onix" => Some(ImportFormat::Onix),
+            "marc" => Some(ImportFormat::Marc),
+            _ => None,
+        }
+    }
+}
+
+/// Splits the file into rows. Fails only if the file as a whole is unusable;
+/// rows that can't be read are returned as errors.
+pub fn parse(format: &ImportFormat, body: &[u8]) -> Result<Vec<ParsedRow>, String> {
+    match format {
+        ImportFormat::Csv => parse_csv(body),
+        ImportFormat::Jsonl => parse_jsonl(body),
+        ImportFormat::Onix => parse_onix(body),
+        ImportFormat::Marc => parse_marc(body),
+    }
+}
+
+fn parse_csv(body: &[u8]) -> Result<Vec<ParsedRow>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(body);
+
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("The CSV header could not be read: {}.", e))?
+        .clone();
+
+    let has = |column: &str| headers.iter().any(|h| h == column);
+
+    if !has("title") || !(has("author") || has("author_lastname")) {
+        return Err(
+            "The CSV needs a title column and an author or author_firstname and author_lastname columns."
+                .to_string(),
+        );
+    }
+
+    Ok(reader
+        .records()
+        .enumerate()
+        .map(|(i, record)| match record {
+            Ok(record) => ParsedRow {
+                line: record.position().map_or(i as u64 + 2, |p| p.line()),
+                row: record
+                    .deserialize(Some(&headers))
+                    .map_err(|e| e.to_string()),
+            },
+            Err(e) => ParsedRow {
+                line: e.position().map_or(i as u64 + 2, |p| p.line()),
+                row: Err(e.to_string()),
+            },
+        })
+        .collect())
+}
+
+fn parse_jsonl(body: &[u8]) -> Result<Vec<ParsedRow>, String> {
+    let body = std::str::from_utf8(body).map_err(|e| format!("The file is not UTF-8: {}.", e))?;
+
+    Ok(body
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| ParsedRow {
+            line: i as u64 + 1,
+            row: serde_json::from_str(line).map_err(|e| e.to_string()),
+        })
+        .collect())
+}
+
+/// Reads each product of an ONIX 3.0 message as a row. Products without an
+/// ISBN can't be matched on a later import, so they are rejected, as are
+/// deletions, which are left to be done by hand.
+fn parse_onix(body: &[u8]) -> Result<Vec<ParsedRow>, String> {
+    Ok(onix::parse(body)?
+        .into_iter()
+        .map(|p| ParsedRow {
+            line: p.line,
+            row: if p.deleted {
+                Err("Delete notifications are not applied.".to_string())
+            } else if p.isbn.is_none() {
+                Err("The product has no ISBN.".to_string())
+            } else {
+                Ok(ImportRow {
+                    title: p.title,
+                    author: None,
+                    author_firstname: Some(p.author_firstname),
+                    author_lastname: Some(p.author_lastname),
+                    published: p.published,
+                    cover: p.cover,
+                    isbn: p.isbn,
+                    publisher: p.publisher,
+                    edition: p.edition,
+                    pages: p.pages,
+                    genre: None,
+                })
+            },
+        })
+        .collect())
+}
+
+/// Reads each record of a MARC21 or MARCXML file as a row, its number in the
+/// file standing for the line in MARC21. Deleted records are rejected, as for
+/// ONIX.
+fn parse_marc(body: &[u8]) -> Result<Vec<ParsedRow>, String> {
+    Ok(marc::parse(body)?
+        .into_iter()
+        .map(|(line, record)| ParsedRow {
+            line,
+            row: record.and_then(|r| {
+                if r.is_deleted() {
+                    return Err("Deleted records are not applied.".to_string());
+                }
+
+                let (firstname, lastname) = r.author();
+
+                Ok(ImportRow {
+                    title: r.title(),
+                    author: None,
+                    author_firstname: Some(firstname),
+                    author_lastname: Some(lastname),
+                    published: r.published(),
+                    cover: r.cover(),
+                    isbn: r.isbn(),
+                    publisher: r.publisher(),
+                    edition: r.edition(),
+                    pages: r.pages(),
+                    genre: None,
+                })
+            }),
+        })
+        .collect())
+}
+
+/// Writes `errors` as CSV with a `line,field,message` header.
+pub fn error_report(errors: &[RowError]) -> Result<Vec<u8>, csv::Error> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+
+    writer.write_record(["line", "field", "message"])?;
+
+    for e in errors {
+        writer.write_record([
+            e.line.to_string().as_str(),
+            e.field.as_deref().unwrap_or(""),
+            e.message.as_str(),
+        ])?;
+    }
+
+    writer
+        .into_inner()
+        .map_err(|e| csv::Error::from(e.into_error()))
+}
+
+/// Imports `rows` as `user`, each row in its own transaction. A dry run does
+/// everything in one transaction it then rolls back, so it reports exactly
+/// what a real run would, authors shared between rows included.
+pub async fn run(
+    db: &DatabaseConnection,
+    config: &AppConfig,
+    user: &AuthenticatedUser,
+    rows: Vec<ParsedRow>,
+    dry_run: bool,
+    progress: &dyn Progress,
+) -> Result<Summary, DbErr> {
+    if dry_run {
+        let txn = db.begin().await?;
+        let summary = run_in(&txn, config, user, rows, progress).await?;
+        txn.rollback().await?;
+
+        Ok(summary)
+    } else {
+        run_in(db, config, user, rows, progress).await
+    }
+}
+
+async fn run_in<C: ConnectionTrait + TransactionTrait>(
+    db: &C,
+    config: &AppConfig,
+    user: &AuthenticatedUser,
+    rows: Vec<ParsedRow>,
+    progress: &dyn Progress,
+) -> Result<Summary, DbErr> {
+    let mut summary = Summary::default();
+    let mut authors = HashMap::<(String, String), i32>::new();
+
+    for ParsedRow { line, row } in rows {
+        let result = match row {
+            Ok(row) => import_row(db, config, user, &mut authors, &mut summary, &row).await?,
+            Err(message) => Err(vec![(None, message)]),
+        };
+
+        if let Err(errors) = result {
+            summary.failed += 1;
+            summary
+                .errors
+                .extend(errors.into_iter().map(|(field, message)| RowError {
+                    line,
+                    field,
+                    message,
+                }));
+        }
+
+        summary.processed += 1;
+
+        if summary.processed % PROGRESS_EVERY == 0 {
+            progress.report(&summary).await;
+        }
+    }
+
+    progress.report(&summary).await;
+
+    Ok(summary)
+}
+
+type RowErrors = Vec<(Option<String>, String)>;
+
+/// Imports one row, matching or creating its author, and updating the book
+/// with its ISBN if there is one. Only fails outright if the transaction
+/// itself can't be opened or closed.
+async fn import_row<C: ConnectionTrait + TransactionTrait>(
+    db: &C,
+    config: &AppConfig,
+    user: &AuthenticatedUser,
+    authors: &mut HashMap<(String, String), i32>,
+    summary: &mut Summary,
+    row: &ImportRow,
+) -> Result<Result<(), RowErrors>, DbErr> {
+    let (firstname, lastname) = row.author_name();
+    let name = (firstname.to_lowercase(), lastname.to_lowercase());
+
+    let txn = db.begin().await?;
+
+    let (author_id, matched) = match authors.get(&name) {
+        Some(&id) => (id, None),
+        None => match find_author(&txn, &firstname, &lastname).await? {
+            Some(id) => (id, Some(false)),
+            None => {
+                let req_author = authors::ReqAuthor {
+                    firstname: firstname.clone(),
+                    lastname: lastname.clone(),
+                    ..Default::default()
+                };
+
+                match authors::insert(&txn, user, &req_author).await {
+                    Ok(author) => (author.id, Some(true)),
+                    Err(e) => {
+                        txn.rollback().await?;
+                        return Ok(Err(prefixed("author", e.into_messages())));
+                    }
+                }
+            }
+        },
+    };
+
+    let req_book = books::ReqBook {
+        author_id,
+        title: row.title.clone(),
+        published: row.published.clone(),
+        cover: row.cover.clone().unwrap_or_default(),
+        isbn: row.isbn.clone(),
+        publisher: row.publisher.clone(),
+        edition: row.edition.clone(),
+        pages: row.pages,
+        genre: row.genre.clone(),
+    };
+
+    let existing = match find_book(&txn, &req_book).await? {
+        Some(book) if book.deleted_at.is_some() => {
+            txn.rollback().await?;
+            return Ok(Err(vec![(
+                Some("isbn".to_string()),
+                "A deleted book has this ISBN. Restore it to update it.".to_string(),
+            )]));
+        }
+        existing => existing,
+    };
+    let updating = existing.is_some();
+
+    let saved = match existing {
+        Some(before) => {
+            let preconditions = Preconditions::if_match(Some(before.etag()));
+            books::save(&txn, config, user, &preconditions, before, &req_book).await
+        }
+        None => books::insert(&txn, user, &req_book).await,
+    };
+
+    if let Err(e) = saved {
+        txn.rollback().await?;
+        return Ok(Err(e.into_messages()));
+    }
+
+    txn.commit().await?;
+
+    // Only remembered once committed, so a failed row can't leave behind an
+    // author that was rolled back.
+    authors.insert(name, author_id);
+
+    if updating {
+        summary.books_updated += 1;
+    } else {
+        summary.books_created += 1;
+    }
+
+    match matched {
+        Some(true) => summary.authors_created += 1,
+        Some(false) => summary.authors_matched += 1,
+        None => {}
+    }
+
+    Ok(Ok(()))
+}
+
+/// Finds the book, deleted or not, with the row's ISBN.
+async fn find_book<C: ConnectionTrait>(
+    db: &C,
+    req_book: &books::ReqBook,
+) -> Result<Option<book::Model>, DbErr> {
+    let isbn = match req_book.isbn.as_deref().map(str::parse::<Isbn>) {
+        Some(Ok(isbn)) => isbn,
+        // Invalid ISBNs are reported by the validation.
+        _ => return Ok(None),
+    };
+
+    Book::find()
+        .filter(book::Column::Isbn.eq(isbn.as_str()))
+        .one(db)
+        .await
+}
+
+/// Author fields are reported as `author.firstname` and so on.
+fn prefixed(prefix: &str, errors: RowErrors) -> RowErrors {
+    errors
+        .into_iter()
+        .map(|(field, message)| {
+            (
+                Some(field.map_or(prefix.to_string(), |f| format!("{}.{}", prefix, f))),
+                message,
+            )
+        })
+        .collect()
+}
+
+/// Finds a live author by name, or else by alias or pen name, ignoring case.
+async fn find_author<C: ConnectionTrait>(
+    db: &C,
+    firstname: &str,
+    lastname: &str,
+) -> Result<Option<i32>, DbErr> {
+    let by_name = Author::find()
+        .filter(author::Column::Firstname.eq(firstname))
+        .filter(author::Column::Lastname.eq(lastname))
+        .filter(author::Column::DeletedAt.is_null())
+        .order_by_asc(author::Column::Id)
+        .one(db)
+        .await?;
+
+    if let Some(author) = by_name {
+        return Ok(Some(author.id));
+    }
+
+    Ok(Author::find()
+        .inner_join(AuthorAlias)
+        .filter(author_alias::Column::Name.eq(format!("{} {}", firstname, lastname).trim()))
+        .filter(author::Column::DeletedAt.is_null())
+        .order_by_asc(author::Column::Id)
+        .one(db)
+        .await?
+        .map(|a| a.id))
+}
+
+/// Reports progress on an import job's row.
+struct JobProgress {
+    db: DatabaseConnection,
+    id: i32,
+}
+
+#[rocket::async_trait]
+impl Progress for JobProgress {
+    async fn report(&self, summary: &Summary) {
+        let job = import_job::ActiveModel {
+            id: Unchanged(self.id),
+            ..summary.into()
+        };
+
+        if let Err(e) = job.update(&self.db).await {
+            warn!("Could not report the progress of import {}: {}", self.id, e);
+        }
+    }
+}
+
+impl From<&Summary> for import_job::ActiveModel {
+    fn from(summary: &Summary) -> Self {
+        Self {
+            processed_rows: Set(summary.processed as i32),
+            failed_rows: Set(summary.failed as i32),
+            books_created: Set(summary.books_created as i32),
+            books_updated: Set(summary.books_updated as i32),
+            authors_created: Set(summary.authors_created as i32),
+            authors_matched: Set(summary.authors_matched as i32),
+            errors: Set(serde_json::json!(summary.errors)),
+            ..Default::default()
+        }
+    }
+}
+
+/// Runs the import of `job` in the background.
+pub fn spawn(
+    db: DatabaseConnection,
+    config: AppConfig,
+    user: AuthenticatedUser,
+    job: import_job::Model,
+    rows: Vec<ParsedRow>,
+) {
+    tokio::spawn(async move {
+        let id = job.id;
+        let progress = JobProgress { db: db.clone(), id };
+
+        let running = import_job::ActiveModel {
+            id: Unchanged(id),
+            status: Set(ImportStatus::Running),
+            ..Default::default()
+        };
+
+        let result = match running.update(&db).await {
+            Ok(_) => run(&db, &config, &user, rows, job.dry_run, &progress).await,
+            Err(e) => Err(e),
+        };
+
+        let finished = match result {
+            Ok(summary) => import_job::ActiveModel {
+                id: Unchanged(id),
+                status: Set(ImportStatus::Completed),
+                finished_at: Set(Some(Utc::now())),
+                ..(&summary).into()
+            },
+            Err(e) => {
+                error!("Import {} failed: {}", id, e);
+
+                import_job::ActiveModel {
+                    id: Unchanged(id),
+                    status: Set(ImportStatus::Failed),
+                    finished_at: Set(Some(Utc::now())),
+                    ..Default::default()
+                }
+            }
+        };
+
+        if let Err(e) = finished.update(&db).await {
+            error!("Could not record the end of import {}: {}", id, e);
+        }
+    });
+}
+
+/// Marks imports cut short by a restart as failed.
+pub async fn fail_interrupted(db: &DatabaseConnection) -> Result<u64, DbErr> {
+    Ok(ImportJob::update_many()
+        .col_expr(
+            import_job::Column::Status,
+            sea_orm::sea_query::Expr::value(ImportStatus::Failed),
+        )
+        .col_expr(
+            import_job::Column::FinishedAt,
+            sea_orm::sea_query::Expr::value(Utc::now()),
+        )
+        .filter(import_job::Column::Status.is_in([ImportStatus::Pending, ImportStatus::Running]))
+        .exec(db)
+        .await?
+        .rows_affected)
+}
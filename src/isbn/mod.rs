@@ -0,0 +1,98 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use std::{fmt, str::FromStr};
+
+/// An ISBN, kept as ISBN-13 digits.
+///
+/// Parsed from ISBN-10 or ISBN-13, with or without hyphens and spaces, and
+/// written as the 13 digits alone.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Isbn(String);
+
+impl Isbn {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+fn isbn13_check(digits: &[u8]) -> u8 {
+    let sum: u32 = digits
+        .iter()
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { *d as u32 } else { 3 * *d as u32 })
+        .sum();
+
+    ((10 - sum % 10) % 10) as u8
+}
+
+#[derive(Debug)]
+pub struct ParseIsbnError;
+
+impl fmt::Display for ParseIsbnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected a valid ISBN-10 or ISBN-13")
+    }
+}
+
+impl FromStr for Isbn {
+    type Err = ParseIsbnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars = s
+            .chars()
+            .filter(|c| *c != '-' && !c.is_whitespace())
+            .collect::<Vec<_>>();
+
+        match chars.len() {
+            10 => {
+                let mut sum = 0;
+
+                for (i, c) in chars.iter().enumerate() {
+                    let value = match c {
+                        'X' | 'x' if i == 9 => 10,
+                        c => c.to_digit(10).ok_or(ParseIsbnError)?,
+                    };
+
+                    sum += (10 - i as u32) * value;
+                }
+
+                if sum % 11 != 0 {
+                    return Err(ParseIsbnError);
+                }
+
+                let mut digits = vec![9, 7, 8];
+                digits.extend(chars[..9].iter().map(|c| c.to_digit(10).unwrap() as u8));
+                digits.push(isbn13_check(&digits));
+
+                Ok(Isbn(digits.iter().map(|d| (b'0' + d) as char).collect()))
+            }
+            13 => {
+                let digits = chars
+                    .iter()
+                    .map(|c| c.to_digit(10).map(|d| d as u8))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or(ParseIsbnError)?;
+
+                if !(digits.starts_with(&[9, 7, 8]) || digits.starts_with(&[9, 7, 9]))
+                    || isbn13_check(&digits[..12]) != digits[12]
+                {
+                    return Err(ParseIsbnError);
+                }
+
+                Ok(Isbn(chars.into_iter().collect()))
+            }
+            _ => Err(ParseIsbnError),
+        }
+    }
+}
+
+impl fmt::Display for Isbn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
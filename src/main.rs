@@ -9,18 +9,48 @@
 extern crate rocket;
 
 use controllers::{Response, SuccessResponse};
-use fairings::cors::{options, Cors};
+use fairings::{
+    cors::{options, Cors},
+    deprecation::Deprecation,
+    idempotency::Idempotency,
+    purge::Purge,
+    request_id::RequestId,
+};
 use migrator::Migrator;
-use rocket::http::Status;
+use openapi::{ApiDoc, SpecCheck, DOCS_PATH, SPEC_PATH};
+use rocket::{http::Status, Build, Rocket};
+use sea_orm::DatabaseConnection;
 use sea_orm_migration::prelude::*;
+use utoipa::OpenApi;
+use utoipa_rapidoc::RapiDoc;
 
 mod auth;
+mod citation;
+mod cli;
 mod controllers;
+mod covers;
 mod db;
 mod entities;
+mod exports;
 mod fairings;
+mod graphql;
+mod idempotency;
+mod imports;
+mod isbn;
+mod marc;
 mod migrator;
+mod names;
+mod onix;
+mod opds;
+mod openapi;
+mod openlibrary;
+mod publication;
+mod revisions;
+mod storage;
+mod validation;
+mod xml;
 
+#[derive(Clone)]
 pub struct AppConfig {
     db_host: String,
     db_port: String,
@@ -28,6 +58,20 @@ pub struct AppConfig {
     db_password: String,
     db_database: String,
     jwt_secret: String,
+    storage_driver: String,
+    storage_path: String,
+    s3_endpoint: String,
+    s3_bucket: String,
+    s3_region: String,
+    s3_access_key: String,
+    s3_secret_key: String,
+    cover_max_size: u64,
+    soft_delete_retention_days: i64,
+    require_if_match: bool,
+    bulk_limit: usize,
+    idempotency_ttl_hours: i64,
+    import_max_size: u64,
+    base_url: String,
 }
 
 impl Default for AppConfig {
@@ -40,57 +84,178 @@ impl Default for AppConfig {
             db_database: std::env::var("BOOKSTORE_DB_DATABASE").unwrap_or("bookstore".to_string()),
             jwt_secret: std::env::var("BOOKSTORE_JWT_SECRET")
                 .expect("Please set the BOOKSTORE_JWT_SECRET env variable."),
+            storage_driver: std::env::var("BOOKSTORE_STORAGE_DRIVER")
+                .unwrap_or("local".to_string()),
+            storage_path: std::env::var("BOOKSTORE_STORAGE_PATH").unwrap_or("storage".to_string()),
+            s3_endpoint: std::env::var("BOOKSTORE_S3_ENDPOINT")
+                .unwrap_or("http://localhost:9000".to_string()),
+            s3_bucket: std::env::var("BOOKSTORE_S3_BUCKET").unwrap_or("bookstore".to_string()),
+            s3_region: std::env::var("BOOKSTORE_S3_REGION").unwrap_or("us-east-1".to_string()),
+            s3_access_key: std::env::var("BOOKSTORE_S3_ACCESS_KEY").unwrap_or("".to_string()),
+            s3_secret_key: std::env::var("BOOKSTORE_S3_SECRET_KEY").unwrap_or("".to_string()),
+            cover_max_size: std::env::var("BOOKSTORE_COVER_MAX_SIZE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(5 * 1024 * 1024),
+            soft_delete_retention_days: std::env::var("BOOKSTORE_SOFT_DELETE_RETENTION_DAYS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(30),
+            require_if_match: std::env::var("BOOKSTORE_REQUIRE_IF_MATCH")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            bulk_limit: std::env::var("BOOKSTORE_BULK_LIMIT")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(100),
+            idempotency_ttl_hours: std::env::var("BOOKSTORE_IDEMPOTENCY_TTL_HOURS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(24),
+            import_max_size: std::env::var("BOOKSTORE_IMPORT_MAX_SIZE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(10 * 1024 * 1024),
+            base_url: std::env::var("BOOKSTORE_BASE_URL")
+                .unwrap_or("http://localhost:8000".to_string()),
         }
     }
 }
 
+#[utoipa::path(tag = "meta", responses((status = 200, body = String)))]
 #[get("/")]
 fn index() -> Response<String> {
     Ok(SuccessResponse((Status::Ok, "Hello, World".to_string())))
 }
 
-#[launch]
-async fn rocket() -> _ {
+#[rocket::main]
+async fn main() -> Result<(), Box<rocket::Error>> {
     dotenvy::dotenv().ok();
 
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let Some(code) = cli::run_offline(&args) {
+        std::process::exit(code);
+    }
+
     let config = AppConfig::default();
 
     let db = db::connect(&config).await.unwrap();
     Migrator::up(&db, None).await.unwrap();
 
-    rocket::build()
+    if !args.is_empty() {
+        std::process::exit(cli::run(&db, &config, &args).await);
+    }
+
+    // Ignited first so the logger is set up.
+    let rocket = rocket(db.clone(), config).ignite().await?;
+
+    match imports::fail_interrupted(&db).await {
+        Ok(0) => {}
+        Ok(n) => warn!("Marked {} imports interrupted by a restart as failed.", n),
+        Err(e) => error!("Could not check for interrupted imports: {}", e),
+    }
+
+    rocket.launch().await?;
+
+    Ok(())
+}
+
+fn rocket(db: DatabaseConnection, config: AppConfig) -> Rocket<Build> {
+    let storage = storage::from_config(&config);
+    let schema = graphql::schema(db.clone(), config.clone());
+
+    let graphql_routes = if cfg!(debug_assertions) {
+        routes![
+            controllers::graphql::execute,
+            controllers::graphql::graphiql
+        ]
+    } else {
+        routes![controllers::graphql::execute]
+    };
+
+    let figment = rocket::Config::figment()
+        .merge(("limits.file", config.cover_max_size))
+        .merge(("limits.data-form", config.cover_max_size + 64 * 1024))
+        .merge(("limits.bytes", config.import_max_size));
+
+    let mut rocket = rocket::custom(figment)
+        .attach(RequestId)
+        .attach(Idempotency {
+            ttl_hours: config.idempotency_ttl_hours,
+        })
+        .attach(SpecCheck)
         .attach(Cors)
+        .attach(Deprecation)
+        .attach(Purge {
+            retention_days: config.soft_delete_retention_days,
+        })
         .manage(db)
+        .manage(storage)
         .manage(config)
-        .mount("/", routes![options])
-        .mount("/", routes![index])
-        .mount(
-            "/auth",
-            routes![
-                controllers::auth::sign_in,
-                controllers::auth::sign_up,
-                controllers::auth::me
+        .manage(schema)
+        .register(
+            "/",
+            catchers![
+                controllers::catchers::bad_request,
+                controllers::catchers::unauthorized,
+                controllers::catchers::not_found,
+                controllers::catchers::unprocessable_entity,
+                controllers::catchers::internal_server_error,
+                controllers::catchers::default,
             ],
         )
-        .mount(
-            "/authors",
-            routes![
-                controllers::authors::index,
-                controllers::authors::create,
-                controllers::authors::show,
-                controllers::authors::update,
-                controllers::authors::delete,
-                controllers::authors::get_books,
-            ],
+        .register(
+            format!(
+                "{}{}",
+                controllers::v1::PREFIX,
+                controllers::opds::ATOM_BASE
+            ),
+            catchers![controllers::catchers::challenge],
         )
-        .mount(
-            "/books",
-            routes![
-                controllers::books::index,
-                controllers::books::create,
-                controllers::books::show,
-                controllers::books::update,
-                controllers::books::delete,
-            ],
+        .register(
+            format!(
+                "{}{}",
+                controllers::v1::PREFIX,
+                controllers::opds::JSON_BASE
+            ),
+            catchers![controllers::catchers::challenge],
         )
+        .mount("/", routes![options])
+        .mount("/", routes![index])
+        .mount(graphql::PATH, graphql_routes)
+        .mount(
+            "/",
+            RapiDoc::with_openapi(SPEC_PATH, ApiDoc::openapi()).path(DOCS_PATH),
+        );
+
+    // Older routes are also mounted at the root for clients predating `/v1`.
+    for (base, routes) in controllers::v1::routes() {
+        if controllers::v1::UNVERSIONED_BASES.contains(&base) {
+            rocket = rocket.mount(base, routes.clone());
+        }
+
+        rocket = rocket.mount(format!("{}{}", controllers::v1::PREFIX, base), routes);
+    }
+
+    rocket
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_match_the_openapi_spec() {
+        std::env::set_var("BOOKSTORE_JWT_SECRET", "secret");
+
+        let rocket = rocket(DatabaseConnection::Disconnected, AppConfig::default());
+        let (undocumented, unrouted) = openapi::mismatches(&rocket);
+
+        assert!(
+            undocumented.is_empty(),
+            "not documented: {:?}",
+            undocumented
+        );
+        assert!(unrouted.is_empty(), "not routed: {:?}", unrouted);
+    }
 }
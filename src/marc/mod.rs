@@ -0,0 +1,705 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use crate::isbn::Isbn;
+use crate::xml::{self, close, leaf_with, open, open_with};
+
+const SUBFIELD_DELIMITER: u8 = 0x1F;
+const FIELD_TERMINATOR: u8 = 0x1E;
+const RECORD_TERMINATOR: u8 = 0x1D;
+
+/// A bibliographic record, as read from MARC21 or MARCXML.
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct Record {
+    /// The 24 characters of the leader. The length and base address are
+    /// recomputed when the record is written.
+    pub leader: String,
+    pub fields: Vec<Field>,
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub enum Field {
+    /// A field from 001 to 009: one value, no indicators or subfields.
+    Control { tag: String, value: String },
+    Data {
+        tag: String,
+        indicators: [char; 2],
+        subfields: Vec<(char, String)>,
+    },
+}
+
+impl Field {
+    fn tag(&self) -> &str {
+        match self {
+            Field::Control { tag, .. } | Field::Data { tag, .. } => tag,
+        }
+    }
+
+    /// The first subfield `code` of a data field.
+    fn subfield(&self, code: char) -> Option<&str> {
+        match self {
+            Field::Data { subfields, .. } => subfields
+                .iter()
+                .find(|(c, _)| *c == code)
+                .map(|(_, v)| v.as_str()),
+            Field::Control { .. } => None,
+        }
+    }
+
+    fn indicator(&self, i: usize) -> char {
+        match self {
+            Field::Data { indicators, .. } => indicators[i],
+            Field::Control { .. } => ' ',
+        }
+    }
+}
+
+/// A record as read, or why it couldn't be, with where it starts: its line in
+/// MARCXML, its number in MARC21.
+pub type Parsed = (u64, Result<Record, String>);
+
+/// Whether `body` is MARCXML rather than binary MARC21.
+pub fn is_xml(body: &[u8]) -> bool {
+    body.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'<')
+}
+
+/// Reads MARCXML or binary MARC21, whichever `body` is. Fails only if the
+/// file as a whole is unusable.
+pub fn parse(body: &[u8]) -> Result<Vec<Parsed>, String> {
+    if is_xml(body) {
+        parse_xml(body)
+    } else {
+        Ok(parse_binary(body))
+    }
+}
+
+fn parse_binary(body: &[u8]) -> Vec<Parsed> {
+    body.split(|b| *b == RECORD_TERMINATOR)
+        .map(|r| r.trim_ascii())
+        .filter(|r| !r.is_empty())
+        .enumerate()
+        .map(|(i, r)| (i as u64 + 1, binary_record(r)))
+        .collect()
+}
+
+fn binary_record(bytes: &[u8]) -> Result<Record, String> {
+    let leader = bytes
+        .get(..24)
+        .filter(|l| l.is_ascii())
+        .ok_or("The record has no leader.")?;
+    let leader = String::from_utf8_lossy(leader).into_owned();
+
+    if leader.as_bytes()[9] != b'a' && !bytes.is_ascii() {
+        return Err(
+            "The record is MARC-8 encoded, which is not supported. Convert it to UTF-8 first."
+                .to_string(),
+        );
+    }
+
+    let base = leader[12..17]
+        .parse::<usize>()
+        .ok()
+        .filter(|b| *b > 24 && *b <= bytes.len())
+        .ok_or("The record's base address is invalid.")?;
+    let directory = &bytes[24..base - 1];
+
+    if !directory.len().is_multiple_of(12) {
+        return Err("The record's directory is malformed.".to_string());
+    }
+
+    let fields = directory
+        .chunks(12)
+        .map(|entry| {
+            let entry = std::str::from_utf8(entry)
+                .ok()
+                .filter(|e| e.is_ascii())
+                .ok_or("The record's directory is malformed.")?;
+            let tag = &entry[..3];
+            let length = entry[3..7].parse::<usize>();
+            let start = entry[7..].parse::<usize>();
+
+            let data = match (length, start) {
+                (Ok(length), Ok(start)) => bytes.get(base + start..base + start + length),
+                _ => None,
+            }
+            .ok_or(format!("Field {} is out of bounds.", tag))?;
+            let data = data.strip_suffix(&[FIELD_TERMINATOR]).unwrap_or(data);
+            let text = |bytes: &[u8]| {
+                String::from_utf8(bytes.to_vec())
+                    .map_err(|_| format!("Field {} is not valid UTF-8.", tag))
+            };
+
+            if is_control(tag) {
+                return Ok(Field::Control {
+                    tag: tag.to_string(),
+                    value: text(data)?,
+                });
+            }
+
+            let mut parts = data.split(|b| *b == SUBFIELD_DELIMITER);
+            let indicators = text(parts.next().unwrap_or_default())?;
+
+            if indicators.chars().count() > 2 {
+                return Err(format!("Field {} has more than two indicators.", tag));
+            }
+
+            let mut indicators = indicators.chars();
+
+            Ok(Field::Data {
+                tag: tag.to_string(),
+                indicators: [
+                    indicators.next().unwrap_or(' '),
+                    indicators.next().unwrap_or(' '),
+                ],
+                subfields: parts
+                    .filter(|p| !p.is_empty())
+                    .map(|p| {
+                        let p = text(p)?;
+                        let mut chars = p.chars();
+                        let code = chars.next().unwrap_or(' ');
+                        Ok((code, chars.as_str().to_string()))
+                    })
+                    .collect::<Result<_, String>>()?,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    Ok(Record { leader, fields })
+}
+
+fn parse_xml(body: &[u8]) -> Result<Vec<Parsed>, String> {
+    let root = xml::parse(body)?;
+
+    let records = match root.name.as_str() {
+        "collection" => root.children("record").collect(),
+        "record" => vec![&root],
+        _ => return Err("The file is not a MARCXML collection or record.".to_string()),
+    };
+
+    Ok(records
+        .into_iter()
+        .map(|r| (r.line, xml_record(r)))
+        .collect())
+}
+
+fn xml_record(record: &xml::Element) -> Result<Record, String> {
+    let fields = record
+        .children
+        .iter()
+        .filter(|f| ["controlfield", "datafield"].contains(&f.name.as_str()))
+        .map(|f| {
+            let tag = f.attribute("tag").unwrap_or("");
+
+            if tag.len() != 3 || !tag.is_ascii() {
+                return Err(format!(
+                    "The tag \"{}\" on line {} is not three characters.",
+                    tag, f.line
+                ));
+            }
+
+            if f.name == "controlfield" {
+                return Ok(Field::Control {
+                    tag: tag.to_string(),
+                    value: f.text.clone(),
+                });
+            }
+
+            Ok(Field::Data {
+                tag: tag.to_string(),
+                indicators: [indicator(f, tag, "ind1")?, indicator(f, tag, "ind2")?],
+                subfields: f
+                    .children("subfield")
+                    .map(|s| {
+                        let code = s.attribute("code").unwrap_or(" ");
+                        (code.chars().next().unwrap_or(' '), s.text.clone())
+                    })
+                    .collect(),
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    // Text is read trimmed, which drops the leading blanks a leader
+    // may have, so it is padded back to length on the left.
+    let leader = format!("{:>24}", record.text_of("leader").unwrap_or(""));
+
+    Ok(Record { leader, fields })
+}
+
+/// An indicator of a data field; blank if missing or empty.
+fn indicator(field: &xml::Element, tag: &str, name: &str) -> Result<char, String> {
+    let value = field.attribute(name).unwrap_or("");
+    let mut chars = value.chars();
+
+    match (chars.next(), chars.next()) {
+        (None, _) => Ok(' '),
+        (Some(c), None) => Ok(c),
+        _ => Err(format!(
+            "Field {} on line {} has an {} of more than one character.",
+            tag, field.line, name
+        )),
+    }
+}
+
+fn is_control(tag: &str) -> bool {
+    tag.starts_with("00")
+}
+
+/// Strips the punctuation cataloguers end elements with, e.g. the ` /`
+/// before a statement of responsibility. A final full stop is kept after an
+/// initial, as in `Le Guin, Ursula K.`.
+fn clean(value: &str) -> String {
+    let value = value
+        .trim()
+        .trim_end_matches([' ', '/', ':', ';', ',', '=']);
+
+    match value.strip_suffix('.') {
+        Some(rest)
+            if rest
+                .rsplit(' ')
+                .next()
+                .is_none_or(|w| w.chars().count() > 1) =>
+        {
+            rest.to_string()
+        }
+        _ => value.to_string(),
+    }
+}
+
+/// The first run of `n` digits in `value`.
+fn digits(value: &str, n: usize) -> Option<&str> {
+    value
+        .char_indices()
+        .filter(|(_, c)| c.is_ascii_digit())
+        .map(|(i, _)| i)
+        .find(|&i| {
+            value
+                .get(i..i + n)
+                .is_some_and(|d| d.bytes().all(|b| b.is_ascii_digit()))
+        })
+        .map(|i| &value[i..i + n])
+}
+
+impl Record {
+    /// An empty book record; `deleted` marks it as a deletion.
+    pub fn book(deleted: bool) -> Self {
+        Self {
+            leader: format!("00000{}am a2200000 i 4500", if deleted { 'd' } else { 'n' }),
+            fields: vec![],
+        }
+    }
+
+    pub fn control(mut self, tag: &str, value: String) -> Self {
+        self.fields.push(Field::Control {
+            tag: tag.to_string(),
+            value,
+        });
+        self
+    }
+
+    /// Adds a data field, unless every subfield is empty.
+    pub fn data(mut self, tag: &str, indicators: &str, subfields: &[(char, String)]) -> Self {
+        let subfields = subfields
+            .iter()
+            .filter(|(_, v)| !v.is_empty())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if !subfields.is_empty() {
+            let mut indicators = indicators.chars();
+
+            self.fields.push(Field::Data {
+                tag: tag.to_string(),
+                indicators: [
+                    indicators.next().unwrap_or(' '),
+                    indicators.next().unwrap_or(' '),
+                ],
+                subfields,
+            });
+        }
+
+        self
+    }
+
+    fn fields<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a Field> + 'a {
+        self.fields.iter().filter(move |f| f.tag() == tag)
+    }
+
+    fn field(&self, tag: &str) -> Option<&Field> {
+        self.fields.iter().find(|f| f.tag() == tag)
+    }
+
+    /// Whether the record is marked as deleted (leader/05 `d`).
+    pub fn is_deleted(&self) -> bool {
+        self.leader.chars().nth(5) == Some('d')
+    }
+
+    /// The title proper and the rest of the title (245 $a and $b).
+    pub fn title(&self) -> String {
+        let Some(f) = self.field("245") else {
+            return String::new();
+        };
+
+        let title = clean(f.subfield('a').unwrap_or(""));
+
+        match f.subfield('b').map(clean) {
+            Some(rest) if !rest.is_empty() => format!("{}: {}", title, rest),
+            _ => title,
+        }
+    }
+
+    /// The main author (100), or failing that the first added author (700),
+    /// as first and last names.
+    pub fn author(&self) -> (String, String) {
+        let Some(f) = self.field("100").or_else(|| self.field("700")) else {
+            return Default::default();
+        };
+
+        let name = clean(f.subfield('a').unwrap_or(""));
+
+        // Indicator 1 is the type of name: 0 a forename, 1 a surname first,
+        // 3 a family name.
+        match (f.indicator(0), name.split_once(',')) {
+            ('1', Some((last, first))) => (first.trim().to_string(), last.trim().to_string()),
+            _ => (String::new(), name),
+        }
+    }
+
+    /// The first ISBN (020 $a) that is valid, or else the first one given.
+    pub fn isbn(&self) -> Option<String> {
+        let isbns = self
+            .fields("020")
+            .filter_map(|f| f.subfield('a'))
+            .filter_map(|a| a.split_whitespace().next())
+            .collect::<Vec<_>>();
+
+        isbns
+            .iter()
+            .find(|i| i.parse::<Isbn>().is_ok())
+            .or(isbns.first())
+            .map(|i| i.to_string())
+    }
+
+    /// The publication statement: 264 with indicator 2 `1`, or 260 in
+    /// records from before RDA.
+    fn publication(&self) -> Option<&Field> {
+        self.fields("264")
+            .find(|f| f.indicator(1) == '1')
+            .or_else(|| self.field("260"))
+    }
+
+    pub fn publisher(&self) -> Option<String> {
+        self.publication()
+            .and_then(|f| f.subfield('b'))
+            .map(clean)
+            .filter(|p| !p.is_empty())
+    }
+
+    /// The year of publication, from the publication statement or else 008.
+    pub fn published(&self) -> Option<String> {
+        self.publication()
+            .and_then(|f| f.subfield('c'))
+            .and_then(|c| digits(c, 4))
+            .or_else(|| match self.field("008") {
+                Some(Field::Control { value, .. }) => value
+                    .get(7..11)
+                    .filter(|y| y.bytes().all(|b| b.is_ascii_digit())),
+                _ => None,
+            })
+            .map(str::to_owned)
+    }
+
+    /// The edition statement (250 $a).
+    pub fn edition(&self) -> Option<String> {
+        self.field("250")
+            .and_then(|f| f.subfield('a'))
+            .map(clean)
+            .filter(|e| !e.is_empty())
+    }
+
+    /// The page count, from the physical description (300 $a), e.g.
+    /// `xii, 341 pages`.
+    pub fn pages(&self) -> Option<i32> {
+        let extent = self.field("300")?.subfield('a')?;
+
+        extent
+            .split(|c: char| !c.is_ascii_digit())
+            .find(|n| !n.is_empty())
+            .and_then(|n| n.parse().ok())
+    }
+
+    /// A link to the cover: an 856 $u whose materials ($3) mention a cover.
+    pub fn cover(&self) -> Option<String> {
+        self.fields("856")
+            .find(|f| {
+                f.subfield('3')
+                    .is_some_and(|m| m.to_lowercase().contains("cover"))
+            })
+            .and_then(|f| f.subfield('u'))
+            .map(str::to_owned)
+    }
+
+    /// The record as MARC21, in UTF-8. Fails if a tag is not three
+    /// characters, or a field or the record is longer than MARC21 can give a
+    /// length for.
+    pub fn to_binary(&self) -> Result<Vec<u8>, String> {
+        let mut directory = vec![];
+        let mut data = vec![];
+
+        for field in &self.fields {
+            // The directory has room for three bytes of tag.
+            if field.tag().len() != 3 || !field.tag().is_ascii() {
+                return Err(format!(
+                    "The tag \"{}\" is not three characters.",
+                    field.tag()
+                ));
+            }
+
+            let start = data.len();
+
+            match field {
+                Field::Control { value, .. } => data.extend(value.as_bytes()),
+                Field::Data {
+                    indicators,
+                    subfields,
+                    ..
+                } => {
+                    data.extend(indicators.iter().collect::<String>().as_bytes());
+
+                    for (code, value) in subfields {
+                        data.push(SUBFIELD_DELIMITER);
+                        data.extend(code.to_string().as_bytes());
+                        data.extend(value.as_bytes());
+                    }
+                }
+            }
+
+            data.push(FIELD_TERMINATOR);
+
+            if data.len() - start > 9999 {
+                return Err(format!("Field {} is too long for MARC21.", field.tag()));
+            }
+
+            directory.extend(
+                format!("{:3}{:04}{:05}", field.tag(), data.len() - start, start).as_bytes(),
+            );
+        }
+
+        directory.push(FIELD_TERMINATOR);
+
+        let base = 24 + directory.len();
+        let length = base + data.len() + 1;
+
+        if length > 99999 {
+            return Err("The record is too long for MARC21.".to_string());
+        }
+
+        let mut leader = format!("{:24}", self.leader)
+            .chars()
+            .take(24)
+            .map(|c| if c.is_ascii() { c } else { ' ' })
+            .collect::<String>();
+        leader.replace_range(..5, &format!("{:05}", length));
+        leader.replace_range(9..10, "a");
+        leader.replace_range(12..17, &format!("{:05}", base));
+
+        let mut out = Vec::with_capacity(length);
+        out.extend(leader.as_bytes());
+        out.extend(directory);
+        out.extend(data);
+        out.push(RECORD_TERMINATOR);
+
+        Ok(out)
+    }
+
+    /// The record as a MARCXML `record`, to go between [`header`] and
+    /// [`footer`].
+    pub fn to_xml(&self) -> String {
+        let mut out = String::new();
+
+        open(&mut out, 1, "record");
+        leaf_with(&mut out, 2, "leader", &[], &self.leader);
+
+        for field in &self.fields {
+            match field {
+                Field::Control { tag, value } => {
+                    leaf_with(&mut out, 2, "controlfield", &[("tag", tag)], value);
+                }
+                Field::Data {
+                    tag,
+                    indicators,
+                    subfields,
+                } => {
+                    open_with(
+                        &mut out,
+                        2,
+                        "datafield",
+                        &[
+                            ("tag", tag),
+                            ("ind1", &indicators[0].to_string()),
+                            ("ind2", &indicators[1].to_string()),
+                        ],
+                    );
+
+                    for (code, value) in subfields {
+                        leaf_with(
+                            &mut out,
+                            3,
+                            "subfield",
+                            &[("code", &code.to_string())],
+                            value,
+                        );
+                    }
+
+                    close(&mut out, 2, "datafield");
+                }
+            }
+        }
+
+        close(&mut out, 1, "record");
+
+        out
+    }
+}
+
+/// The start of a MARCXML collection.
+pub fn header() -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    open(
+        &mut out,
+        0,
+        "collection xmlns=\"http://www.loc.gov/MARC21/slim\"",
+    );
+    out
+}
+
+/// The end of a MARCXML collection.
+pub fn footer() -> String {
+    let mut out = String::new();
+    close(&mut out, 0, "collection");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MARCXML: &[u8] = include_bytes!("fixtures/records.xml");
+    const MARC21: &[u8] = include_bytes!("fixtures/records.mrc");
+
+    fn records(body: &[u8]) -> Vec<Record> {
+        parse(body)
+            .unwrap()
+            .into_iter()
+            .map(|(_, r)| r.unwrap())
+            .collect()
+    }
+
+    fn collection(records: &[Record]) -> Vec<u8> {
+        let mut out = header();
+        out.extend(records.iter().map(Record::to_xml));
+        out.push_str(&footer());
+
+        out.into_bytes()
+    }
+
+    #[test]
+    fn parses_marcxml() {
+        let records = records(MARCXML);
+
+        assert_eq!(records.len(), 2);
+
+        let dune = &records[0];
+        assert!(!dune.is_deleted());
+        assert_eq!(dune.title(), "Dune");
+        assert_eq!(dune.author(), ("Frank".to_string(), "Herbert".to_string()));
+        assert_eq!(dune.isbn().as_deref(), Some("9780441013593"));
+        assert_eq!(dune.publisher().as_deref(), Some("Ace"));
+        assert_eq!(dune.published().as_deref(), Some("2005"));
+        assert_eq!(dune.edition().as_deref(), Some("2nd ed"));
+        assert_eq!(dune.pages(), Some(896));
+        assert_eq!(
+            dune.cover().as_deref(),
+            Some("https://example.com/covers/dune.jpg")
+        );
+
+        let hobbit = &records[1];
+        assert!(hobbit.is_deleted());
+        assert_eq!(hobbit.title(), "The hobbit: or there and back again");
+        assert_eq!(
+            hobbit.author(),
+            ("J. R. R.".to_string(), "Tolkien".to_string())
+        );
+        assert_eq!(hobbit.isbn().as_deref(), Some("0261103342"));
+        assert_eq!(hobbit.publisher().as_deref(), Some("Allen & Unwin"));
+        assert_eq!(hobbit.published().as_deref(), Some("1937"));
+        assert_eq!(hobbit.pages(), None);
+    }
+
+    #[test]
+    fn parses_marc21_as_marcxml() {
+        let binary = records(MARC21);
+        let xml = records(MARCXML);
+
+        assert_eq!(binary.len(), xml.len());
+
+        for (b, x) in binary.iter().zip(&xml) {
+            assert_eq!(b.fields, x.fields);
+            assert_eq!(b.is_deleted(), x.is_deleted());
+        }
+    }
+
+    #[test]
+    fn round_trips_marc21() {
+        let written = records(MARC21)
+            .iter()
+            .map(|r| r.to_binary().unwrap())
+            .collect::<Vec<_>>()
+            .concat();
+
+        assert_eq!(written, MARC21);
+    }
+
+    #[test]
+    fn round_trips_marcxml() {
+        let parsed = records(MARCXML);
+
+        assert_eq!(records(&collection(&parsed)), parsed);
+    }
+
+    #[test]
+    fn escapes_datafield_attributes() {
+        let record = Record {
+            leader: Record::book(false).leader,
+            fields: vec![Field::Data {
+                tag: "9\"<".to_string(),
+                indicators: ['&', '"'],
+                subfields: vec![('a', "<value>".to_string())],
+            }],
+        };
+        let xml = collection(std::slice::from_ref(&record));
+
+        assert_eq!(records(&xml), vec![record]);
+    }
+
+    #[test]
+    fn rejects_tags_of_other_lengths() {
+        let xml = br#"<record><leader>00000nam a2200000 i 4500</leader>
+            <datafield tag="2450" ind1="1" ind2="0"><subfield code="a">Dune</subfield></datafield>
+        </record>"#;
+
+        assert!(parse(xml).unwrap()[0].1.is_err());
+    }
+
+    #[test]
+    fn rejects_indicators_of_more_than_one_character() {
+        let xml = br#"<record><leader>00000nam a2200000 i 4500</leader>
+            <datafield tag="245" ind1="10" ind2="0"><subfield code="a">Dune</subfield></datafield>
+        </record>"#;
+
+        assert!(parse(xml).unwrap()[0].1.is_err());
+    }
+}
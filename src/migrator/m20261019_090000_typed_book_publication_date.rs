@@ -0,0 +1,141 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, Value};
+
+use crate::entities::sea_orm_active_enums::PublishedPrecision;
+use crate::publication::PublicationDate;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .add_column(ColumnDef::new(Book::PublishedOn).date().null())
+                    .add_column(
+                        ColumnDef::new(Book::PublishedPrecision)
+                            .enumeration(
+                                Book::PublishedPrecision,
+                                [Alias::new("year"), Alias::new("month"), Alias::new("day")],
+                            )
+                            .null(),
+                    )
+                    .add_column(ColumnDef::new(Book::PublishedRaw).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+
+        let rows = db
+            .query_all(
+                backend.build(
+                    Query::select()
+                        .columns([Book::Id, Book::Year])
+                        .from(Book::Table)
+                        .order_by(Book::Id, Order::Asc),
+                ),
+            )
+            .await?;
+
+        let mut unparseable = 0;
+
+        for row in rows {
+            let id: i32 = row.try_get("", "id")?;
+            let year: String = row.try_get("", "year")?;
+
+            if year.trim().is_empty() {
+                continue;
+            }
+
+            // Years that don't parse are kept as they were for someone to fix.
+            let date = match year.parse::<PublicationDate>() {
+                Ok(d) => d,
+                Err(_) => {
+                    warn!(
+                        "Book {} has a year that could not be parsed, kept in published_raw: {:?}",
+                        id, year
+                    );
+                    unparseable += 1;
+
+                    db.execute(
+                        backend.build(
+                            Query::update()
+                                .table(Book::Table)
+                                .value(Book::PublishedRaw, year)
+                                .and_where(Expr::col(Book::Id).eq(id)),
+                        ),
+                    )
+                    .await?;
+
+                    continue;
+                }
+            };
+
+            let (published_on, precision) = date.to_columns();
+
+            db.execute(
+                backend.build(
+                    Query::update()
+                        .table(Book::Table)
+                        .values([
+                            (Book::PublishedOn, published_on.into()),
+                            (Book::PublishedPrecision, precision_value(&precision)),
+                        ])
+                        .and_where(Expr::col(Book::Id).eq(id)),
+                ),
+            )
+            .await?;
+        }
+
+        if unparseable > 0 {
+            warn!(
+                "{} book(s) had a year that could not be parsed and were left without a publication date.",
+                unparseable
+            );
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .drop_column(Book::PublishedOn)
+                    .drop_column(Book::PublishedPrecision)
+                    .drop_column(Book::PublishedRaw)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+fn precision_value(precision: &PublishedPrecision) -> SimpleExpr {
+    use sea_orm_migration::sea_orm::ActiveEnum;
+
+    Value::from(precision.to_value()).into()
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum Book {
+    Table,
+    Id,
+    Year,
+    PublishedOn,
+    PublishedPrecision,
+    PublishedRaw,
+}
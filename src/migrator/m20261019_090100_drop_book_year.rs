@@ -0,0 +1,92 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::ConnectionTrait;
+
+use super::m20261019_090000_typed_book_publication_date::Book;
+use crate::entities::sea_orm_active_enums::PublishedPrecision;
+use crate::publication::PublicationDate;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    /// Drops the old `year` column, once its values are in `published_on`, or
+    /// `published_raw` for those that could not be parsed.
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if !manager.has_column("book", "year").await? {
+            return Ok(());
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .drop_column(Book::Year)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .add_column(ColumnDef::new(Book::Year).string().not_null().default(""))
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        let backend = manager.get_database_backend();
+
+        let rows = db
+            .query_all(
+                backend.build(
+                    Query::select()
+                        .columns([
+                            Book::Id,
+                            Book::PublishedOn,
+                            Book::PublishedPrecision,
+                            Book::PublishedRaw,
+                        ])
+                        .from(Book::Table),
+                ),
+            )
+            .await?;
+
+        for row in rows {
+            let id: i32 = row.try_get("", "id")?;
+            let published_on = row.try_get("", "published_on")?;
+            let precision: Option<PublishedPrecision> = row.try_get("", "published_precision")?;
+            let raw: Option<String> = row.try_get("", "published_raw")?;
+
+            let year = match PublicationDate::from_columns(published_on, precision.as_ref()) {
+                Some(d) => d.to_string(),
+                None => match raw {
+                    Some(raw) => raw,
+                    None => continue,
+                },
+            };
+
+            db.execute(
+                backend.build(
+                    Query::update()
+                        .table(Book::Table)
+                        .value(Book::Year, year)
+                        .and_where(Expr::col(Book::Id).eq(id)),
+                ),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
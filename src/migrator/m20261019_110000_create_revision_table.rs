@@ -0,0 +1,95 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Revision::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Revision::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Revision::UserId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-revision-user_id")
+                            .from(Revision::Table, Revision::UserId)
+                            .to(User::Table, User::Id),
+                    )
+                    .col(
+                        ColumnDef::new(Revision::Entity)
+                            .enumeration(
+                                Revision::Entity,
+                                [Alias::new("book"), Alias::new("author")],
+                            )
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Revision::EntityId).integer().not_null())
+                    .col(
+                        ColumnDef::new(Revision::Action)
+                            .enumeration(
+                                Revision::Action,
+                                [
+                                    Alias::new("create"),
+                                    Alias::new("update"),
+                                    Alias::new("delete"),
+                                    Alias::new("restore"),
+                                    Alias::new("revert"),
+                                ],
+                            )
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Revision::Changes).json().not_null())
+                    .col(
+                        ColumnDef::new(Revision::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .index(
+                        Index::create()
+                            .name("idx-revision-entity-entity_id")
+                            .col(Revision::Entity)
+                            .col(Revision::EntityId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Revision::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum Revision {
+    Table,
+    Id,
+    UserId,
+    Entity,
+    EntityId,
+    Action,
+    Changes,
+    CreatedAt,
+}
@@ -0,0 +1,75 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(IdempotencyKey::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(IdempotencyKey::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // 0 for requests made without signing in.
+                    .col(ColumnDef::new(IdempotencyKey::UserId).integer().not_null())
+                    .col(ColumnDef::new(IdempotencyKey::Key).string().not_null())
+                    .col(
+                        ColumnDef::new(IdempotencyKey::Fingerprint)
+                            .string_len(64)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(IdempotencyKey::ResponseStatus).small_integer())
+                    .col(ColumnDef::new(IdempotencyKey::ResponseHeaders).json())
+                    .col(ColumnDef::new(IdempotencyKey::ResponseBody).blob(BlobSize::Medium))
+                    .col(
+                        ColumnDef::new(IdempotencyKey::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .index(
+                        Index::create()
+                            .name("idx-idempotency_key-user_id-key")
+                            .col(IdempotencyKey::UserId)
+                            .col(IdempotencyKey::Key)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(IdempotencyKey::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum IdempotencyKey {
+    Table,
+    Id,
+    UserId,
+    Key,
+    Fingerprint,
+    ResponseStatus,
+    ResponseHeaders,
+    ResponseBody,
+    CreatedAt,
+}
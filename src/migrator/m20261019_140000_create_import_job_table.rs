@@ -0,0 +1,116 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use sea_orm_migration::prelude::*;
+
+use super::m20220101_000001_create_user_table::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ImportJob::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ImportJob::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ImportJob::UserId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-import_job-user_id")
+                            .from(ImportJob::Table, ImportJob::UserId)
+                            .to(User::Table, User::Id),
+                    )
+                    .col(
+                        ColumnDef::new(ImportJob::Format)
+                            .enumeration(
+                                ImportJob::Format,
+                                [Alias::new("csv"), Alias::new("jsonl")],
+                            )
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ImportJob::DryRun)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(ImportJob::Status)
+                            .enumeration(
+                                ImportJob::Status,
+                                [
+                                    Alias::new("pending"),
+                                    Alias::new("running"),
+                                    Alias::new("completed"),
+                                    Alias::new("failed"),
+                                ],
+                            )
+                            .not_null()
+                            .default("pending"),
+                    )
+                    .col(&mut counter(ImportJob::TotalRows))
+                    .col(&mut counter(ImportJob::ProcessedRows))
+                    .col(&mut counter(ImportJob::FailedRows))
+                    .col(&mut counter(ImportJob::BooksCreated))
+                    .col(&mut counter(ImportJob::AuthorsCreated))
+                    .col(&mut counter(ImportJob::AuthorsMatched))
+                    .col(ColumnDef::new(ImportJob::Errors).json().not_null())
+                    .col(
+                        ColumnDef::new(ImportJob::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .col(ColumnDef::new(ImportJob::FinishedAt).timestamp().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ImportJob::Table).to_owned())
+            .await
+    }
+}
+
+fn counter(col: ImportJob) -> ColumnDef {
+    ColumnDef::new(col)
+        .integer()
+        .not_null()
+        .default(0)
+        .to_owned()
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum ImportJob {
+    Table,
+    Id,
+    UserId,
+    Format,
+    DryRun,
+    Status,
+    TotalRows,
+    ProcessedRows,
+    FailedRows,
+    BooksCreated,
+    AuthorsCreated,
+    AuthorsMatched,
+    Errors,
+    CreatedAt,
+    FinishedAt,
+}
@@ -0,0 +1,74 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use sea_orm_migration::prelude::*;
+
+use super::m20230321_054811_create_book_table::Book;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .add_column(ColumnDef::new(BookEdition::Isbn).string_len(13).null())
+                    .add_column(ColumnDef::new(BookEdition::Publisher).string().null())
+                    .add_column(ColumnDef::new(BookEdition::Edition).string().null())
+                    .add_column(ColumnDef::new(BookEdition::Pages).integer().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Unique, so an ISBN identifies one book; MySQL allows many NULLs.
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-book-isbn")
+                    .table(Book::Table)
+                    .col(BookEdition::Isbn)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-book-isbn")
+                    .table(Book::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Book::Table)
+                    .drop_column(BookEdition::Isbn)
+                    .drop_column(BookEdition::Publisher)
+                    .drop_column(BookEdition::Edition)
+                    .drop_column(BookEdition::Pages)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum BookEdition {
+    Isbn,
+    Publisher,
+    Edition,
+    Pages,
+}
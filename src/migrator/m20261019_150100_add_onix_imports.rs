@@ -0,0 +1,58 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use sea_orm_migration::prelude::*;
+
+use super::m20261019_140000_create_import_job_table::ImportJob;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ImportJob::Table)
+                    .modify_column(&mut format(&["csv", "jsonl", "onix"]))
+                    .add_column(
+                        ColumnDef::new(OnixImport::BooksUpdated)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ImportJob::Table)
+                    .modify_column(&mut format(&["csv", "jsonl"]))
+                    .drop_column(OnixImport::BooksUpdated)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+fn format(values: &[&str]) -> ColumnDef {
+    ColumnDef::new(ImportJob::Format)
+        .enumeration(ImportJob::Format, values.iter().map(|v| Alias::new(v)))
+        .not_null()
+        .to_owned()
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum OnixImport {
+    BooksUpdated,
+}
@@ -0,0 +1,45 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use sea_orm_migration::prelude::*;
+
+use super::m20261019_140000_create_import_job_table::ImportJob;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ImportJob::Table)
+                    .modify_column(&mut format(&["csv", "jsonl", "onix", "marc"]))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ImportJob::Table)
+                    .modify_column(&mut format(&["csv", "jsonl", "onix"]))
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+fn format(values: &[&str]) -> ColumnDef {
+    ColumnDef::new(ImportJob::Format)
+        .enumeration(ImportJob::Format, values.iter().map(|v| Alias::new(v)))
+        .not_null()
+        .to_owned()
+}
@@ -0,0 +1,127 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // One row per ISBN, so an edition with an ISBN-10 and an ISBN-13 has two.
+        manager
+            .create_table(
+                Table::create()
+                    .table(OpenLibraryEdition::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OpenLibraryEdition::Isbn)
+                            .string_len(13)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(OpenLibraryEdition::EditionKey)
+                            .string_len(32)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(OpenLibraryEdition::WorkKey).string_len(32))
+                    .col(ColumnDef::new(OpenLibraryEdition::AuthorKey).string_len(32))
+                    .col(ColumnDef::new(OpenLibraryEdition::Title).text().not_null())
+                    .col(ColumnDef::new(OpenLibraryEdition::Subtitle).text())
+                    .col(ColumnDef::new(OpenLibraryEdition::Publisher).string())
+                    .col(ColumnDef::new(OpenLibraryEdition::PublishDate).string())
+                    .col(ColumnDef::new(OpenLibraryEdition::Pages).integer())
+                    .col(ColumnDef::new(OpenLibraryEdition::EditionName).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(OpenLibraryWork::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OpenLibraryWork::WorkKey)
+                            .string_len(32)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(OpenLibraryWork::AuthorKey).string_len(32))
+                    .col(ColumnDef::new(OpenLibraryWork::Title).text().not_null())
+                    .col(ColumnDef::new(OpenLibraryWork::FirstPublishDate).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(OpenLibraryAuthor::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OpenLibraryAuthor::AuthorKey)
+                            .string_len(32)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(OpenLibraryAuthor::Name).string().not_null())
+                    .col(ColumnDef::new(OpenLibraryAuthor::Bio).text())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OpenLibraryAuthor::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(OpenLibraryWork::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(OpenLibraryEdition::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum OpenLibraryEdition {
+    Table,
+    Isbn,
+    EditionKey,
+    WorkKey,
+    AuthorKey,
+    Title,
+    Subtitle,
+    Publisher,
+    PublishDate,
+    Pages,
+    EditionName,
+}
+
+#[derive(Iden)]
+pub enum OpenLibraryWork {
+    Table,
+    WorkKey,
+    AuthorKey,
+    Title,
+    FirstPublishDate,
+}
+
+#[derive(Iden)]
+pub enum OpenLibraryAuthor {
+    Table,
+    AuthorKey,
+    Name,
+    Bio,
+}
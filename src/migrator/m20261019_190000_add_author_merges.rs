@@ -0,0 +1,114 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use sea_orm_migration::prelude::*;
+
+use super::m20230321_053747_create_author_table::Author;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Author::Table)
+                    .add_column(ColumnDef::new(AuthorMerge::MergedIntoId).integer().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_foreign_key(
+                ForeignKey::create()
+                    .name("fk-author-merged_into_id")
+                    .from(Author::Table, AuthorMerge::MergedIntoId)
+                    .to(Author::Table, Author::Id)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuthorAlias::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuthorAlias::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AuthorAlias::AuthorId).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-author_alias-author_id")
+                            .from(AuthorAlias::Table, AuthorAlias::AuthorId)
+                            .to(Author::Table, Author::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(AuthorAlias::Name).string().not_null())
+                    .col(
+                        ColumnDef::new(AuthorAlias::CreatedAt)
+                            .timestamp()
+                            .extra("DEFAULT CURRENT_TIMESTAMP".to_owned()),
+                    )
+                    .index(
+                        Index::create()
+                            .name("idx-author_alias-author_id-name")
+                            .col(AuthorAlias::AuthorId)
+                            .col(AuthorAlias::Name)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuthorAlias::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name("fk-author-merged_into_id")
+                    .table(Author::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Author::Table)
+                    .drop_column(AuthorMerge::MergedIntoId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum AuthorMerge {
+    MergedIntoId,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum AuthorAlias {
+    Table,
+    Id,
+    AuthorId,
+    Name,
+    CreatedAt,
+}
@@ -0,0 +1,143 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use sea_orm_migration::prelude::*;
+
+use super::{
+    m20230321_053747_create_author_table::Author, m20261019_190000_add_author_merges::AuthorAlias,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+fn precision(col: AuthorProfile) -> ColumnDef {
+    ColumnDef::new(col.clone())
+        .enumeration(
+            col,
+            [Alias::new("year"), Alias::new("month"), Alias::new("day")],
+        )
+        .null()
+        .to_owned()
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Author::Table)
+                    .add_column(
+                        ColumnDef::new(AuthorProfile::SortName)
+                            .string()
+                            .not_null()
+                            .default(""),
+                    )
+                    .add_column(ColumnDef::new(AuthorProfile::BornOn).date().null())
+                    .add_column(&mut precision(AuthorProfile::BornPrecision))
+                    .add_column(ColumnDef::new(AuthorProfile::DiedOn).date().null())
+                    .add_column(&mut precision(AuthorProfile::DiedPrecision))
+                    .add_column(ColumnDef::new(AuthorProfile::Nationality).string().null())
+                    .add_column(ColumnDef::new(AuthorProfile::Website).string().null())
+                    .add_column(ColumnDef::new(AuthorProfile::Photo).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // "Lastname, Firstname", or the single name.
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Author::Table)
+                    .value(
+                        AuthorProfile::SortName,
+                        Expr::cust(
+                            "IF(`firstname` = '', `lastname`, CONCAT(`lastname`, ', ', `firstname`))",
+                        ),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-author-sort_name")
+                    .table(Author::Table)
+                    .col(AuthorProfile::SortName)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AuthorAlias::Table)
+                    .add_column(
+                        ColumnDef::new(AuthorProfile::Kind)
+                            .enumeration(
+                                AuthorProfile::Kind,
+                                [Alias::new("alias"), Alias::new("pen_name")],
+                            )
+                            .not_null()
+                            .default("alias"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AuthorAlias::Table)
+                    .drop_column(AuthorProfile::Kind)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-author-sort_name")
+                    .table(Author::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Author::Table)
+                    .drop_column(AuthorProfile::SortName)
+                    .drop_column(AuthorProfile::BornOn)
+                    .drop_column(AuthorProfile::BornPrecision)
+                    .drop_column(AuthorProfile::DiedOn)
+                    .drop_column(AuthorProfile::DiedPrecision)
+                    .drop_column(AuthorProfile::Nationality)
+                    .drop_column(AuthorProfile::Website)
+                    .drop_column(AuthorProfile::Photo)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden, Clone)]
+pub enum AuthorProfile {
+    SortName,
+    BornOn,
+    BornPrecision,
+    DiedOn,
+    DiedPrecision,
+    Nationality,
+    Website,
+    Photo,
+    Kind,
+}
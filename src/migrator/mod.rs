@@ -10,6 +10,22 @@ pub use sea_orm_migration::prelude::*;
 mod m20220101_000001_create_user_table;
 mod m20230321_053747_create_author_table;
 mod m20230321_054811_create_book_table;
+mod m20261019_090000_typed_book_publication_date;
+mod m20261019_090100_drop_book_year;
+mod m20261019_100000_add_soft_delete_columns;
+mod m20261019_100100_add_user_role;
+mod m20261019_110000_create_revision_table;
+mod m20261019_120000_add_version_columns;
+mod m20261019_130000_create_idempotency_key_table;
+mod m20261019_140000_create_import_job_table;
+mod m20261019_150000_add_book_edition_columns;
+mod m20261019_150100_add_onix_imports;
+mod m20261019_160000_add_marc_imports;
+mod m20261019_170000_add_user_api_key;
+mod m20261019_180000_create_open_library_tables;
+mod m20261019_190000_add_author_merges;
+mod m20261019_200000_add_author_profile_columns;
+mod m20261019_210000_add_book_genre;
 
 pub struct Migrator;
 
@@ -20,6 +36,22 @@ impl MigratorTrait for Migrator {
             Box::new(m20220101_000001_create_user_table::Migration),
             Box::new(m20230321_053747_create_author_table::Migration),
             Box::new(m20230321_054811_create_book_table::Migration),
+            Box::new(m20261019_090000_typed_book_publication_date::Migration),
+            Box::new(m20261019_090100_drop_book_year::Migration),
+            Box::new(m20261019_100000_add_soft_delete_columns::Migration),
+            Box::new(m20261019_100100_add_user_role::Migration),
+            Box::new(m20261019_110000_create_revision_table::Migration),
+            Box::new(m20261019_120000_add_version_columns::Migration),
+            Box::new(m20261019_130000_create_idempotency_key_table::Migration),
+            Box::new(m20261019_140000_create_import_job_table::Migration),
+            Box::new(m20261019_150000_add_book_edition_columns::Migration),
+            Box::new(m20261019_150100_add_onix_imports::Migration),
+            Box::new(m20261019_160000_add_marc_imports::Migration),
+            Box::new(m20261019_170000_add_user_api_key::Migration),
+            Box::new(m20261019_180000_create_open_library_tables::Migration),
+            Box::new(m20261019_190000_add_author_merges::Migration),
+            Box::new(m20261019_200000_add_author_profile_columns::Migration),
+            Box::new(m20261019_210000_add_book_genre::Migration),
         ]
     }
 }
@@ -0,0 +1,249 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use std::collections::{HashMap, HashSet};
+
+/// Why two names were taken for the same person, strongest first.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Reason {
+    /// The same once case, punctuation and spacing are ignored, as with
+    /// "J. R. R. Tolkien" and "JRR Tolkien".
+    SameName,
+    /// The same surname, with given names that agree where both are given,
+    /// an initial agreeing with any name it starts, as with "J. R. R. Tolkien"
+    /// and "John Tolkien".
+    Initials,
+    /// Different surnames, but similar enough by trigrams, as with
+    /// "J. R. R. Tolkein" and "J. R. R. Tolkien".
+    Similar,
+}
+
+impl Reason {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Reason::SameName => "same_name",
+            Reason::Initials => "initials",
+            Reason::Similar => "similar",
+        }
+    }
+}
+
+/// A person's name, split into lowercase words without punctuation, with the
+/// trigrams of the whole name worked out once for comparing it with many others.
+#[derive(Clone)]
+pub struct Name {
+    given: Vec<String>,
+    family: String,
+    trigrams: HashSet<[char; 3]>,
+}
+
+impl Name {
+    pub fn new(given: &str, family: &str) -> Self {
+        let given = words(given, true);
+        let family = words(family, false);
+        let full = given
+            .iter()
+            .chain(family.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        Self {
+            trigrams: trigrams(&full.join(" ")),
+            family: family.concat(),
+            given,
+        }
+    }
+
+    /// A name given whole, its last word taken as the surname.
+    pub fn parse(full: &str) -> Self {
+        match full.trim().rsplit_once(char::is_whitespace) {
+            Some((given, family)) => Self::new(given, family),
+            None => Self::new("", full),
+        }
+    }
+
+    /// Whether these may be the same person's name, and how close they are.
+    /// With the same surname, the given names must agree, as relatives share
+    /// surnames; otherwise the names must be at least `threshold` similar.
+    pub fn compare(&self, other: &Name, threshold: f64) -> Option<(Reason, f64)> {
+        if self.family != other.family {
+            let similarity = overlap(&self.trigrams, &other.trigrams);
+            return (similarity >= threshold).then_some((Reason::Similar, similarity));
+        }
+
+        if self.given.concat() == other.given.concat() {
+            return Some((Reason::SameName, 1.0));
+        }
+
+        initials_agree(&self.given, &other.given)
+            .then(|| (Reason::Initials, overlap(&self.trigrams, &other.trigrams)))
+    }
+}
+
+/// The pairs `(i, j)`, `i < j`, of people, each known by one or more names,
+/// who may be the same by [`Name::compare`]; any other pair is not.
+///
+/// People are paired when they share a surname, or else when their names
+/// share one of the rarest trigrams: two sets of trigrams at least
+/// `threshold` alike must share one of the first `n - ceil(threshold * n) + 1`
+/// of each, `n` being its size, when both are ordered the same way.
+pub fn candidates(people: &[Vec<Name>], threshold: f64) -> Vec<(usize, usize)> {
+    let mut frequency = HashMap::<[char; 3], usize>::new();
+
+    for name in people.iter().flatten() {
+        for trigram in &name.trigrams {
+            *frequency.entry(*trigram).or_default() += 1;
+        }
+    }
+
+    let mut surnames = HashMap::<&str, Vec<usize>>::new();
+    let mut rarest = HashMap::<[char; 3], Vec<usize>>::new();
+
+    for (i, names) in people.iter().enumerate() {
+        for name in names {
+            surnames.entry(&name.family).or_default().push(i);
+
+            let mut trigrams = name.trigrams.iter().copied().collect::<Vec<_>>();
+            trigrams.sort_by_key(|t| (frequency[t], *t));
+
+            let n = trigrams.len();
+            // Rounded down a little so `0.7 * 10` is not taken for more than 7.
+            let shared = ((threshold * n as f64 - 1e-9).ceil() as usize).min(n);
+            let prefix = n - shared + 1;
+
+            for trigram in trigrams.into_iter().take(prefix) {
+                rarest.entry(trigram).or_default().push(i);
+            }
+        }
+    }
+
+    let mut pairs = HashSet::new();
+
+    for bucket in surnames.into_values().chain(rarest.into_values()) {
+        let mut bucket = bucket;
+        bucket.sort_unstable();
+        bucket.dedup();
+
+        for (k, i) in bucket.iter().enumerate() {
+            pairs.extend(bucket[k + 1..].iter().map(|j| (*i, *j)));
+        }
+    }
+
+    let mut pairs = pairs.into_iter().collect::<Vec<_>>();
+    pairs.sort_unstable();
+    pairs
+}
+
+/// The words of `name`, lowercase. Apostrophes are dropped, so "O'Brien" is
+/// one word, and any other punctuation separates words. In given names, runs
+/// of two or three capitals such as "JRR" are read as initials.
+fn words(name: &str, given: bool) -> Vec<String> {
+    name.replace(['\'', '’'], "")
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .flat_map(|w| {
+            let initials = given
+                && (2..=3).contains(&w.chars().count())
+                && w.chars().all(|c| c.is_uppercase());
+
+            match initials {
+                true => w.chars().map(|c| c.to_lowercase().collect()).collect(),
+                false => vec![w.to_lowercase()],
+            }
+        })
+        .collect()
+}
+
+/// Whether the given names agree word by word as far as both go, a single
+/// letter agreeing with any word it starts. No given name agrees only with none.
+fn initials_agree(a: &[String], b: &[String]) -> bool {
+    if a.is_empty() || b.is_empty() {
+        return a.is_empty() && b.is_empty();
+    }
+
+    a.iter().zip(b).all(|(x, y)| {
+        x == y
+            || (x.chars().count() == 1 && y.starts_with(x.as_str()))
+            || (y.chars().count() == 1 && x.starts_with(y.as_str()))
+    })
+}
+
+/// The trigrams of each word, padded with two spaces before and one after, as
+/// PostgreSQL's `pg_trgm` does.
+fn trigrams(s: &str) -> HashSet<[char; 3]> {
+    s.split_whitespace()
+        .flat_map(|word| {
+            let padded = ["  ", word, " "].concat().chars().collect::<Vec<_>>();
+
+            padded
+                .windows(3)
+                .map(|w| [w[0], w[1], w[2]])
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// The share of trigrams `a` and `b` have in common, from 0 to 1.
+fn overlap(a: &HashSet<[char; 3]>, b: &HashSet<[char; 3]>) -> f64 {
+    let union = a.union(b).count();
+
+    match union {
+        0 => 0.0,
+        _ => a.intersection(b).count() as f64 / union as f64,
+    }
+}
+
+/// How a person files in lists sorted by name: `Family, Given`, or the one
+/// name of those known by a single name.
+pub fn sort_name(given: &str, family: &str) -> String {
+    match (given.trim(), family.trim()) {
+        ("", family) => family.to_string(),
+        (given, "") => given.to_string(),
+        (given, family) => format!("{}, {}", family, given),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn candidates_include_every_match() {
+        let people = [
+            vec![Name::new("J. R. R.", "Tolkien")],
+            vec![Name::new("JRR", "Tolkien")],
+            vec![Name::new("John", "Tolkien")],
+            vec![Name::new("Christopher", "Tolkien")],
+            vec![Name::new("J. R. R.", "Tolkein")],
+            vec![Name::new("Samuel", "Clemens"), Name::parse("Mark Twain")],
+            vec![Name::new("Mark", "Twain")],
+            vec![Name::new("Ursula K.", "Le Guin")],
+            vec![Name::new("Ursula", "LeGuin")],
+            vec![Name::new("", "Homer")],
+        ];
+
+        for threshold in [0.1, 0.3, 0.5, 0.7, 0.9, 1.0] {
+            let candidates = candidates(&people, threshold);
+
+            for i in 0..people.len() {
+                for j in i + 1..people.len() {
+                    let matches = people[i]
+                        .iter()
+                        .any(|x| people[j].iter().any(|y| x.compare(y, threshold).is_some()));
+
+                    assert!(
+                        !matches || candidates.contains(&(i, j)),
+                        "{} and {} at {}",
+                        i,
+                        j,
+                        threshold
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,472 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use chrono::{DateTime, Utc};
+
+use crate::isbn::Isbn;
+use crate::xml::{self, close, leaf, leaf_with, open, Element};
+
+/// A product of an ONIX 3.0 message, as much of it as a book has.
+#[derive(Default)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct Product {
+    /// The line the product starts on in the message it was read from.
+    pub line: u64,
+    pub record_reference: String,
+    /// Whether the product is withdrawn (notification type 05).
+    pub deleted: bool,
+    pub isbn: Option<String>,
+    pub title: String,
+    pub author_firstname: String,
+    pub author_lastname: String,
+    pub edition: Option<String>,
+    pub pages: Option<i32>,
+    pub publisher: Option<String>,
+    /// `YYYY`, `YYYY-MM` or `YYYY-MM-DD`.
+    pub published: Option<String>,
+    pub cover: Option<String>,
+}
+
+/// Reads the products of an ONIX 3.0 message, in reference or short tags.
+pub fn parse(body: &[u8]) -> Result<Vec<Product>, String> {
+    let mut root = xml::parse(body)?;
+    root.rename(&long_name);
+
+    if root.name != "ONIXMessage" {
+        return Err("The file is not an ONIX message.".to_string());
+    }
+
+    Ok(root.children("Product").map(product).collect())
+}
+
+/// The reference name of a short tag.
+fn long_name(short: &str) -> Option<&'static str> {
+    Some(match short {
+        "ONIXmessage" => "ONIXMessage",
+        "product" => "Product",
+        "a001" => "RecordReference",
+        "a002" => "NotificationType",
+        "productidentifier" => "ProductIdentifier",
+        "b221" => "ProductIDType",
+        "b244" => "IDValue",
+        "descriptivedetail" => "DescriptiveDetail",
+        "titledetail" => "TitleDetail",
+        "b202" => "TitleType",
+        "titleelement" => "TitleElement",
+        "x409" => "TitleElementLevel",
+        "b203" => "TitleText",
+        "b030" => "TitlePrefix",
+        "b031" => "TitleWithoutPrefix",
+        "contributor" => "Contributor",
+        "b035" => "ContributorRole",
+        "b036" => "PersonName",
+        "b039" => "NamesBeforeKey",
+        "b040" => "KeyNames",
+        "b057" => "EditionNumber",
+        "b058" => "EditionStatement",
+        "extent" => "Extent",
+        "b218" => "ExtentType",
+        "b219" => "ExtentValue",
+        "b220" => "ExtentUnit",
+        "publishingdetail" => "PublishingDetail",
+        "publisher" => "Publisher",
+        "b291" => "PublishingRole",
+        "b081" => "PublisherName",
+        "publishingdate" => "PublishingDate",
+        "x448" => "PublishingDateRole",
+        "b306" => "Date",
+        "collateraldetail" => "CollateralDetail",
+        "supportingresource" => "SupportingResource",
+        "x436" => "ResourceContentType",
+        "resourceversion" => "ResourceVersion",
+        "x435" => "ResourceLink",
+        _ => return None,
+    })
+}
+
+fn product(p: &Element) -> Product {
+    let detail = p.child("DescriptiveDetail");
+    let publishing = p.child("PublishingDetail");
+    let (author_firstname, author_lastname) = detail.map(author).unwrap_or_default();
+
+    Product {
+        line: p.line,
+        record_reference: p.text_of("RecordReference").unwrap_or("").to_string(),
+        deleted: p.text_of("NotificationType") == Some("05"),
+        isbn: isbn(p),
+        title: detail.and_then(title).unwrap_or_default(),
+        author_firstname,
+        author_lastname,
+        edition: detail.and_then(|d| {
+            d.text_of("EditionStatement")
+                .or_else(|| d.text_of("EditionNumber"))
+                .map(str::to_owned)
+        }),
+        pages: detail.and_then(pages),
+        publisher: publishing.and_then(publisher),
+        published: publishing.and_then(published),
+        cover: p.child("CollateralDetail").and_then(cover),
+    }
+}
+
+/// The ISBN-13 (type 15), or an EAN (03) that is one, or the ISBN-10 (02).
+fn isbn(p: &Element) -> Option<String> {
+    let id = |kind: &str| {
+        p.children("ProductIdentifier")
+            .filter(|i| i.text_of("ProductIDType") == Some(kind))
+            .find_map(|i| i.text_of("IDValue"))
+    };
+
+    id("15")
+        .or_else(|| id("03").filter(|v| v.starts_with("978") || v.starts_with("979")))
+        .or_else(|| id("02"))
+        .map(str::to_owned)
+}
+
+/// The distinctive title (type 01) at product level (01).
+fn title(d: &Element) -> Option<String> {
+    let detail = d
+        .children("TitleDetail")
+        .find(|t| t.text_of("TitleType") == Some("01"))?;
+    let element = detail
+        .children("TitleElement")
+        .find(|e| e.text_of("TitleElementLevel") == Some("01"))
+        .or_else(|| detail.child("TitleElement"))?;
+
+    match element.text_of("TitleText") {
+        Some(text) => Some(text.to_string()),
+        None => {
+            let rest = element.text_of("TitleWithoutPrefix")?;
+
+            Some(match element.text_of("TitlePrefix") {
+                Some(prefix) => format!("{} {}", prefix, rest),
+                None => rest.to_string(),
+            })
+        }
+    }
+}
+
+/// The first author (role A01), or failing that the first contributor.
+fn author(d: &Element) -> (String, String) {
+    let contributor = d
+        .children("Contributor")
+        .find(|c| c.text_of("ContributorRole") == Some("A01"))
+        .or_else(|| d.child("Contributor"));
+
+    let Some(c) = contributor else {
+        return Default::default();
+    };
+
+    match (c.text_of("NamesBeforeKey"), c.text_of("KeyNames")) {
+        (first, Some(last)) => (first.unwrap_or("").to_string(), last.to_string()),
+        _ => match c.text_of("PersonName").map(|n| n.rsplit_once(' ')) {
+            Some(Some((first, last))) => (first.trim().to_string(), last.to_string()),
+            Some(None) => (String::new(), c.text_of("PersonName").unwrap().to_string()),
+            None => Default::default(),
+        },
+    }
+}
+
+/// The page count: an extent in pages (unit 03), the main content (type 00)
+/// preferred.
+fn pages(d: &Element) -> Option<i32> {
+    let in_pages = || {
+        d.children("Extent")
+            .filter(|e| e.text_of("ExtentUnit") == Some("03"))
+    };
+
+    in_pages()
+        .find(|e| e.text_of("ExtentType") == Some("00"))
+        .or_else(|| in_pages().next())
+        .and_then(|e| e.text_of("ExtentValue"))
+        .and_then(|v| v.parse().ok())
+}
+
+fn publisher(p: &Element) -> Option<String> {
+    p.children("Publisher")
+        .find(|p| p.text_of("PublishingRole").is_none_or(|r| r == "01"))
+        .and_then(|p| p.text_of("PublisherName"))
+        .map(str::to_owned)
+}
+
+/// The publication date (role 01) as `YYYY[-MM[-DD]]`.
+fn published(p: &Element) -> Option<String> {
+    let date = p
+        .children("PublishingDate")
+        .find(|d| d.text_of("PublishingDateRole") == Some("01"))?
+        .child("Date")?;
+    let value = date.text.trim();
+
+    if !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    match (date.attribute("dateformat"), value.len()) {
+        (None | Some("00"), 8) => Some(format!("{}-{}-{}", &value[..4], &value[4..6], &value[6..])),
+        (None | Some("01"), 6) => Some(format!("{}-{}", &value[..4], &value[4..])),
+        (None | Some("05"), 4) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// The link to the front cover (resource type 01).
+fn cover(c: &Element) -> Option<String> {
+    c.children("SupportingResource")
+        .filter(|r| r.text_of("ResourceContentType") == Some("01"))
+        .flat_map(|r| r.children("ResourceVersion"))
+        .find_map(|v| v.text_of("ResourceLink"))
+        .map(str::to_owned)
+}
+
+/// The start of a message, up to its first product.
+pub fn header(sender: &str, sent: DateTime<Utc>) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+
+    open(
+        &mut out,
+        0,
+        "ONIXMessage release=\"3.0\" xmlns=\"http://ns.editeur.org/onix/3.0/reference\"",
+    );
+    open(&mut out, 1, "Header");
+    open(&mut out, 2, "Sender");
+    leaf(&mut out, 3, "SenderName", sender);
+    close(&mut out, 2, "Sender");
+    leaf(
+        &mut out,
+        2,
+        "SentDateTime",
+        &sent.format("%Y%m%dT%H%M%SZ").to_string(),
+    );
+    close(&mut out, 1, "Header");
+
+    out
+}
+
+/// The end of a message.
+pub fn footer() -> String {
+    let mut out = String::new();
+    close(&mut out, 0, "ONIXMessage");
+    out
+}
+
+impl Product {
+    /// The product in reference tags, to go between [`header`] and [`footer`].
+    pub fn to_xml(&self) -> String {
+        let mut out = String::new();
+
+        open(&mut out, 1, "Product");
+        leaf(&mut out, 2, "RecordReference", &self.record_reference);
+        leaf(
+            &mut out,
+            2,
+            "NotificationType",
+            if self.deleted { "05" } else { "03" },
+        );
+
+        open(&mut out, 2, "ProductIdentifier");
+        leaf(&mut out, 3, "ProductIDType", "01");
+        leaf(&mut out, 3, "IDTypeName", "BookStore");
+        leaf(&mut out, 3, "IDValue", &self.record_reference);
+        close(&mut out, 2, "ProductIdentifier");
+
+        if let Some(isbn) = self.isbn.as_ref().and_then(|i| i.parse::<Isbn>().ok()) {
+            open(&mut out, 2, "ProductIdentifier");
+            leaf(&mut out, 3, "ProductIDType", "15");
+            leaf(&mut out, 3, "IDValue", isbn.as_str());
+            close(&mut out, 2, "ProductIdentifier");
+        }
+
+        open(&mut out, 2, "DescriptiveDetail");
+        leaf(&mut out, 3, "ProductComposition", "00");
+        leaf(&mut out, 3, "ProductForm", "00");
+
+        open(&mut out, 3, "TitleDetail");
+        leaf(&mut out, 4, "TitleType", "01");
+        open(&mut out, 4, "TitleElement");
+        leaf(&mut out, 5, "TitleElementLevel", "01");
+        leaf(&mut out, 5, "TitleText", &self.title);
+        close(&mut out, 4, "TitleElement");
+        close(&mut out, 3, "TitleDetail");
+
+        open(&mut out, 3, "Contributor");
+        leaf(&mut out, 4, "SequenceNumber", "1");
+        leaf(&mut out, 4, "ContributorRole", "A01");
+        leaf(
+            &mut out,
+            4,
+            "PersonName",
+            format!("{} {}", self.author_firstname, self.author_lastname).trim(),
+        );
+        if !self.author_firstname.is_empty() {
+            leaf(&mut out, 4, "NamesBeforeKey", &self.author_firstname);
+        }
+        leaf(&mut out, 4, "KeyNames", &self.author_lastname);
+        close(&mut out, 3, "Contributor");
+
+        match &self.edition {
+            Some(number) if number.parse::<u32>().is_ok() => {
+                leaf(&mut out, 3, "EditionNumber", number)
+            }
+            Some(statement) => leaf(&mut out, 3, "EditionStatement", statement),
+            None => {}
+        }
+
+        if let Some(pages) = self.pages {
+            open(&mut out, 3, "Extent");
+            leaf(&mut out, 4, "ExtentType", "00");
+            leaf(&mut out, 4, "ExtentValue", &pages.to_string());
+            leaf(&mut out, 4, "ExtentUnit", "03");
+            close(&mut out, 3, "Extent");
+        }
+
+        close(&mut out, 2, "DescriptiveDetail");
+
+        if let Some(cover) = &self.cover {
+            open(&mut out, 2, "CollateralDetail");
+            open(&mut out, 3, "SupportingResource");
+            leaf(&mut out, 4, "ResourceContentType", "01");
+            leaf(&mut out, 4, "ContentAudience", "00");
+            leaf(&mut out, 4, "ResourceMode", "03");
+            open(&mut out, 4, "ResourceVersion");
+            leaf(&mut out, 5, "ResourceForm", "02");
+            leaf(&mut out, 5, "ResourceLink", cover);
+            close(&mut out, 4, "ResourceVersion");
+            close(&mut out, 3, "SupportingResource");
+            close(&mut out, 2, "CollateralDetail");
+        }
+
+        if self.publisher.is_some() || self.published.is_some() {
+            open(&mut out, 2, "PublishingDetail");
+
+            if let Some(publisher) = &self.publisher {
+                open(&mut out, 3, "Publisher");
+                leaf(&mut out, 4, "PublishingRole", "01");
+                leaf(&mut out, 4, "PublisherName", publisher);
+                close(&mut out, 3, "Publisher");
+            }
+
+            if let Some(published) = &self.published {
+                let value = published.replace('-', "");
+                let format = match value.len() {
+                    8 => "00",
+                    6 => "01",
+                    _ => "05",
+                };
+
+                open(&mut out, 3, "PublishingDate");
+                leaf(&mut out, 4, "PublishingDateRole", "01");
+                leaf_with(&mut out, 4, "Date", &[("dateformat", format)], &value);
+                close(&mut out, 3, "PublishingDate");
+            }
+
+            close(&mut out, 2, "PublishingDetail");
+        }
+
+        close(&mut out, 1, "Product");
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REFERENCE: &[u8] = include_bytes!("fixtures/reference.xml");
+    const SHORT: &[u8] = include_bytes!("fixtures/short.xml");
+
+    fn dune() -> Product {
+        Product {
+            record_reference: "com.example.9780441013593".to_string(),
+            isbn: Some("9780441013593".to_string()),
+            title: "Dune".to_string(),
+            author_firstname: "Frank".to_string(),
+            author_lastname: "Herbert".to_string(),
+            edition: Some("2".to_string()),
+            pages: Some(896),
+            publisher: Some("Ace".to_string()),
+            published: Some("2005-08".to_string()),
+            cover: Some("https://example.com/covers/dune.jpg".to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Drops what an export doesn't keep as is: the line, and the ISBN form,
+    /// as ISBNs are always exported as ISBN-13.
+    fn normalized(mut products: Vec<Product>) -> Vec<Product> {
+        for p in &mut products {
+            p.line = 0;
+            p.isbn = p
+                .isbn
+                .as_ref()
+                .and_then(|i| i.parse::<Isbn>().ok())
+                .map(|i| i.as_str().to_string());
+        }
+
+        products
+    }
+
+    fn export(products: &[Product]) -> Vec<u8> {
+        let sent = DateTime::parse_from_rfc3339("2026-10-19T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let mut out = header("BookStore", sent);
+        out.extend(products.iter().map(Product::to_xml));
+        out.push_str(&footer());
+
+        out.into_bytes()
+    }
+
+    #[test]
+    fn parses_reference_tags() {
+        let products = normalized(parse(REFERENCE).unwrap());
+
+        assert_eq!(products.len(), 2);
+        assert_eq!(products[0], dune());
+        assert_eq!(
+            products[1],
+            Product {
+                record_reference: "com.example.0261103342".to_string(),
+                deleted: true,
+                isbn: Some("9780261103344".to_string()),
+                title: "The Hobbit".to_string(),
+                author_firstname: "J. R. R.".to_string(),
+                author_lastname: "Tolkien".to_string(),
+                edition: Some("Anniversary edition".to_string()),
+                published: Some("1937".to_string()),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn parses_short_tags() {
+        assert_eq!(normalized(parse(SHORT).unwrap()), vec![dune()]);
+    }
+
+    #[test]
+    fn round_trips_reference_tags() {
+        let parsed = normalized(parse(REFERENCE).unwrap());
+        let reparsed = normalized(parse(&export(&parsed)).unwrap());
+
+        assert_eq!(reparsed, parsed);
+    }
+
+    #[test]
+    fn round_trips_short_tags() {
+        let parsed = normalized(parse(SHORT).unwrap());
+        let reparsed = normalized(parse(&export(&parsed)).unwrap());
+
+        assert_eq!(reparsed, parsed);
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        assert!(parse(b"<collection/>").is_err());
+    }
+}
@@ -0,0 +1,392 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Value};
+
+use crate::xml::{close, empty, leaf, leaf_with, open};
+
+/// The number of entries on each page of a paged feed.
+pub const PAGE_SIZE: u64 = 50;
+
+pub const REL_ACQUISITION: &str = "http://opds-spec.org/acquisition";
+pub const REL_IMAGE: &str = "http://opds-spec.org/image";
+pub const REL_THUMBNAIL: &str = "http://opds-spec.org/image/thumbnail";
+
+/// The two encodings of a catalogue: OPDS 1.2 is Atom, OPDS 2.0 is JSON.
+#[derive(Clone, Copy)]
+pub enum Flavor {
+    Atom,
+    Json,
+}
+
+/// What a link leads to.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    /// A feed of further feeds.
+    Navigation,
+    /// A feed of books.
+    Acquisition,
+    /// A single book.
+    Entry,
+}
+
+impl Flavor {
+    pub fn media_type(self, kind: Kind) -> &'static str {
+        match (self, kind) {
+            (Flavor::Atom, Kind::Navigation) => {
+                "application/atom+xml;profile=opds-catalog;kind=navigation"
+            }
+            (Flavor::Atom, Kind::Acquisition) => {
+                "application/atom+xml;profile=opds-catalog;kind=acquisition"
+            }
+            (Flavor::Atom, Kind::Entry) => "application/atom+xml;type=entry;profile=opds-catalog",
+            (Flavor::Json, Kind::Entry) => "application/opds-publication+json",
+            (Flavor::Json, _) => "application/opds+json",
+        }
+    }
+
+    pub fn feed(self, feed: &Feed) -> String {
+        match self {
+            Flavor::Atom => atom(feed),
+            Flavor::Json => self.json(feed).to_string(),
+        }
+    }
+
+    /// A single book, as the target of its acquisition link.
+    pub fn entry(self, publication: &Publication) -> String {
+        match self {
+            Flavor::Atom => {
+                let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+                atom_entry(&mut out, 0, publication, true);
+                out
+            }
+            Flavor::Json => self.publication(publication).to_string(),
+        }
+    }
+
+    fn json(self, feed: &Feed) -> Value {
+        let mut metadata = json!({
+            "title": feed.title,
+            "modified": timestamp(feed.updated),
+        });
+
+        if let Some(page) = feed.page {
+            metadata["currentPage"] = json!(page);
+            metadata["itemsPerPage"] = json!(PAGE_SIZE);
+        }
+
+        let mut out = json!({
+            "metadata": metadata,
+            "links": feed.links.iter().map(|l| self.link(l)).collect::<Vec<_>>(),
+        });
+
+        if !feed.navigation.is_empty() {
+            out["navigation"] = feed
+                .navigation
+                .iter()
+                .map(|n| {
+                    json!({
+                        "href": n.href,
+                        "title": n.title,
+                        "type": self.media_type(n.kind),
+                    })
+                })
+                .collect();
+        }
+
+        if feed.kind == Kind::Acquisition {
+            out["publications"] = feed
+                .publications
+                .iter()
+                .map(|p| self.publication(p))
+                .collect();
+        }
+
+        out
+    }
+
+    fn link(self, link: &Link) -> Value {
+        json!({
+            "rel": link.rel,
+            "href": link.href,
+            "type": self.media_type(link.kind),
+        })
+    }
+
+    fn publication(self, p: &Publication) -> Value {
+        let mut metadata = json!({
+            "@type": "http://schema.org/Book",
+            "identifier": p.identifier(),
+            "title": p.title,
+            "author": [{
+                "name": p.author,
+                "links": [{
+                    "href": p.author_href,
+                    "type": self.media_type(Kind::Acquisition),
+                }],
+            }],
+            "modified": timestamp(p.updated),
+        });
+
+        if let Some(publisher) = &p.publisher {
+            metadata["publisher"] = json!(publisher);
+        }
+
+        if let Some(published) = &p.published {
+            metadata["published"] = json!(published);
+        }
+
+        if let Some(pages) = p.pages {
+            metadata["numberOfPages"] = json!(pages);
+        }
+
+        if let Some(genre) = &p.genre {
+            metadata["subject"] = json!([{
+                "name": genre.name,
+                "links": [{
+                    "href": genre.href,
+                    "type": self.media_type(Kind::Acquisition),
+                }],
+            }]);
+        }
+
+        let images = [&p.image, &p.thumbnail]
+            .into_iter()
+            .flatten()
+            .map(|i| match i.media_type {
+                Some(t) => json!({ "href": i.href, "type": t }),
+                None => json!({ "href": i.href }),
+            })
+            .collect::<Vec<_>>();
+
+        let mut out = json!({
+            "metadata": metadata,
+            "links": [
+                self.link(&Link::new("self", &p.acquisition, Kind::Entry)),
+                self.link(&Link::new(REL_ACQUISITION, &p.acquisition, Kind::Entry)),
+            ],
+        });
+
+        if !images.is_empty() {
+            out["images"] = json!(images);
+        }
+
+        out
+    }
+}
+
+/// A catalogue feed, independent of its encoding.
+pub struct Feed {
+    pub id: String,
+    pub title: String,
+    pub updated: DateTime<Utc>,
+    pub kind: Kind,
+    /// The page number of a paged feed.
+    pub page: Option<u64>,
+    pub links: Vec<Link>,
+    pub navigation: Vec<Navigation>,
+    pub publications: Vec<Publication>,
+}
+
+pub struct Link {
+    pub rel: &'static str,
+    pub href: String,
+    pub kind: Kind,
+}
+
+impl Link {
+    pub fn new(rel: &'static str, href: &str, kind: Kind) -> Self {
+        Self {
+            rel,
+            href: href.to_string(),
+            kind,
+        }
+    }
+}
+
+/// An entry of a navigation feed, leading to another feed.
+pub struct Navigation {
+    pub id: String,
+    pub title: String,
+    pub summary: String,
+    pub href: String,
+    pub kind: Kind,
+}
+
+/// A genre, and the feed of its books.
+pub struct Genre {
+    pub name: String,
+    pub href: String,
+}
+
+pub struct Image {
+    pub href: String,
+    /// Unknown for covers hosted elsewhere.
+    pub media_type: Option<&'static str>,
+}
+
+/// A book, as an entry of an acquisition feed.
+pub struct Publication {
+    pub id: i32,
+    pub title: String,
+    pub author: String,
+    pub author_href: String,
+    pub isbn: Option<String>,
+    pub publisher: Option<String>,
+    /// A year, month or day, as in `2001`, `2001-09` or `2001-09-11`.
+    pub published: Option<String>,
+    pub pages: Option<i32>,
+    pub genre: Option<Genre>,
+    pub updated: DateTime<Utc>,
+    pub acquisition: String,
+    pub image: Option<Image>,
+    pub thumbnail: Option<Image>,
+}
+
+impl Publication {
+    /// The ISBN as a URN, or else a URN of the ID.
+    fn identifier(&self) -> String {
+        match &self.isbn {
+            Some(isbn) => format!("urn:isbn:{}", isbn),
+            None => format!("urn:bookstore:book:{}", self.id),
+        }
+    }
+}
+
+const NAMESPACES: &str =
+    "xmlns=\"http://www.w3.org/2005/Atom\" xmlns:dc=\"http://purl.org/dc/terms/\"";
+
+fn atom(feed: &Feed) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+
+    open(&mut out, 0, &format!("feed {}", NAMESPACES));
+    leaf(&mut out, 1, "id", &feed.id);
+    leaf(&mut out, 1, "title", &feed.title);
+    leaf(&mut out, 1, "updated", &timestamp(feed.updated));
+    open(&mut out, 1, "author");
+    leaf(&mut out, 2, "name", "BookStore");
+    close(&mut out, 1, "author");
+
+    for link in &feed.links {
+        atom_link(
+            &mut out,
+            1,
+            link.rel,
+            &link.href,
+            Flavor::Atom.media_type(link.kind),
+        );
+    }
+
+    for n in &feed.navigation {
+        open(&mut out, 1, "entry");
+        leaf(&mut out, 2, "title", &n.title);
+        leaf(&mut out, 2, "id", &n.id);
+        leaf(&mut out, 2, "updated", &timestamp(feed.updated));
+        leaf_with(&mut out, 2, "content", &[("type", "text")], &n.summary);
+        atom_link(
+            &mut out,
+            2,
+            "subsection",
+            &n.href,
+            Flavor::Atom.media_type(n.kind),
+        );
+        close(&mut out, 1, "entry");
+    }
+
+    for p in &feed.publications {
+        atom_entry(&mut out, 1, p, false);
+    }
+
+    close(&mut out, 0, "feed");
+
+    out
+}
+
+/// Writes `p` as an `<entry>`, declaring the namespaces when it stands alone.
+fn atom_entry(out: &mut String, depth: usize, p: &Publication, alone: bool) {
+    if alone {
+        open(out, depth, &format!("entry {}", NAMESPACES));
+    } else {
+        open(out, depth, "entry");
+    }
+
+    leaf(out, depth + 1, "title", &p.title);
+    leaf(
+        out,
+        depth + 1,
+        "id",
+        &format!("urn:bookstore:book:{}", p.id),
+    );
+    leaf(out, depth + 1, "updated", &timestamp(p.updated));
+    open(out, depth + 1, "author");
+    leaf(out, depth + 2, "name", &p.author);
+    leaf(out, depth + 2, "uri", &p.author_href);
+    close(out, depth + 1, "author");
+
+    if let Some(isbn) = &p.isbn {
+        leaf(
+            out,
+            depth + 1,
+            "dc:identifier",
+            &format!("urn:isbn:{}", isbn),
+        );
+    }
+
+    if let Some(publisher) = &p.publisher {
+        leaf(out, depth + 1, "dc:publisher", publisher);
+    }
+
+    if let Some(published) = &p.published {
+        leaf(out, depth + 1, "dc:issued", published);
+    }
+
+    if let Some(genre) = &p.genre {
+        empty(
+            out,
+            depth + 1,
+            "category",
+            &[("term", &genre.name), ("label", &genre.name)],
+        );
+    }
+
+    for (rel, image) in [(REL_IMAGE, &p.image), (REL_THUMBNAIL, &p.thumbnail)] {
+        if let Some(image) = image {
+            match image.media_type {
+                Some(t) => atom_link(out, depth + 1, rel, &image.href, t),
+                None => empty(
+                    out,
+                    depth + 1,
+                    "link",
+                    &[("rel", rel), ("href", &image.href)],
+                ),
+            }
+        }
+    }
+
+    atom_link(
+        out,
+        depth + 1,
+        REL_ACQUISITION,
+        &p.acquisition,
+        Flavor::Atom.media_type(Kind::Entry),
+    );
+    close(out, depth, "entry");
+}
+
+fn atom_link(out: &mut String, depth: usize, rel: &str, href: &str, media_type: &str) {
+    empty(
+        out,
+        depth,
+        "link",
+        &[("rel", rel), ("href", href), ("type", media_type)],
+    );
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
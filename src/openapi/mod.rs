@@ -0,0 +1,238 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use rocket::{
+    fairing::{self, Fairing, Info, Kind},
+    http::Method,
+    Build, Rocket,
+};
+use std::collections::BTreeSet;
+use utoipa::{
+    openapi::{
+        security::{ApiKey, ApiKeyValue, Http, HttpAuthScheme, SecurityScheme},
+        OpenApi as Spec,
+    },
+    Modify, OpenApi,
+};
+
+use crate::controllers::{self, v1};
+use crate::graphql;
+
+/// Where the spec is served.
+pub const SPEC_PATH: &str = "/openapi.json";
+
+/// Where the API browser is served.
+pub const DOCS_PATH: &str = "/docs";
+
+/// Routes that are not part of the REST API.
+const UNDOCUMENTED: [&str; 3] = [SPEC_PATH, DOCS_PATH, graphql::PATH];
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "📕 BookStore"),
+    paths(
+        crate::index,
+        controllers::auth::sign_in,
+        controllers::auth::sign_up,
+        controllers::auth::me,
+        controllers::auth::create_api_key,
+        controllers::auth::revoke_api_key,
+        controllers::authors::index,
+        controllers::authors::create,
+        controllers::authors::bulk,
+        controllers::authors::show,
+        controllers::authors::update,
+        controllers::authors::patch,
+        controllers::authors::delete,
+        controllers::authors::restore,
+        controllers::authors::get_books,
+        controllers::revisions::author_history,
+        controllers::revisions::revert_author,
+        controllers::merges::duplicates,
+        controllers::merges::merge,
+        controllers::books::index,
+        controllers::books::create,
+        controllers::books::bulk,
+        controllers::books::show,
+        controllers::books::update,
+        controllers::books::patch,
+        controllers::books::delete,
+        controllers::books::restore,
+        controllers::revisions::book_history,
+        controllers::revisions::revert_book,
+        controllers::covers::upload,
+        controllers::covers::delete,
+        controllers::covers::original,
+        controllers::covers::thumbnail,
+        controllers::citations::cite,
+        controllers::citations::cite_many,
+        controllers::enrichment::enrich,
+        controllers::exports::export_books,
+        controllers::exports::export_authors,
+        controllers::imports::create,
+        controllers::imports::show,
+        controllers::imports::errors,
+        controllers::opds::atom_root,
+        controllers::opds::atom_newest,
+        controllers::opds::atom_authors,
+        controllers::opds::atom_author,
+        controllers::opds::atom_genres,
+        controllers::opds::atom_genre,
+        controllers::opds::atom_book,
+        controllers::opds::json_root,
+        controllers::opds::json_newest,
+        controllers::opds::json_authors,
+        controllers::opds::json_author,
+        controllers::opds::json_genres,
+        controllers::opds::json_genre,
+        controllers::opds::json_book,
+    ),
+    modifiers(&TokenAuth, &ReaderAuth, &TrimSlashes),
+    tags(
+        (name = "auth", description = "Accounts and tokens."),
+        (name = "authors"),
+        (name = "books"),
+        (name = "covers", description = "Book cover images."),
+        (name = "exports", description = "The catalogue as CSV, JSON Lines or Excel downloads."),
+        (name = "imports", description = "Loading books in bulk from CSV, JSON Lines, ONIX or MARC files."),
+        (name = "opds", description = "The catalogue for e-reader apps, as OPDS 1.2 and 2.0 feeds."),
+    )
+)]
+pub struct ApiDoc;
+
+/// Declares the `token` header set by signed-in clients.
+struct TokenAuth;
+
+impl Modify for TokenAuth {
+    fn modify(&self, openapi: &mut Spec) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "token",
+                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("token"))),
+            );
+        }
+    }
+}
+
+/// Declares the ways catalogue readers, which cannot send `token`, sign in:
+/// HTTP Basic with the account password or an API key, or the key alone.
+struct ReaderAuth;
+
+impl Modify for ReaderAuth {
+    fn modify(&self, openapi: &mut Spec) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "basic",
+                SecurityScheme::Http(Http::new(HttpAuthScheme::Basic)),
+            );
+            components.add_security_scheme(
+                "api_key",
+                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-Api-Key"))),
+            );
+        }
+    }
+}
+
+/// Lists `/` routes mounted under a prefix as `/books` rather than `/books/`.
+struct TrimSlashes;
+
+impl Modify for TrimSlashes {
+    fn modify(&self, openapi: &mut Spec) {
+        openapi.paths.paths = std::mem::take(&mut openapi.paths.paths)
+            .into_iter()
+            .map(|(path, item)| (normalize(&path), item))
+            .collect();
+    }
+}
+
+/// Refuses to launch when a mounted route is missing from the spec, or the
+/// spec documents an operation no route serves.
+pub struct SpecCheck;
+
+#[rocket::async_trait]
+impl Fairing for SpecCheck {
+    fn info(&self) -> Info {
+        Info {
+            name: "Check the OpenAPI spec against the routes",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        let (undocumented, unrouted) = mismatches(&rocket);
+
+        if undocumented.is_empty() && unrouted.is_empty() {
+            return Ok(rocket);
+        }
+
+        for (method, path) in undocumented {
+            error!("{} {} is not documented in the OpenAPI spec.", method, path);
+        }
+
+        for (method, path) in unrouted {
+            error!(
+                "{} {} is documented in the OpenAPI spec but not routed.",
+                method, path
+            );
+        }
+
+        Err(rocket)
+    }
+}
+
+type Operation = (String, String);
+
+/// The mounted routes missing from the spec, and the documented operations
+/// no route serves.
+pub fn mismatches(rocket: &Rocket<Build>) -> (Vec<Operation>, Vec<Operation>) {
+    let routes = rocket
+        .routes()
+        .filter(|r| r.method != Method::Options && !v1::is_unversioned_alias(r))
+        .map(|r| {
+            (
+                r.method.to_string(),
+                normalize(&r.uri.path().replace('<', "{").replace('>', "}")),
+            )
+        })
+        .filter(|(_, path)| !UNDOCUMENTED.contains(&path.as_str()))
+        .collect::<BTreeSet<_>>();
+
+    let documented = operations(&ApiDoc::openapi());
+
+    (
+        routes.difference(&documented).cloned().collect(),
+        documented.difference(&routes).cloned().collect(),
+    )
+}
+
+/// Every `(method, path)` pair documented in `spec`.
+fn operations(spec: &Spec) -> BTreeSet<Operation> {
+    spec.paths
+        .paths
+        .iter()
+        .flat_map(|(path, item)| {
+            [
+                ("GET", &item.get),
+                ("PUT", &item.put),
+                ("POST", &item.post),
+                ("DELETE", &item.delete),
+                ("PATCH", &item.patch),
+            ]
+            .into_iter()
+            .filter(|(_, op)| op.is_some())
+            .map(move |(method, _)| (method.to_string(), normalize(path)))
+        })
+        .collect()
+}
+
+/// Drops the trailing slash of `path`, unless it is the root.
+fn normalize(path: &str) -> String {
+    match path.trim_end_matches('/') {
+        "" => "/".to_string(),
+        p => p.to_string(),
+    }
+}
@@ -0,0 +1,378 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use flate2::read::MultiGzDecoder;
+use sea_orm::{sea_query::OnConflict, *};
+use serde_json::Value;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+use crate::entities::{open_library_author, open_library_edition, open_library_work, prelude::*};
+use crate::isbn::Isbn;
+use crate::publication::PublicationDate;
+
+/// Records written to the database at a time.
+const BATCH_SIZE: usize = 1000;
+
+/// The longest value of a `VARCHAR` column.
+const MAX_STRING_LENGTH: usize = 255;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// One line of a dump.
+pub enum Record {
+    /// An edition, once for each ISBN it has.
+    Editions(Vec<open_library_edition::Model>),
+    Work(open_library_work::Model),
+    Author(open_library_author::Model),
+    /// Any other type, or an edition without an ISBN.
+    Skipped,
+}
+
+/// Reads a dump line: the type, key, revision, time of the last change and the
+/// record as JSON, separated by tabs.
+pub fn parse_line(line: &str) -> Result<Record, String> {
+    let columns = line.splitn(5, '\t').collect::<Vec<_>>();
+
+    let [kind, key, _, _, json] = columns[..] else {
+        return Err("The line does not have 5 tab-separated columns.".to_string());
+    };
+
+    if !matches!(kind, "/type/edition" | "/type/work" | "/type/author") {
+        return Ok(Record::Skipped);
+    }
+
+    let record = serde_json::from_str::<Value>(json)
+        .map_err(|e| format!("The record is not valid JSON: {}.", e))?;
+
+    let Some(title) = text(&record["title"]).or_else(|| text(&record["name"])) else {
+        return Ok(Record::Skipped);
+    };
+
+    Ok(match kind {
+        "/type/edition" => {
+            let mut isbns = ["isbn_13", "isbn_10"]
+                .iter()
+                .filter_map(|field| record[field].as_array())
+                .flatten()
+                .filter_map(|i| i.as_str()?.parse::<Isbn>().ok())
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>();
+
+            isbns.sort_unstable();
+            isbns.dedup();
+
+            let edition = open_library_edition::Model {
+                isbn: String::new(),
+                edition_key: key.to_string(),
+                work_key: first_key(&record["works"]),
+                author_key: first_key(&record["authors"]),
+                title,
+                subtitle: text(&record["subtitle"]),
+                publisher: record["publishers"].get(0).and_then(short),
+                publish_date: short(&record["publish_date"]),
+                pages: record["number_of_pages"]
+                    .as_i64()
+                    .and_then(|p| i32::try_from(p).ok())
+                    .filter(|p| *p > 0),
+                edition_name: short(&record["edition_name"]),
+            };
+
+            match isbns.is_empty() {
+                true => Record::Skipped,
+                false => Record::Editions(
+                    isbns
+                        .into_iter()
+                        .map(|isbn| open_library_edition::Model {
+                            isbn,
+                            ..edition.clone()
+                        })
+                        .collect(),
+                ),
+            }
+        }
+        "/type/work" => Record::Work(open_library_work::Model {
+            work_key: key.to_string(),
+            author_key: record["authors"]
+                .get(0)
+                .and_then(|a| a["author"]["key"].as_str())
+                .map(str::to_string),
+            title,
+            first_publish_date: short(&record["first_publish_date"]),
+        }),
+        _ => Record::Author(open_library_author::Model {
+            author_key: key.to_string(),
+            name: title.chars().take(MAX_STRING_LENGTH).collect(),
+            bio: text(&record["bio"]),
+        }),
+    })
+}
+
+/// Trimmed text, given as a string or as `{"type": "/type/text", "value": ...}`.
+fn text(value: &Value) -> Option<String> {
+    value
+        .as_str()
+        .or_else(|| value["value"].as_str())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+}
+
+/// Text that fits a `VARCHAR` column.
+fn short(value: &Value) -> Option<String> {
+    text(value).map(|t| t.chars().take(MAX_STRING_LENGTH).collect())
+}
+
+/// The key of the first of a list of `{"key": ...}`.
+fn first_key(value: &Value) -> Option<String> {
+    value.get(0)?["key"].as_str().map(str::to_string)
+}
+
+/// Opens a dump, decompressing it if it is gzipped.
+pub fn open(path: &str) -> Result<Box<dyn BufRead + Send>, String> {
+    let file = File::open(path).map_err(|e| format!("Could not open {}: {}", path, e))?;
+
+    Ok(match path.ends_with(".gz") {
+        true => Box::new(BufReader::new(MultiGzDecoder::new(file))),
+        false => Box::new(BufReader::new(file)),
+    })
+}
+
+#[derive(Default)]
+pub struct LoadSummary {
+    pub lines: u64,
+    pub editions: u64,
+    pub works: u64,
+    pub authors: u64,
+    pub failed: u64,
+}
+
+/// Loads the editions, works and authors of a dump into the lookup tables,
+/// replacing the rows of records loaded before. Lines that can't be read are
+/// passed to `on_error` with their number, and `on_batch` is told how far the
+/// load got after every batch.
+pub async fn load(
+    db: &DatabaseConnection,
+    input: impl BufRead,
+    mut on_error: impl FnMut(u64, String),
+    mut on_batch: impl FnMut(&LoadSummary),
+) -> Result<LoadSummary, String> {
+    let mut summary = LoadSummary::default();
+    let mut editions = vec![];
+    let mut works = vec![];
+    let mut authors = vec![];
+
+    for line in input.lines() {
+        summary.lines += 1;
+
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                summary.failed += 1;
+                on_error(summary.lines, format!("The line could not be read: {}.", e));
+                continue;
+            }
+        };
+
+        match parse_line(&line) {
+            Ok(Record::Editions(e)) => {
+                summary.editions += 1;
+                editions.extend(e.into_iter().map(open_library_edition::ActiveModel::from));
+            }
+            Ok(Record::Work(w)) => {
+                summary.works += 1;
+                works.push(open_library_work::ActiveModel::from(w));
+            }
+            Ok(Record::Author(a)) => {
+                summary.authors += 1;
+                authors.push(open_library_author::ActiveModel::from(a));
+            }
+            Ok(Record::Skipped) => {}
+            Err(e) => {
+                summary.failed += 1;
+                on_error(summary.lines, e);
+            }
+        }
+
+        if editions.len() + works.len() + authors.len() >= BATCH_SIZE {
+            save(db, &mut editions, &mut works, &mut authors).await?;
+            on_batch(&summary);
+        }
+    }
+
+    save(db, &mut editions, &mut works, &mut authors).await?;
+    on_batch(&summary);
+
+    Ok(summary)
+}
+
+/// Upserts and clears the pending rows.
+async fn save(
+    db: &DatabaseConnection,
+    editions: &mut Vec<open_library_edition::ActiveModel>,
+    works: &mut Vec<open_library_work::ActiveModel>,
+    authors: &mut Vec<open_library_author::ActiveModel>,
+) -> Result<(), String> {
+    use open_library_author::Column as A;
+    use open_library_edition::Column as E;
+    use open_library_work::Column as W;
+
+    let fail = |e: DbErr| format!("Could not save the records: {}", e);
+
+    if !editions.is_empty() {
+        OpenLibraryEdition::insert_many(std::mem::take(editions))
+            .on_conflict(
+                OnConflict::column(E::Isbn)
+                    .update_columns([
+                        E::EditionKey,
+                        E::WorkKey,
+                        E::AuthorKey,
+                        E::Title,
+                        E::Subtitle,
+                        E::Publisher,
+                        E::PublishDate,
+                        E::Pages,
+                        E::EditionName,
+                    ])
+                    .to_owned(),
+            )
+            .exec(db)
+            .await
+            .map_err(fail)?;
+    }
+
+    if !works.is_empty() {
+        OpenLibraryWork::insert_many(std::mem::take(works))
+            .on_conflict(
+                OnConflict::column(W::WorkKey)
+                    .update_columns([W::AuthorKey, W::Title, W::FirstPublishDate])
+                    .to_owned(),
+            )
+            .exec(db)
+            .await
+            .map_err(fail)?;
+    }
+
+    if !authors.is_empty() {
+        OpenLibraryAuthor::insert_many(std::mem::take(authors))
+            .on_conflict(
+                OnConflict::column(A::AuthorKey)
+                    .update_columns([A::Name, A::Bio])
+                    .to_owned(),
+            )
+            .exec(db)
+            .await
+            .map_err(fail)?;
+    }
+
+    Ok(())
+}
+
+/// What Open Library knows of an edition.
+pub struct Metadata {
+    /// The edition's key, e.g. `/books/OL7353617M`.
+    pub source: String,
+    pub title: String,
+    pub published: Option<PublicationDate>,
+    pub publisher: Option<String>,
+    pub edition: Option<String>,
+    pub pages: Option<i32>,
+    pub author_bio: Option<String>,
+}
+
+/// The edition with `isbn`, filled in from its work and author.
+pub async fn lookup<C: ConnectionTrait>(db: &C, isbn: &Isbn) -> Result<Option<Metadata>, DbErr> {
+    let Some(edition) = OpenLibraryEdition::find_by_id(isbn.to_string())
+        .one(db)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let work = match &edition.work_key {
+        Some(key) => OpenLibraryWork::find_by_id(key.clone()).one(db).await?,
+        None => None,
+    };
+
+    let author = match edition
+        .author_key
+        .as_ref()
+        .or(work.as_ref().and_then(|w| w.author_key.as_ref()))
+    {
+        Some(key) => OpenLibraryAuthor::find_by_id(key.clone()).one(db).await?,
+        None => None,
+    };
+
+    let published = edition
+        .publish_date
+        .as_deref()
+        .and_then(parse_date)
+        .or_else(|| {
+            work.as_ref()
+                .and_then(|w| w.first_publish_date.as_deref())
+                .and_then(parse_date)
+        });
+
+    Ok(Some(Metadata {
+        source: edition.edition_key,
+        title: match edition.subtitle {
+            Some(subtitle) => format!("{}: {}", edition.title, subtitle),
+            None => edition.title,
+        }
+        .chars()
+        .take(MAX_STRING_LENGTH)
+        .collect(),
+        published,
+        publisher: edition.publisher,
+        edition: edition.edition_name,
+        pages: edition.pages,
+        author_bio: author.and_then(|a| a.bio),
+    }))
+}
+
+/// Reads the free-form dates of Open Library, such as `1968`, `Nov 1968`,
+/// `November 1, 1968`, `1 November 1968`, `c1968` or `1968-11-01`, to the
+/// precision they give.
+pub fn parse_date(value: &str) -> Option<PublicationDate> {
+    if let Ok(date) = value.trim().parse() {
+        return Some(date);
+    }
+
+    let mut year = None;
+    let mut month = None;
+    let mut day = None;
+
+    for word in value.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let number = word.trim_start_matches('c');
+
+        if number.len() == 4 && number.bytes().all(|b| b.is_ascii_digit()) {
+            year = year.or(number.parse::<i32>().ok());
+        } else if (1..=2).contains(&word.len()) && word.bytes().all(|b| b.is_ascii_digit()) {
+            day = day.or(word.parse::<u32>().ok());
+        } else if word.len() >= 3 {
+            let prefix = word.get(..3).unwrap_or_default().to_ascii_lowercase();
+            month = month.or(MONTHS.iter().position(|m| *m == prefix));
+        }
+    }
+
+    let year = year?;
+    let month = month.map(|m| m + 1);
+
+    let full = match (month, day) {
+        (Some(m), Some(d)) => format!("{:04}-{:02}-{:02}", year, m, d).parse().ok(),
+        _ => None,
+    };
+
+    full.or_else(|| match month {
+        Some(m) => format!("{:04}-{:02}", year, m).parse().ok(),
+        None => format!("{:04}", year).parse().ok(),
+    })
+}
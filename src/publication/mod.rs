@@ -0,0 +1,160 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use chrono::Datelike;
+use sea_orm::prelude::Date;
+use std::{fmt, str::FromStr};
+
+use crate::entities::sea_orm_active_enums::PublishedPrecision;
+
+/// A publication date known to year, year-month or full-date precision. Also
+/// used for when authors were born and died.
+///
+/// Written and parsed as `YYYY`, `YYYY-MM` or `YYYY-MM-DD`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicationDate {
+    pub year: i32,
+    pub month: Option<u32>,
+    pub day: Option<u32>,
+}
+
+impl PublicationDate {
+    /// Rebuilds a publication date from the `published_on` and
+    /// `published_precision` columns of a book, or the like columns of an author.
+    pub fn from_columns(
+        date: Option<Date>,
+        precision: Option<&PublishedPrecision>,
+    ) -> Option<Self> {
+        let date = date?;
+
+        Some(match precision.unwrap_or(&PublishedPrecision::Day) {
+            PublishedPrecision::Year => Self {
+                year: date.year(),
+                month: None,
+                day: None,
+            },
+            PublishedPrecision::Month => Self {
+                year: date.year(),
+                month: Some(date.month()),
+                day: None,
+            },
+            PublishedPrecision::Day => Self {
+                year: date.year(),
+                month: Some(date.month()),
+                day: Some(date.day()),
+            },
+        })
+    }
+
+    /// The values for the `published_on` and `published_precision` columns.
+    /// Unknown parts are stored as the first month / day.
+    pub fn to_columns(self) -> (Date, PublishedPrecision) {
+        (self.first_day(), self.precision())
+    }
+
+    pub fn precision(&self) -> PublishedPrecision {
+        match (self.month, self.day) {
+            (None, _) => PublishedPrecision::Year,
+            (Some(_), None) => PublishedPrecision::Month,
+            (Some(_), Some(_)) => PublishedPrecision::Day,
+        }
+    }
+
+    /// The earliest day covered by this date.
+    pub fn first_day(&self) -> Date {
+        Date::from_ymd_opt(self.year, self.month.unwrap_or(1), self.day.unwrap_or(1)).unwrap()
+    }
+
+    /// The latest day covered by this date.
+    pub fn last_day(&self) -> Date {
+        match (self.month, self.day) {
+            (None, _) => Date::from_ymd_opt(self.year, 12, 31).unwrap(),
+            (Some(m), None) => {
+                let (y, m) = if m == 12 {
+                    (self.year + 1, 1)
+                } else {
+                    (self.year, m + 1)
+                };
+
+                Date::from_ymd_opt(y, m, 1).unwrap().pred_opt().unwrap()
+            }
+            (Some(_), Some(_)) => self.first_day(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParsePublicationDateError;
+
+impl fmt::Display for ParsePublicationDateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected a date formatted as YYYY, YYYY-MM or YYYY-MM-DD"
+        )
+    }
+}
+
+impl FromStr for PublicationDate {
+    type Err = ParsePublicationDateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s.trim().split('-').collect::<Vec<_>>();
+
+        let number = |part: &str, len: usize| -> Result<u32, ParsePublicationDateError> {
+            if part.len() != len || !part.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(ParsePublicationDateError);
+            }
+
+            part.parse().map_err(|_| ParsePublicationDateError)
+        };
+
+        let date = match parts.as_slice() {
+            [y] => Self {
+                year: number(y, 4)? as i32,
+                month: None,
+                day: None,
+            },
+            [y, m] => Self {
+                year: number(y, 4)? as i32,
+                month: Some(number(m, 2)?),
+                day: None,
+            },
+            [y, m, d] => Self {
+                year: number(y, 4)? as i32,
+                month: Some(number(m, 2)?),
+                day: Some(number(d, 2)?),
+            },
+            _ => return Err(ParsePublicationDateError),
+        };
+
+        if date.year == 0
+            || Date::from_ymd_opt(date.year, date.month.unwrap_or(1), date.day.unwrap_or(1))
+                .is_none()
+        {
+            return Err(ParsePublicationDateError);
+        }
+
+        Ok(date)
+    }
+}
+
+impl fmt::Display for PublicationDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}", self.year)?;
+
+        if let Some(m) = self.month {
+            write!(f, "-{:02}", m)?;
+        }
+
+        if let Some(d) = self.day {
+            write!(f, "-{:02}", d)?;
+        }
+
+        Ok(())
+    }
+}
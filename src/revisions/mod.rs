@@ -0,0 +1,167 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use sea_orm::{
+    ActiveEnum, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::entities::{
+    author, author_alias, book, revision,
+    sea_orm_active_enums::{RevisionAction, RevisionEntity},
+};
+
+/// Columns that change on every write or never change, and so are left out of diffs.
+const UNTRACKED: [&str; 5] = ["id", "user_id", "created_at", "updated_at", "version"];
+
+/// A model whose changes are recorded as revisions.
+pub trait Tracked: Serialize {
+    const ENTITY: RevisionEntity;
+
+    fn id(&self) -> i32;
+}
+
+impl Tracked for book::Model {
+    const ENTITY: RevisionEntity = RevisionEntity::Book;
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl Tracked for author::Model {
+    const ENTITY: RevisionEntity = RevisionEntity::Author;
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+/// An author with their aliases, so alias changes are recorded and reverted
+/// along with the author's own fields. Aliases are listed as `{ "name", "kind" }`
+/// and left out when there are none.
+#[derive(Serialize)]
+pub struct AuthorWithAliases {
+    #[serde(flatten)]
+    pub author: author::Model,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<Value>,
+}
+
+impl AuthorWithAliases {
+    /// `author` with those of `aliases` that are theirs.
+    pub fn new(author: &author::Model, aliases: &[author_alias::Model]) -> Self {
+        Self {
+            author: author.to_owned(),
+            aliases: aliases
+                .iter()
+                .filter(|a| a.author_id == author.id)
+                .map(|a| json!({ "name": a.name, "kind": a.kind.to_value() }))
+                .collect(),
+        }
+    }
+
+    pub async fn load<C: ConnectionTrait>(db: &C, author: &author::Model) -> Result<Self, DbErr> {
+        let aliases = author_alias::Entity::find()
+            .filter(author_alias::Column::AuthorId.eq(author.id))
+            .order_by_asc(author_alias::Column::Id)
+            .all(db)
+            .await?;
+
+        Ok(Self::new(author, &aliases))
+    }
+}
+
+impl Tracked for AuthorWithAliases {
+    const ENTITY: RevisionEntity = RevisionEntity::Author;
+
+    fn id(&self) -> i32 {
+        self.author.id
+    }
+}
+
+/// The tracked fields of a model as a JSON object.
+pub fn snapshot<T: Tracked>(model: &T) -> Map<String, Value> {
+    match serde_json::to_value(model) {
+        Ok(Value::Object(mut fields)) => {
+            fields.retain(|k, _| !UNTRACKED.contains(&k.as_str()));
+            fields
+        }
+        _ => Map::new(),
+    }
+}
+
+/// Builds `{ field: { "old": .., "new": .. } }` for every field that differs.
+pub fn diff(before: &Map<String, Value>, after: &Map<String, Value>) -> Map<String, Value> {
+    let mut keys = before.keys().chain(after.keys()).collect::<Vec<_>>();
+    keys.sort();
+    keys.dedup();
+
+    keys.into_iter()
+        .filter_map(|k| {
+            let old = before.get(k).unwrap_or(&Value::Null);
+            let new = after.get(k).unwrap_or(&Value::Null);
+
+            (old != new).then(|| (k.to_owned(), json!({ "old": old, "new": new })))
+        })
+        .collect()
+}
+
+/// Records a revision for the change from `before` to `after`. Either may be
+/// `None` for a record that did not exist yet or no longer exists. Changes that
+/// touch no tracked field are not recorded.
+pub async fn record<C: ConnectionTrait, T: Tracked>(
+    db: &C,
+    user_id: i32,
+    action: RevisionAction,
+    before: Option<&T>,
+    after: Option<&T>,
+) -> Result<(), DbErr> {
+    let entity_id = match after.or(before) {
+        Some(m) => m.id(),
+        None => return Ok(()),
+    };
+
+    let changes = diff(
+        &before.map(snapshot).unwrap_or_default(),
+        &after.map(snapshot).unwrap_or_default(),
+    );
+
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    revision::Entity::insert(revision::ActiveModel {
+        user_id: Set(user_id),
+        entity: Set(T::ENTITY),
+        entity_id: Set(entity_id),
+        action: Set(action),
+        changes: Set(Value::Object(changes)),
+        ..Default::default()
+    })
+    .exec(db)
+    .await?;
+
+    Ok(())
+}
+
+/// The JSON of `current` with every field changed by `revision` set back to
+/// the value it had before that revision.
+pub fn reverted<T: Tracked>(current: &T, revision: &revision::Model) -> Value {
+    let mut json = serde_json::to_value(current).unwrap_or_default();
+
+    if let (Value::Object(fields), Value::Object(changes)) = (&mut json, &revision.changes) {
+        for (k, change) in changes {
+            if let Some(old) = change.get("old") {
+                fields.insert(k.to_owned(), old.to_owned());
+            }
+        }
+    }
+
+    json
+}
@@ -0,0 +1,55 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use std::{fmt, sync::Arc};
+
+use crate::AppConfig;
+
+pub mod local;
+pub mod s3;
+
+#[derive(Debug)]
+pub struct StorageError(pub String);
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Storage error: {}", self.0)
+    }
+}
+
+impl From<std::io::Error> for StorageError {
+    fn from(err: std::io::Error) -> Self {
+        StorageError(err.to_string())
+    }
+}
+
+/// A place to keep uploaded files, addressed by slash separated keys
+/// such as `covers/1/original`.
+#[rocket::async_trait]
+pub trait Storage: Send + Sync {
+    async fn put(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<(), StorageError>;
+
+    /// Returns `None` if nothing is stored under `key`.
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError>;
+
+    /// Deleting a missing key is not an error.
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+}
+
+pub fn from_config(config: &AppConfig) -> Arc<dyn Storage> {
+    match config.storage_driver.as_str() {
+        "local" => Arc::new(local::LocalStorage::new(&config.storage_path)),
+        "s3" => Arc::new(s3::S3Storage::new(
+            &config.s3_endpoint,
+            &config.s3_bucket,
+            &config.s3_region,
+            &config.s3_access_key,
+            &config.s3_secret_key,
+        )),
+        driver => panic!("Unknown BOOKSTORE_STORAGE_DRIVER: {}", driver),
+    }
+}
@@ -0,0 +1,119 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use sea_orm::{ConnectionTrait, DbErr};
+use url::Url;
+
+use crate::controllers::{error::FieldError, ErrorResponse};
+
+/// The length of a `string()` column.
+pub const MAX_STRING_LENGTH: usize = 255;
+
+/// A rule a string field must satisfy.
+pub enum Rule {
+    /// Must not be empty or whitespace only.
+    Required,
+    /// At most this many characters.
+    MaxLength(usize),
+    /// Empty, an absolute `http(s)` URL, or a path on this server.
+    Url,
+}
+
+impl Rule {
+    fn check(&self, value: &str) -> Result<(), String> {
+        match self {
+            Rule::Required if value.trim().is_empty() => Err("Must not be blank.".to_string()),
+            Rule::MaxLength(max) if value.chars().count() > *max => {
+                Err(format!("Must be at most {} characters long.", max))
+            }
+            Rule::Url if !value.is_empty() && !is_url(value) => {
+                Err("Must be an http(s) URL or a path starting with `/`.".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn is_url(value: &str) -> bool {
+    if value.starts_with('/') && !value.starts_with("//") {
+        return Url::parse("http://localhost").unwrap().join(value).is_ok();
+    }
+
+    Url::parse(value)
+        .map(|u| ["http", "https"].contains(&u.scheme()) && u.has_host())
+        .unwrap_or(false)
+}
+
+/// Collects the field errors of a request body.
+#[derive(Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    /// Checks `value` against `rules`, reporting only the first one it breaks.
+    pub fn field(&mut self, name: &str, value: &str, rules: &[Rule]) -> &mut Self {
+        if let Some(Err(message)) = rules.iter().map(|r| r.check(value)).find(Result::is_err) {
+            self.error(name, message);
+        }
+
+        self
+    }
+
+    pub fn error(&mut self, name: &str, message: impl Into<String>) -> &mut Self {
+        self.errors.push(FieldError {
+            field: name.to_string(),
+            message: message.into(),
+        });
+
+        self
+    }
+
+    fn finish(self) -> Result<(), ErrorResponse> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ErrorResponse::validation(self.errors))
+        }
+    }
+}
+
+/// A request body that is checked before it reaches the database.
+#[rocket::async_trait]
+pub trait Validate: Sync {
+    /// Checks the fields on their own.
+    fn rules(&self, v: &mut Validator);
+
+    /// Checks that the records the body refers to exist.
+    async fn references<C: ConnectionTrait>(
+        &self,
+        _db: &C,
+        _v: &mut Validator,
+    ) -> Result<(), DbErr> {
+        Ok(())
+    }
+
+    /// Reports the broken field rules alone as a 422, for records whose
+    /// references may be gone, such as deleted ones.
+    fn validate_rules(&self) -> Result<(), ErrorResponse> {
+        let mut v = Validator::default();
+
+        self.rules(&mut v);
+
+        v.finish()
+    }
+
+    /// Reports every broken rule at once as a 422.
+    async fn validate<C: ConnectionTrait>(&self, db: &C) -> Result<(), ErrorResponse> {
+        let mut v = Validator::default();
+
+        self.rules(&mut v);
+        self.references(db, &mut v).await?;
+
+        v.finish()
+    }
+}
@@ -0,0 +1,216 @@
+/**
+ * 📕 BookStore
+ *
+ * @author Afaan Bilal
+ * @link   https://afaan.dev
+ * @link   https://github.com/AfaanBilal/bookstore
+ */
+use quick_xml::{
+    escape::escape,
+    events::{BytesStart, Event},
+    Reader,
+};
+use std::fmt::Write;
+
+/// An XML element, read whole. Names are kept without their namespace prefix.
+#[derive(Debug, Default)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    pub text: String,
+    /// The line the element starts on, for error reports.
+    pub line: u64,
+}
+
+impl Element {
+    /// The first child named `name`.
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    /// The children named `name`.
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// The trimmed text of the first child named `name`, unless blank.
+    pub fn text_of(&self, name: &str) -> Option<&str> {
+        self.child(name)
+            .map(|c| c.text.trim())
+            .filter(|t| !t.is_empty())
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Renames this element and its descendants with `rename`, e.g. to read
+    /// short tags as their long equivalents.
+    pub fn rename(&mut self, rename: &impl Fn(&str) -> Option<&'static str>) {
+        if let Some(name) = rename(&self.name) {
+            self.name = name.to_string();
+        }
+
+        for child in &mut self.children {
+            child.rename(rename);
+        }
+    }
+}
+
+/// Reads a document into its root element.
+pub fn parse(body: &[u8]) -> Result<Element, String> {
+    let mut reader = Reader::from_reader(body);
+    reader.config_mut().trim_text(true);
+
+    let line = |offset: u64| {
+        body[..(offset as usize).min(body.len())]
+            .iter()
+            .filter(|b| **b == b'\n')
+            .count() as u64
+            + 1
+    };
+
+    let fail = |reader: &Reader<&[u8]>, e: &dyn std::fmt::Display| {
+        format!(
+            "The XML is malformed at line {}: {}.",
+            line(reader.error_position()),
+            e
+        )
+    };
+
+    let mut stack: Vec<Element> = vec![];
+    let mut root = None;
+
+    loop {
+        // Taken after the tag is read, as whitespace before it is skipped.
+        let event = reader.read_event();
+        let position = reader.buffer_position();
+
+        match event {
+            Ok(Event::Start(e)) => stack.push(element(&e, line(position))?),
+            Ok(Event::Empty(e)) => {
+                let element = element(&e, line(position))?;
+
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            }
+            Ok(Event::End(_)) => {
+                let element = stack.pop().ok_or("The XML has an unmatched end tag.")?;
+
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            }
+            Ok(Event::Text(t)) => {
+                if let Some(current) = stack.last_mut() {
+                    current
+                        .text
+                        .push_str(&t.unescape().map_err(|e| fail(&reader, &e))?);
+                }
+            }
+            Ok(Event::CData(t)) => {
+                if let Some(current) = stack.last_mut() {
+                    current.text.push_str(&String::from_utf8_lossy(&t));
+                }
+            }
+            Ok(Event::Eof) => match stack.last() {
+                Some(open) => {
+                    return Err(format!(
+                        "The XML ends before the <{}> on line {} is closed.",
+                        open.name, open.line
+                    ))
+                }
+                None => break,
+            },
+            Ok(_) => {}
+            Err(e) => return Err(fail(&reader, &e)),
+        }
+    }
+
+    root.ok_or_else(|| "The XML has no root element.".to_string())
+}
+
+fn element(start: &BytesStart, line: u64) -> Result<Element, String> {
+    let attributes = start
+        .attributes()
+        .map(|a| {
+            let a = a.map_err(|e| format!("The XML has a malformed attribute: {}.", e))?;
+            let value = a
+                .unescape_value()
+                .map_err(|e| format!("The XML has a malformed attribute: {}.", e))?;
+
+            Ok((
+                String::from_utf8_lossy(a.key.local_name().as_ref()).into_owned(),
+                value.into_owned(),
+            ))
+        })
+        .collect::<Result<_, String>>()?;
+
+    Ok(Element {
+        name: String::from_utf8_lossy(start.local_name().as_ref()).into_owned(),
+        attributes,
+        line,
+        ..Default::default()
+    })
+}
+
+/// Writes `<name>value</name>` on its own line at `depth`.
+pub fn leaf(out: &mut String, depth: usize, name: &str, value: &str) {
+    leaf_with(out, depth, name, &[], value);
+}
+
+/// Writes `<name attribute="...">value</name>` on its own line at `depth`.
+pub fn leaf_with(
+    out: &mut String,
+    depth: usize,
+    name: &str,
+    attributes: &[(&str, &str)],
+    value: &str,
+) {
+    let _ = write!(out, "{:indent$}<{}", "", name, indent = depth * 2);
+
+    for (key, value) in attributes {
+        let _ = write!(out, " {}=\"{}\"", key, escape(value));
+    }
+
+    let _ = writeln!(out, ">{}</{}>", escape(value), name);
+}
+
+/// Writes the start tag of `name` on its own line at `depth`.
+pub fn open(out: &mut String, depth: usize, tag: &str) {
+    let _ = writeln!(out, "{:indent$}<{}>", "", tag, indent = depth * 2);
+}
+
+/// Writes `<name attribute="...">` on its own line at `depth`.
+pub fn open_with(out: &mut String, depth: usize, name: &str, attributes: &[(&str, &str)]) {
+    let _ = write!(out, "{:indent$}<{}", "", name, indent = depth * 2);
+
+    for (key, value) in attributes {
+        let _ = write!(out, " {}=\"{}\"", key, escape(value));
+    }
+
+    let _ = writeln!(out, ">");
+}
+
+/// Writes the end tag of `name` on its own line at `depth`.
+pub fn close(out: &mut String, depth: usize, name: &str) {
+    let _ = writeln!(out, "{:indent$}</{}>", "", name, indent = depth * 2);
+}
+
+/// Writes `<name attribute="..."/>` on its own line at `depth`.
+pub fn empty(out: &mut String, depth: usize, name: &str, attributes: &[(&str, &str)]) {
+    let _ = write!(out, "{:indent$}<{}", "", name, indent = depth * 2);
+
+    for (key, value) in attributes {
+        let _ = write!(out, " {}=\"{}\"", key, escape(value));
+    }
+
+    let _ = writeln!(out, "/>");
+}